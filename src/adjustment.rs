@@ -0,0 +1,188 @@
+use serde::Serialize;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt,
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+use twilight_model::id::{
+    marker::{InteractionMarker, UserMarker},
+    Id,
+};
+
+use crate::{
+    audit,
+    store::{self, StoreError},
+};
+
+const REFUNDS_FILE_NAME: &str = "refunded_orders.json";
+
+/// How long a balance adjustment can wait for confirmation
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustmentKind {
+    Credit,
+    Debit,
+    Refund,
+}
+
+impl AdjustmentKind {
+    /// The balance after adjusting it by `amount` cents, `None` if it
+    /// doesn't fit in a balance
+    pub fn apply(self, balance: i32, amount: i32) -> Option<i32> {
+        match self {
+            Self::Credit | Self::Refund => balance.checked_add(amount),
+            Self::Debit => balance.checked_sub(amount),
+        }
+    }
+}
+
+impl fmt::Display for AdjustmentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Credit => "credit",
+            Self::Debit => "debit",
+            Self::Refund => "refund",
+        })
+    }
+}
+
+/// A change to a user's balance that a moderator asked for
+#[derive(Clone, Debug)]
+pub struct Adjustment {
+    pub kind: AdjustmentKind,
+    pub moderator_id: Id<UserMarker>,
+    pub user_id: Id<UserMarker>,
+    pub user_name: String,
+    /// The user's id on the website
+    pub account_id: u32,
+    /// In cents, always positive
+    pub amount: i32,
+    pub balance_before: i32,
+    pub balance_after: i32,
+    pub reason: String,
+    /// Only set for refunds
+    pub order_id: Option<String>,
+}
+
+/// Adjustments waiting for the moderator to confirm them, by the id of the
+/// interaction that asked for them
+#[derive(Debug, Default)]
+pub struct PendingAdjustments {
+    adjustments: Mutex<HashMap<Id<InteractionMarker>, (Instant, Adjustment)>>,
+}
+
+impl PendingAdjustments {
+    pub fn insert(&self, id: Id<InteractionMarker>, adjustment: Adjustment) {
+        let now = Instant::now();
+        let mut adjustments = self
+            .adjustments
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        adjustments
+            .retain(|_, (created_at, _)| now.duration_since(*created_at) < CONFIRMATION_TIMEOUT);
+        adjustments.insert(id, (now, adjustment));
+    }
+
+    /// The adjustment unless it has expired, it stays pending
+    pub fn get(&self, id: Id<InteractionMarker>) -> Option<Adjustment> {
+        let adjustments = self
+            .adjustments
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let (created_at, adjustment) = adjustments.get(&id)?;
+
+        (created_at.elapsed() < CONFIRMATION_TIMEOUT).then(|| adjustment.clone())
+    }
+
+    /// Remove the adjustment, returning it unless it has expired
+    pub fn take(&self, id: Id<InteractionMarker>) -> Option<Adjustment> {
+        let (created_at, adjustment) = self
+            .adjustments
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&id)?;
+
+        (created_at.elapsed() < CONFIRMATION_TIMEOUT).then_some(adjustment)
+    }
+}
+
+/// The orders that were refunded, so that none is refunded twice
+#[derive(Debug)]
+pub struct RefundedOrders {
+    path: PathBuf,
+    orders: Mutex<RefundedOrdersState>,
+}
+
+#[derive(Debug, Default)]
+struct RefundedOrdersState {
+    refunded: BTreeSet<String>,
+    /// Being refunded, until the API answers
+    reserved: HashSet<String>,
+}
+
+impl RefundedOrders {
+    /// Load the refunded orders, taking them from the audit log the first
+    /// time since that is where earlier versions kept them
+    pub fn load(data_dir: PathBuf) -> Result<Self, StoreError> {
+        let path = data_dir.join(REFUNDS_FILE_NAME);
+        let refunded = match store::load_json(&path)? {
+            Some(refunded) => refunded,
+            None => {
+                let refunded =
+                    audit::logged_refunds(&data_dir).map_err(|source| StoreError::Io {
+                        path: data_dir.clone(),
+                        source,
+                    })?;
+                let refunded = refunded.into_iter().collect();
+                store::save_json(&path, &refunded)?;
+                refunded
+            }
+        };
+
+        Ok(Self {
+            path,
+            orders: Mutex::new(RefundedOrdersState {
+                refunded,
+                reserved: HashSet::new(),
+            }),
+        })
+    }
+
+    /// Whether the order was refunded or is being refunded
+    pub fn contains(&self, order_id: &str) -> bool {
+        let orders = self.orders.lock().unwrap_or_else(PoisonError::into_inner);
+        orders.refunded.contains(order_id) || orders.reserved.contains(order_id)
+    }
+
+    /// Claim the order for a refund, `false` if it was refunded or is being
+    /// refunded already
+    pub fn reserve(&self, order_id: &str) -> bool {
+        let mut orders = self.orders.lock().unwrap_or_else(PoisonError::into_inner);
+        !orders.refunded.contains(order_id) && orders.reserved.insert(order_id.to_string())
+    }
+
+    /// Give the order back after the refund failed, so it can be refunded
+    pub fn release(&self, order_id: &str) {
+        self.orders
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .reserved
+            .remove(order_id);
+    }
+
+    /// Record the refund of the reserved order
+    ///
+    /// The order stays refunded even if the file can't be saved, since the
+    /// refund was made
+    pub fn finish(&self, order_id: &str) -> Result<(), StoreError> {
+        let mut orders = self.orders.lock().unwrap_or_else(PoisonError::into_inner);
+        orders.reserved.remove(order_id);
+        orders.refunded.insert(order_id.to_string());
+        store::save_json(&self.path, &orders.refunded)
+    }
+}
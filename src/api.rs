@@ -1,146 +1,368 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
-use std::env;
+use std::{fmt, time::Duration};
 use twilight_model::id::{marker::UserMarker, Id};
 
-const BASE_URL: &str = "https://api.alterasms.io";
+use crate::decode;
+
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    client: reqwest::Client,
+    base_url: String,
+    admin_token: String,
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ApiResponse {
+    #[serde(deserialize_with = "decode::flag")]
     success: bool,
-    message: String,
+    #[serde(default)]
+    pub message: String,
+    /// Null when there is nothing to return, error responses often leave it
+    /// out
+    #[serde(default)]
     pub resource: Value,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Number {
-    #[serde(rename = "Number")]
+    #[serde(rename = "Number", deserialize_with = "decode::string")]
     pub number: String,
     #[serde(rename = "Service")]
     pub service: String,
     #[serde(rename = "Country")]
     pub country: String,
-    #[serde(rename = "Price")]
+    #[serde(rename = "Price", deserialize_with = "decode::number")]
     pub price: i32,
-    #[serde(rename = "OrderID")]
+    /// What the provider charged for the number, in cents, orders placed
+    /// before it was recorded don't have it
+    #[serde(rename = "BaseCost", deserialize_with = "decode::optional_number", default)]
+    pub base_cost: Option<i32>,
+    #[serde(rename = "OrderID", deserialize_with = "decode::string")]
     pub order_id: String,
-    #[serde(rename = "Received")]
+    #[serde(rename = "Received", deserialize_with = "decode::flag", default)]
     pub received: bool,
+    #[serde(rename = "CreatedAt", default)]
+    pub created_at: Option<String>,
+    #[serde(rename = "ReceivedAt", default)]
+    pub received_at: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Number {
+    /// When the number was ordered as a unix timestamp
+    pub fn ordered_at(&self) -> Option<i64> {
+        parse_timestamp(self.created_at.as_deref()?)
+    }
+
+    /// When the number received its code as a unix timestamp
+    pub fn received_at(&self) -> Option<i64> {
+        parse_timestamp(self.received_at.as_deref()?)
+    }
+}
+
+/// What a new order is charged and what the provider charged for it, in cents
+#[derive(Debug, Clone, Copy)]
+pub struct OrderPrice {
+    pub price: i32,
+    pub base_cost: i32,
+}
+
+fn parse_timestamp(timestamp: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|timestamp| timestamp.timestamp())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum InvoiceStatus {
+    Completed,
+    Pending,
+    Expired,
+    Cancelled,
+    #[serde(other)]
+    Unknown,
+}
+
+impl fmt::Display for InvoiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Completed => "Completed",
+            Self::Pending => "Pending",
+            Self::Expired => "Expired",
+            Self::Cancelled => "Cancelled",
+            Self::Unknown => "Unknown",
+        })
+    }
+}
+
+/// A deposit the user started on the website
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Invoice {
+    #[serde(deserialize_with = "decode::string", default)]
+    pub id: String,
+    /// In USD
+    #[serde(deserialize_with = "decode::number", default)]
+    pub amount: f64,
+    pub status: InvoiceStatus,
+    /// How the user paid, such as a card or a cryptocurrency
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    /// Where the user pays the invoice, only sent while it is pending
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+impl Invoice {
+    /// When the invoice was created as a unix timestamp
+    pub fn created_at(&self) -> Option<i64> {
+        parse_timestamp(self.created_at.as_deref()?)
+    }
+}
+
+/// A user's role on the website, users without one get the least access
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(into = "u8")]
+pub enum Role {
+    #[default]
+    User,
+    Support,
+    Admin,
+}
+
+impl From<u8> for Role {
+    /// Unknown roles get the least access
+    fn from(role: u8) -> Self {
+        match role {
+            2 => Self::Admin,
+            1 => Self::Support,
+            _ => Self::User,
+        }
+    }
+}
+
+impl From<Role> for u8 {
+    fn from(role: Role) -> Self {
+        match role {
+            Role::User => 0,
+            Role::Support => 1,
+            Role::Admin => 2,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::User => "user",
+            Self::Support => "support",
+            Self::Admin => "admin",
+        })
+    }
+}
+
+/// The API sends the role's number, the config file uses its name
+impl<'de> Deserialize<'de> for Role {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RoleRepr {
+            Id(u8),
+            Name(String),
+        }
+
+        match RoleRepr::deserialize(deserializer)? {
+            RoleRepr::Id(role) => Ok(Self::from(role)),
+            RoleRepr::Name(name) => match name.trim().to_lowercase().as_str() {
+                "user" => Ok(Self::User),
+                "support" => Ok(Self::Support),
+                "admin" => Ok(Self::Admin),
+                // Some responses send the number as a string
+                id => id.parse::<u8>().map(Self::from).map_err(|_| {
+                    serde::de::Error::custom(format!(
+                        "unknown role `{}`, expected user, support or admin",
+                        name
+                    ))
+                }),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct User {
-    #[serde(rename = "Balance")]
+    #[serde(rename = "Balance", deserialize_with = "decode::number")]
     pub balance: i32,
-    #[serde(rename = "CreatedAt")]
+    #[serde(rename = "CreatedAt", default)]
     pub created_at: String,
-    #[serde(rename = "UpdatedAt")]
+    #[serde(rename = "UpdatedAt", default)]
     pub updated_at: String,
-    #[serde(rename = "DiscordID")]
+    #[serde(rename = "DiscordID", deserialize_with = "decode::string")]
     pub discord_id: String,
-    #[serde(rename = "Email")]
+    #[serde(rename = "Email", default)]
     pub email: String,
-    #[serde(rename = "ID")]
+    #[serde(rename = "ID", deserialize_with = "decode::number")]
     pub id: u32,
-    #[serde(rename = "Image")]
+    #[serde(rename = "Image", default)]
     pub image: String,
-    #[serde(rename = "Name")]
+    #[serde(rename = "Name", default)]
     pub name: String,
-    #[serde(rename = "Role")]
-    pub role: u8,
-    #[serde(rename = "Invoices")]
-    pub invoices: Vec<Value>,
-    #[serde(rename = "Numbers")]
+    #[serde(rename = "Role", default)]
+    pub role: Role,
+    #[serde(rename = "Invoices", default)]
+    pub invoices: Vec<Invoice>,
+    #[serde(rename = "Numbers", default)]
     pub numbers: Vec<Number>,
 }
 
-pub async fn get_user_data(user_id: Id<UserMarker>) -> Result<User, ApiResponse> {
-    let client = reqwest::Client::new();
-    let request = client
-        .get(format!("{}/user/discord/{}", BASE_URL, user_id))
-        .header(
-            reqwest::header::AUTHORIZATION,
-            format!(
-              "Bearer {}",
-              env::var("ADMIN_TOKEN").unwrap().parse::<String>().unwrap()
-            ),
-        )
-        .send()
-        .await
-        .unwrap()
-        .json::<ApiResponse>()
-        .await
-        .unwrap();
-
-    if request.success {
-        let user_info: User = serde_json::from_value(request.resource.clone()).unwrap();
-        Ok(user_info)
-    } else {
-        Err(request)
-    }
-}
-
-pub async fn post_user_number(
-    number: &str,
-    service: &str,
-    country: &str,
-    price: i32,
-    order_id: &str,
-    user_id: u32,
-) -> Result<(), ApiResponse> {
-    let client = reqwest::Client::new();
-
-    let request = client
-        .post(format!("{}/user/number", BASE_URL))
-        .header(
-            reqwest::header::AUTHORIZATION,
-            format!(
-              "Bearer {}",
-              env::var("ADMIN_TOKEN").unwrap().parse::<String>().unwrap()),
+/// Why a request to the backend failed
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    /// The backend couldn't be reached or the response couldn't be read
+    #[error("request to the backend failed: {0}")]
+    Request(#[from] reqwest::Error),
+    /// The backend answered with something other than what was expected
+    #[error("invalid backend response: {0}")]
+    Decode(#[from] serde_json::Error),
+    /// The backend refused the request, such as for an unknown user
+    #[error("the backend refused the request: {}", .0.message)]
+    Rejected(ApiResponse),
+}
+
+impl ApiClient {
+    pub fn new(base_url: String, admin_token: String, timeout: Duration) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap();
+        Self {
+            client,
+            base_url,
+            admin_token,
+        }
+    }
+
+    /// Send the request with the admin token, returning the resource of a
+    /// successful response
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Value, ApiError> {
+        let body = request
+            .header(
+                reqwest::header::AUTHORIZATION,
+                format!("Bearer {}", self.admin_token),
+            )
+            .send()
+            .await?
+            .bytes()
+            .await?;
+        let response: ApiResponse = serde_json::from_slice(&body)?;
+
+        if response.success {
+            Ok(response.resource)
+        } else {
+            Err(ApiError::Rejected(response))
+        }
+    }
+
+    pub async fn get_user_data(&self, user_id: Id<UserMarker>) -> Result<User, ApiError> {
+        let resource = self
+            .send(
+                self.client
+                    .get(format!("{}/user/discord/{}", self.base_url, user_id)),
+            )
+            .await?;
+
+        Ok(serde_json::from_value(resource)?)
+    }
+
+    pub async fn post_user_number(
+        &self,
+        number: &str,
+        service: &str,
+        country: &str,
+        price: OrderPrice,
+        order_id: &str,
+        user_id: u32,
+    ) -> Result<(), ApiError> {
+        self.send(
+            self.client
+                .post(format!("{}/user/number", self.base_url))
+                .json(&serde_json::json!({
+                    "number": number,
+                    "service": service,
+                    "country": country,
+                    "price": price.price,
+                    "base_cost": price.base_cost,
+                    "order_id": order_id,
+                    "user_id": user_id,
+                })),
         )
-        .json(&serde_json::json!({
-            "number": number,
-            "service": service,
-            "country": country,
-            "price": price,
-            "order_id": order_id,
-            "user_id": user_id,
-        }))
-        .send()
-        .await
-        .unwrap()
-        .json::<ApiResponse>()
-        .await
-        .unwrap();
-
-    if request.success {
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn mark_number_received(
+        &self,
+        number: String,
+        discord_id: String,
+    ) -> Result<(), ApiError> {
+        self.send(self.client.put(format!(
+            "{}/user/number/{}/received?discord_id={}",
+            self.base_url, number, discord_id
+        )))
+        .await?;
+
         Ok(())
-    } else {
-        Err(request)
     }
-}
 
-pub async fn mark_number_received(number: String, discord_id: String) -> Result<(), ApiResponse> {
-    let client = reqwest::Client::new();
+    /// Add `amount` cents to the user's balance, or take them if it's negative
+    pub async fn adjust_balance(
+        &self,
+        user_id: u32,
+        amount: i32,
+        reason: &str,
+    ) -> Result<(), ApiError> {
+        self.send(
+            self.client
+                .post(format!("{}/user/{}/balance", self.base_url, user_id))
+                .json(&serde_json::json!({
+                    "amount": amount,
+                    "reason": reason,
+                })),
+        )
+        .await?;
+
+        Ok(())
+    }
 
-    let request = client
-        .put(format!("{}/user/number/{}/received?discord_id={}", BASE_URL, number, discord_id))
-        .header(
-            reqwest::header::AUTHORIZATION,
-            format!(
-              "Bearer {}",
-              env::var("ADMIN_TOKEN").unwrap().parse::<String>().unwrap()),
+    /// Give the user back what they were charged for the order
+    pub async fn refund_order(&self, order_id: &str, reason: &str) -> Result<(), ApiError> {
+        self.send(
+            self.client
+                .post(format!("{}/user/number/{}/refund", self.base_url, order_id))
+                .json(&serde_json::json!({
+                    "reason": reason,
+                })),
         )
-        .send()
-        .await
-        .unwrap()
-        .json::<ApiResponse>()
-        .await
-        .unwrap();
-
-    if request.success {
+        .await?;
+
         Ok(())
-    } else {
-        Err(request)
+    }
+
+    /// Start a deposit of `amount` USD for the user
+    pub async fn create_invoice(&self, user_id: u32, amount: f64) -> Result<Invoice, ApiError> {
+        let resource = self
+            .send(
+                self.client
+                    .post(format!("{}/user/{}/invoice", self.base_url, user_id))
+                    .json(&serde_json::json!({
+                        "amount": amount,
+                    })),
+            )
+            .await?;
+
+        Ok(serde_json::from_value(resource)?)
     }
 }
@@ -0,0 +1,259 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::{fs, io, path::Path};
+use twilight_model::{
+    id::{marker::UserMarker, Id},
+    user::User,
+};
+use twilight_util::builder::embed::EmbedFieldBuilder;
+
+use crate::{
+    adjustment::AdjustmentKind,
+    blacklist::{Category, RuleKind},
+    policy::ServiceAction,
+    store,
+    templates::Tone,
+    Context,
+};
+
+const FILE_NAME: &str = "audit.log";
+
+/// Something worth keeping a record of, mostly administrative changes
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    BlacklistRuleAdded {
+        pattern: String,
+        kind: RuleKind,
+        category: Category,
+    },
+    BlacklistRuleRemoved {
+        pattern: String,
+        kind: RuleKind,
+        category: Category,
+    },
+    AllowlistAdded {
+        service: String,
+    },
+    AllowlistRemoved {
+        service: String,
+    },
+    BlockedServiceAttempted {
+        service: String,
+        action: ServiceAction,
+        pattern: String,
+        category: Category,
+    },
+    UserBanned {
+        user_id: Id<UserMarker>,
+        reason: String,
+        /// `None` for bans that don't expire
+        expires_at: Option<i64>,
+    },
+    UserUnbanned {
+        user_id: Id<UserMarker>,
+        reason: String,
+    },
+    MaintenanceChanged {
+        enabled: bool,
+        message: Option<String>,
+    },
+    CommandToggled {
+        command: String,
+        enabled: bool,
+    },
+    BalanceAdjusted {
+        kind: AdjustmentKind,
+        user_id: Id<UserMarker>,
+        /// In cents
+        amount: i32,
+        balance_before: i32,
+        balance_after: i32,
+        reason: String,
+        order_id: Option<String>,
+    },
+}
+
+impl AuditEvent {
+    fn title(&self) -> &'static str {
+        match self {
+            Self::BlacklistRuleAdded { .. } => "Blacklist Rule Added",
+            Self::BlacklistRuleRemoved { .. } => "Blacklist Rule Removed",
+            Self::AllowlistAdded { .. } => "Service Allowlisted",
+            Self::AllowlistRemoved { .. } => "Service Removed From Allowlist",
+            Self::BlockedServiceAttempted { .. } => "Blocked Service Attempted",
+            Self::UserBanned {
+                expires_at: None, ..
+            } => "User Banned",
+            Self::UserBanned { .. } => "User Suspended",
+            Self::UserUnbanned { .. } => "User Unbanned",
+            Self::MaintenanceChanged { enabled: true, .. } => "Maintenance Mode On",
+            Self::MaintenanceChanged { enabled: false, .. } => "Maintenance Mode Off",
+            Self::CommandToggled { enabled: true, .. } => "Command Enabled",
+            Self::CommandToggled { enabled: false, .. } => "Command Disabled",
+            Self::BalanceAdjusted {
+                kind: AdjustmentKind::Credit,
+                ..
+            } => "Balance Credited",
+            Self::BalanceAdjusted {
+                kind: AdjustmentKind::Debit,
+                ..
+            } => "Balance Debited",
+            Self::BalanceAdjusted {
+                kind: AdjustmentKind::Refund,
+                ..
+            } => "Order Refunded",
+        }
+    }
+
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Self::BlacklistRuleAdded {
+                pattern,
+                kind,
+                category,
+            }
+            | Self::BlacklistRuleRemoved {
+                pattern,
+                kind,
+                category,
+            } => vec![
+                ("Pattern:", format!("`{}`", pattern)),
+                ("Kind:", kind.to_string()),
+                ("Category:", category.to_string()),
+            ],
+            Self::AllowlistAdded { service } | Self::AllowlistRemoved { service } => {
+                vec![("Service:", service.clone())]
+            }
+            Self::BlockedServiceAttempted {
+                service,
+                action,
+                pattern,
+                category,
+            } => vec![
+                ("Service:", service.clone()),
+                ("Action:", action.to_string()),
+                ("Matched:", format!("`{}` ({})", pattern, category)),
+            ],
+            Self::UserBanned {
+                user_id,
+                reason,
+                expires_at,
+            } => vec![
+                ("User:", format!("<@{}> | `{}`", user_id, user_id)),
+                ("Reason:", reason.clone()),
+                (
+                    "Expires:",
+                    match expires_at {
+                        Some(expires_at) => format!("<t:{}:R>", expires_at),
+                        None => "Never".to_string(),
+                    },
+                ),
+            ],
+            Self::UserUnbanned { user_id, reason } => vec![
+                ("User:", format!("<@{}> | `{}`", user_id, user_id)),
+                ("Banned For:", reason.clone()),
+            ],
+            Self::MaintenanceChanged { enabled: _, message } => match message {
+                Some(message) => vec![("Message:", message.clone())],
+                None => vec![],
+            },
+            Self::CommandToggled {
+                command,
+                enabled: _,
+            } => vec![("Command:", format!("`/{}`", command))],
+            Self::BalanceAdjusted {
+                kind: _,
+                user_id,
+                amount,
+                balance_before,
+                balance_after,
+                reason,
+                order_id,
+            } => {
+                let mut fields = vec![
+                    ("User:", format!("<@{}> | `{}`", user_id, user_id)),
+                    ("Amount:", format!("`${:.2} USD`", *amount as f32 / 100.00)),
+                    (
+                        "Balance:",
+                        format!(
+                            "`${:.2}` → `${:.2} USD`",
+                            *balance_before as f32 / 100.00,
+                            *balance_after as f32 / 100.00
+                        ),
+                    ),
+                    ("Reason:", reason.clone()),
+                ];
+                if let Some(order_id) = order_id {
+                    fields.push(("Order:", format!("`{}`", order_id)));
+                }
+                fields
+            }
+        }
+    }
+}
+
+/// An audit log line
+#[derive(Serialize, Debug)]
+struct AuditRecord<'a> {
+    timestamp: i64,
+    /// `None` for changes the bot made on its own
+    actor_id: Option<Id<UserMarker>>,
+    actor_name: Option<&'a str>,
+    #[serde(flatten)]
+    event: &'a AuditEvent,
+}
+
+/// The orders the audit log in `data_dir` has refunds of, which is how
+/// refunds were told apart before they were kept in their own file
+pub(crate) fn logged_refunds(data_dir: &Path) -> Result<Vec<String>, io::Error> {
+    let log = match fs::read_to_string(data_dir.join(FILE_NAME)) {
+        Ok(log) => log,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+
+    Ok(log
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|record| record["event"] == "balance_adjusted" && record["kind"] == "refund")
+        .filter_map(|record| record["order_id"].as_str().map(str::to_string))
+        .collect())
+}
+
+impl Context {
+    /// Append the event to the audit log and post it to the log channel
+    pub async fn audit(&self, actor: Option<&User>, event: AuditEvent) {
+        let record = AuditRecord {
+            timestamp: chrono::Utc::now().timestamp(),
+            actor_id: actor.map(|user| user.id),
+            actor_name: actor.map(|user| user.name.as_str()),
+            event: &event,
+        };
+
+        let path = self.config().storage.data_dir.join(FILE_NAME);
+        match serde_json::to_string(&record) {
+            Ok(line) => {
+                if let Err(err) = store::append_line(&path, &line) {
+                    tracing::error!("Failed to write audit log: {}", err);
+                }
+            }
+            Err(err) => tracing::error!("Failed to serialize audit event: {}", err),
+        }
+
+        let mut embed = self
+            .embed(Tone::Success, event.title())
+            .description(match actor {
+                Some(user) => format!("By **@{}** | `{}`", user.name, user.id),
+                None => "By the bot".to_string(),
+            });
+        for (name, value) in event.fields() {
+            embed = embed.field(EmbedFieldBuilder::new(name, value).inline());
+        }
+
+        match embed.validate() {
+            Ok(embed) => self.send_log(embed.build()).await,
+            Err(err) => tracing::error!("Invalid audit embed: {}", err),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::{PoisonError, RwLockReadGuard, RwLockWriteGuard},
+};
+use twilight_model::{
+    channel::message::Embed,
+    id::{marker::UserMarker, Id},
+};
+use twilight_util::builder::embed::EmbedFieldBuilder;
+
+use crate::{
+    store::{self, StoreError},
+    Context,
+};
+
+const FILE_NAME: &str = "bans.json";
+
+/// A user who may not use the bot, permanently or until `expires_at`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Ban {
+    pub user_id: Id<UserMarker>,
+    pub reason: String,
+    pub moderator_id: Id<UserMarker>,
+    pub moderator_name: String,
+    /// Unix timestamp
+    pub created_at: i64,
+    /// Unix timestamp, `None` for bans that don't expire
+    pub expires_at: Option<i64>,
+}
+
+impl Ban {
+    pub fn is_active(&self, now: i64) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
+
+/// The contents of the bans data file
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct BanFile {
+    bans: Vec<Ban>,
+}
+
+#[derive(Debug)]
+pub struct BanList {
+    path: PathBuf,
+    file: BanFile,
+}
+
+impl BanList {
+    pub fn load(data_dir: PathBuf) -> Result<Self, StoreError> {
+        let path = data_dir.join(FILE_NAME);
+        let file = store::load_json(&path)?.unwrap_or_default();
+
+        Ok(Self { path, file })
+    }
+
+    /// The user's ban, unless it has expired
+    pub fn active(&self, user_id: Id<UserMarker>) -> Option<&Ban> {
+        let now = chrono::Utc::now().timestamp();
+        self.file
+            .bans
+            .iter()
+            .find(|ban| ban.user_id == user_id && ban.is_active(now))
+    }
+
+    /// Ban the user, replacing their previous ban, which is returned
+    pub fn ban(&mut self, ban: Ban) -> Result<Option<Ban>, StoreError> {
+        let previous_bans = self.file.bans.clone();
+
+        let previous = self.take(ban.user_id);
+        self.file.bans.push(ban);
+        self.save_or_rollback(previous_bans)?;
+
+        Ok(previous)
+    }
+
+    /// Lift the user's ban, returning it if they were banned
+    pub fn unban(&mut self, user_id: Id<UserMarker>) -> Result<Option<Ban>, StoreError> {
+        let previous_bans = self.file.bans.clone();
+
+        let Some(previous) = self.take(user_id) else {
+            return Ok(None);
+        };
+        self.save_or_rollback(previous_bans)?;
+
+        Ok(Some(previous))
+    }
+
+    /// Remove the user's active ban, dropping every expired one on the way
+    fn take(&mut self, user_id: Id<UserMarker>) -> Option<Ban> {
+        let now = chrono::Utc::now().timestamp();
+        self.file.bans.retain(|ban| ban.is_active(now));
+
+        let i = self.file.bans.iter().position(|ban| ban.user_id == user_id)?;
+        Some(self.file.bans.remove(i))
+    }
+
+    /// Save the bans, restoring the previous ones in memory if that fails so
+    /// they don't differ from the file
+    fn save_or_rollback(&mut self, previous_bans: Vec<Ban>) -> Result<(), StoreError> {
+        store::save_json(&self.path, &self.file).inspect_err(|_| self.file.bans = previous_bans)
+    }
+}
+
+impl Context {
+    pub fn bans(&self) -> RwLockReadGuard<'_, BanList> {
+        self.bans.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn bans_mut(&self) -> RwLockWriteGuard<'_, BanList> {
+        self.bans.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The notice shown to a banned user, both when they are banned and when
+    /// they try to use the bot
+    pub fn ban_notice_embed(
+        &self,
+        locale: Option<&str>,
+        ban: &Ban,
+    ) -> Result<Embed, anyhow::Error> {
+        let notice = match ban.expires_at {
+            Some(expires_at) => {
+                self.template_embed(locale, "suspended", &[("expires_at", &expires_at)])
+            }
+            None => self.template_embed(locale, "banned", &[]),
+        };
+        let reason_label = self.config().templates.label(locale, "Reason:");
+
+        Ok(notice
+            .field(EmbedFieldBuilder::new(reason_label, &ban.reason))
+            .validate()?
+            .build())
+    }
+
+    /// Send the ban notice to the user in their DMs, which fails if they
+    /// don't accept DMs from the bot
+    ///
+    /// The notice is in the language the user picked in `/settings`, English
+    /// otherwise since the locale of their client isn't known outside of their
+    /// interactions
+    pub async fn notify_banned_user(&self, ban: &Ban) -> Result<(), anyhow::Error> {
+        let language = self.preferences().get(ban.user_id).language;
+        let embeds = [self.ban_notice_embed(language.as_deref(), ban)?];
+        let channel = self
+            .bot
+            .http
+            .create_private_channel(ban.user_id)
+            .await?
+            .model()
+            .await?;
+
+        self.bot
+            .http
+            .create_message(channel.id)
+            .embeds(&embeds)?
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Parse a duration such as `30m`, `12h` or `1d12h` into seconds, `None` if
+/// it's invalid, zero or too long to count
+pub fn parse_duration(input: &str) -> Option<i64> {
+    let mut total: i64 = 0;
+    let mut number = String::new();
+
+    for c in input.trim().to_lowercase().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return None,
+        };
+        let amount: i64 = number.parse().ok()?;
+        total = total.checked_add(amount.checked_mul(unit)?)?;
+        number.clear();
+    }
+
+    (number.is_empty() && total > 0).then_some(total)
+}
@@ -0,0 +1,35 @@
+//! Run the mock backend, for pointing the bot's `api.base_url` at
+//!
+//! `cargo run --features mock-backend --bin mock_backend -- [address]
+//! [users.json]`, where the users file is a list of
+//! `{ "discord_id": "...", "balance": 1000, "role": "admin" }`. Requests are
+//! authorized with `ADMIN_TOKEN`, like the real backend.
+
+use altera_client::mock_backend::{MockBackend, SeedUser};
+use std::{env, fs, net::SocketAddr, sync::Arc};
+
+const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    tracing_subscriber::fmt().pretty().init();
+
+    let mut args = env::args().skip(1);
+    let addr: SocketAddr = args.next().as_deref().unwrap_or(DEFAULT_ADDR).parse()?;
+    let admin_token = env::var("ADMIN_TOKEN").unwrap_or_default();
+
+    let backend = Arc::new(MockBackend::new(admin_token));
+    if let Some(path) = args.next() {
+        let users: Vec<SeedUser> = serde_json::from_str(&fs::read_to_string(path)?)?;
+        for user in users {
+            backend.add_user(user.discord_id, user.role, user.balance);
+        }
+    }
+
+    let addr = backend.spawn(addr)?;
+    tracing::info!("Mock backend listening on http://{}", addr);
+
+    tokio::signal::ctrl_c().await?;
+
+    Ok(())
+}
@@ -0,0 +1,458 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::{PoisonError, RwLockReadGuard, RwLockWriteGuard},
+};
+use twilight_interactions::command::{CommandOption, CreateOption};
+
+use crate::{
+    store::{self, StoreError},
+    Context,
+};
+
+const FILE_NAME: &str = "blacklist.json";
+
+#[derive(Debug, thiserror::Error)]
+pub enum BlacklistError {
+    #[error(transparent)]
+    Store(#[from] StoreError),
+    #[error("`{pattern}` is not a valid regex: {source}")]
+    Regex {
+        pattern: String,
+        source: regex::Error,
+    },
+    #[error("a {kind} rule for `{pattern}` already exists")]
+    Duplicate { pattern: String, kind: RuleKind },
+}
+
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, CommandOption, CreateOption,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    #[option(name = "banking", value = "banking")]
+    Banking,
+    #[option(name = "crypto", value = "crypto")]
+    Crypto,
+    #[option(name = "payments", value = "payments")]
+    Payments,
+    #[option(name = "trading", value = "trading")]
+    Trading,
+    #[option(name = "other", value = "other")]
+    Other,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Banking => "banking",
+            Self::Crypto => "crypto",
+            Self::Payments => "payments",
+            Self::Trading => "trading",
+            Self::Other => "other",
+        })
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, CommandOption, CreateOption,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleKind {
+    /// The service name equals the pattern, ignoring case
+    #[option(name = "exact", value = "exact")]
+    Exact,
+    /// The service name contains the pattern, ignoring case
+    #[option(name = "substring", value = "substring")]
+    Substring,
+    /// The service name matches the pattern as a case insensitive regex
+    #[option(name = "regex", value = "regex")]
+    Regex,
+}
+
+impl fmt::Display for RuleKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Exact => "exact",
+            Self::Substring => "substring",
+            Self::Regex => "regex",
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BlacklistRule {
+    pub pattern: String,
+    pub kind: RuleKind,
+    pub category: Category,
+}
+
+/// The contents of the blacklist data file
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct BlacklistFile {
+    rules: Vec<BlacklistRule>,
+    /// Services that are never blocked, to override rules that are too broad
+    allowlist: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Blacklist {
+    path: PathBuf,
+    file: BlacklistFile,
+    /// The compiled regex of every rule, in the same order as the rules
+    matchers: Vec<Regex>,
+}
+
+impl Blacklist {
+    /// Load the blacklist from the data directory, creating it with the
+    /// default rules if it doesn't exist
+    pub fn load(data_dir: PathBuf) -> Result<Self, BlacklistError> {
+        let path = data_dir.join(FILE_NAME);
+
+        let (file, created) = match store::load_json(&path)? {
+            Some(file) => (file, false),
+            None => (BlacklistFile::default_rules(), true),
+        };
+
+        let matchers = file
+            .rules
+            .iter()
+            .map(compile)
+            .collect::<Result<_, _>>()?;
+        let blacklist = Self {
+            path,
+            file,
+            matchers,
+        };
+
+        if created {
+            blacklist.save()?;
+            tracing::info!("Created {}", blacklist.path.display());
+        }
+
+        Ok(blacklist)
+    }
+
+    /// The rule blocking the service, unless the service is allowlisted
+    pub fn check(&self, service: &str) -> Option<&BlacklistRule> {
+        let service = service.trim();
+        if self.is_allowlisted(service) {
+            return None;
+        }
+
+        self.matchers
+            .iter()
+            .position(|matcher| matcher.is_match(service))
+            .map(|i| &self.file.rules[i])
+    }
+
+    pub fn is_blacklisted(&self, service: &str) -> bool {
+        self.check(service).is_some()
+    }
+
+    pub fn is_allowlisted(&self, service: &str) -> bool {
+        self.file
+            .allowlist
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(service))
+    }
+
+    pub fn rules(&self) -> &[BlacklistRule] {
+        &self.file.rules
+    }
+
+    pub fn allowlist(&self) -> &[String] {
+        &self.file.allowlist
+    }
+
+    pub fn add_rule(&mut self, rule: BlacklistRule) -> Result<(), BlacklistError> {
+        if self
+            .file
+            .rules
+            .iter()
+            .any(|r| r.kind == rule.kind && r.pattern.eq_ignore_ascii_case(&rule.pattern))
+        {
+            return Err(BlacklistError::Duplicate {
+                pattern: rule.pattern,
+                kind: rule.kind,
+            });
+        }
+
+        self.matchers.push(compile(&rule)?);
+        self.file.rules.push(rule);
+        self.save_or_rollback(|blacklist| {
+            blacklist.file.rules.pop();
+            blacklist.matchers.pop();
+        })
+    }
+
+    /// Remove every rule with the pattern, returning the removed rules
+    pub fn remove_rule(&mut self, pattern: &str) -> Result<Vec<BlacklistRule>, BlacklistError> {
+        let previous = (self.file.rules.clone(), self.matchers.clone());
+
+        let mut removed = vec![];
+        let mut i = 0;
+        while i < self.file.rules.len() {
+            if self.file.rules[i].pattern.eq_ignore_ascii_case(pattern) {
+                removed.push(self.file.rules.remove(i));
+                self.matchers.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+
+        if removed.is_empty() {
+            return Ok(removed);
+        }
+        self.save_or_rollback(|blacklist| {
+            (blacklist.file.rules, blacklist.matchers) = previous;
+        })?;
+        Ok(removed)
+    }
+
+    /// Returns whether the service wasn't allowlisted already
+    pub fn allow(&mut self, service: &str) -> Result<bool, BlacklistError> {
+        if self.is_allowlisted(service) {
+            return Ok(false);
+        }
+
+        self.file.allowlist.push(service.to_string());
+        self.save_or_rollback(|blacklist| {
+            blacklist.file.allowlist.pop();
+        })?;
+        Ok(true)
+    }
+
+    /// Returns whether the service was allowlisted
+    pub fn disallow(&mut self, service: &str) -> Result<bool, BlacklistError> {
+        let Some(i) = self
+            .file
+            .allowlist
+            .iter()
+            .position(|allowed| allowed.eq_ignore_ascii_case(service))
+        else {
+            return Ok(false);
+        };
+
+        let removed = self.file.allowlist.remove(i);
+        self.save_or_rollback(|blacklist| {
+            blacklist.file.allowlist.insert(i, removed);
+        })?;
+        Ok(true)
+    }
+
+    fn save(&self) -> Result<(), BlacklistError> {
+        Ok(store::save_json(&self.path, &self.file)?)
+    }
+
+    /// Save the blacklist, undoing the change in memory if that fails so it
+    /// doesn't differ from the file
+    fn save_or_rollback(
+        &mut self,
+        rollback: impl FnOnce(&mut Self),
+    ) -> Result<(), BlacklistError> {
+        self.save().inspect_err(|_| rollback(self))
+    }
+}
+
+impl Context {
+    pub fn blacklist(&self) -> RwLockReadGuard<'_, Blacklist> {
+        self.blacklist.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn blacklist_mut(&self) -> RwLockWriteGuard<'_, Blacklist> {
+        self.blacklist.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn compile(rule: &BlacklistRule) -> Result<Regex, BlacklistError> {
+    let pattern = match rule.kind {
+        RuleKind::Exact => format!("^{}$", regex::escape(&rule.pattern)),
+        RuleKind::Substring => regex::escape(&rule.pattern),
+        RuleKind::Regex => rule.pattern.clone(),
+    };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|source| BlacklistError::Regex {
+            pattern: rule.pattern.clone(),
+            source,
+        })
+}
+
+impl BlacklistFile {
+    /// The rules the bot shipped with before the blacklist was configurable,
+    /// legitimate services the substring rules block go in the allowlist
+    fn default_rules() -> Self {
+        use Category::*;
+
+        let exact = [
+            ("5ka.ru", Other),
+            ("AdvCash", Payments),
+            ("Afterpay", Payments),
+            ("Airtm", Payments),
+            ("Alipay", Payments),
+            ("beCHARGE", Payments),
+            ("Blackcatcard", Banking),
+            ("BluePay", Payments),
+            ("CashApp", Payments),
+            ("CentroBill", Payments),
+            ("Clearpay", Payments),
+            ("CoinsBaron", Crypto),
+            ("Coinbase", Crypto),
+            ("CoinSwitch", Crypto),
+            ("CoinSpot", Crypto),
+            ("Coinstash", Crypto),
+            ("CornerCard", Banking),
+            ("cPay", Payments),
+            ("Easy Pay", Payments),
+            ("Entropay", Payments),
+            ("ePayments", Payments),
+            ("GCash", Payments),
+            ("Holvi", Banking),
+            ("iCard", Banking),
+            ("iPayYou", Payments),
+            ("Joompay", Payments),
+            ("Leupay", Payments),
+            ("LocalBitcoins", Crypto),
+            ("Mezu", Payments),
+            ("MuchBetter", Payments),
+            ("MyBoost", Payments),
+            ("Neteller", Payments),
+            ("OKCoin", Crypto),
+            ("Papara", Payments),
+            ("PayGo", Payments),
+            ("Payoneer", Payments),
+            ("Paypal", Payments),
+            ("Paysafe", Payments),
+            ("PaySay", Payments),
+            ("PaySend", Payments),
+            ("Paysera", Payments),
+            ("Paytm", Payments),
+            ("QIWIWallet", Payments),
+            ("SimplexCC", Crypto),
+            ("Skrill", Payments),
+            ("Steemit", Crypto),
+            ("Stripe", Payments),
+            ("TransferWise", Payments),
+            ("Venmo", Payments),
+            ("Verse", Payments),
+            ("Vimpay", Payments),
+            ("Walmart money card", Banking),
+            ("Webmoney", Payments),
+            ("xcoins", Crypto),
+            ("ZapZap", Payments),
+            ("zebpay", Crypto),
+            ("ZipCo", Payments),
+            ("ZipPay", Payments),
+            ("Abra", Crypto),
+            ("Akulaku", Payments),
+            ("ANZ", Banking),
+            ("Banq24", Banking),
+            ("banxa", Crypto),
+            ("BeemIt", Payments),
+            ("Billcom", Payments),
+            ("Bunq", Banking),
+            ("CapitalOne", Banking),
+            ("Cardyard", Banking),
+            ("CashAA", Crypto),
+            ("CashZine", Other),
+            ("CodaPayments", Payments),
+            ("Cogni Bank", Banking),
+            ("CreditKarma", Banking),
+            ("Dukascopy", Trading),
+            ("ecoPayz", Payments),
+            ("eToro", Trading),
+            ("EuroPYM", Payments),
+            ("ExpertOption", Trading),
+            ("FBS", Trading),
+            ("FreshForex", Trading),
+            ("FTX", Crypto),
+            ("Go2Bank", Banking),
+            ("GoFundMe", Payments),
+            ("GreenDot", Banking),
+            ("instaforex", Trading),
+            ("InstaGC", Payments),
+            ("Instarem", Payments),
+            ("IQOption", Trading),
+            ("Jerry", Other),
+            ("JuanCash", Payments),
+            ("KBZpay", Payments),
+            ("KVBPrime", Trading),
+            ("LydiaApp", Payments),
+            ("Monese", Banking),
+            ("Moneylion", Banking),
+            ("MoneyPak", Payments),
+            ("MoneyRawr", Payments),
+            ("Monzo", Banking),
+            ("Naver", Other),
+            ("NetBank", Banking),
+            ("Netease", Other),
+            ("NiftyLoans", Banking),
+            ("PayMaya", Payments),
+            ("Paymium", Crypto),
+            ("PayQin", Payments),
+            ("Phyre", Payments),
+            ("RazerPay", Payments),
+            ("Remitly", Payments),
+            ("Revolut", Banking),
+            ("Robinhood", Trading),
+            ("SafeCurrency", Trading),
+            ("sharemoney", Payments),
+            ("SwissBorg", Crypto),
+            ("SwitchHere", Other),
+            ("tala", Banking),
+            ("Tenx", Crypto),
+            ("The Change", Other),
+            ("Tikki", Payments),
+            ("ToTalk", Other),
+            ("TradingView", Trading),
+            ("TransferHome", Payments),
+            ("Trustcom", Other),
+            ("UBank", Banking),
+            ("Varo", Banking),
+            ("Vodi", Other),
+            ("Voyager", Crypto),
+            ("WestStein", Banking),
+            ("Wing", Payments),
+            ("Wirex", Crypto),
+            ("wittix", Banking),
+            ("Womply", Payments),
+            ("X-Bank", Banking),
+            ("Xoom", Payments),
+            ("Yodlee", Banking),
+            ("YouTrip", Payments),
+            ("Zelle", Payments),
+            ("Zest", Banking),
+            ("Indacoin", Crypto),
+            ("Coinomi", Crypto),
+            ("Coinjar", Crypto),
+        ];
+        let substring = [
+            ("coin", Crypto),
+            ("pay", Payments),
+            ("cash", Payments),
+            ("sell", Other),
+        ];
+
+        let rule = |kind| {
+            move |(pattern, category): (&str, Category)| BlacklistRule {
+                pattern: pattern.to_string(),
+                kind,
+                category,
+            }
+        };
+
+        Self {
+            rules: exact
+                .into_iter()
+                .map(rule(RuleKind::Exact))
+                .chain(substring.into_iter().map(rule(RuleKind::Substring)))
+                .collect(),
+            allowlist: vec![],
+        }
+    }
+}
@@ -0,0 +1,329 @@
+//! The subcommands of the binary, the ones other than `serve` only talk to
+//! the provider and the backend so they work without Discord
+
+use std::io::Write;
+
+use anyhow::anyhow;
+use sparkle_convenience::Bot;
+use twilight_gateway::EventTypeFlags;
+use twilight_model::{
+    gateway::Intents,
+    id::{marker::UserMarker, Id},
+};
+
+use crate::{
+    api::{ApiClient, ApiError, InvoiceStatus, User},
+    config::Config,
+    sms::{SMSResponseError, SmsClient},
+    Context,
+};
+
+pub const USAGE: &str = "\
+Usage: altera_client [command] [options]
+
+Commands:
+  serve                      Connect to Discord and handle commands (default)
+  register-commands          Create or update the slash commands and exit
+  balance                    Print the bot's balance with the SMS provider
+  lookup-user <discord_id>   Print the account of a Discord user
+  order-status <order_id>    Print the status of an order with the SMS provider
+  export-orders <discord_id>...
+                             Print the orders of the users as CSV
+  check-config               Validate the config and print a summary
+  help                       Print this message
+
+Options:
+  --prune-commands           With serve and register-commands, delete
+                             commands the bot doesn't have anymore
+  --json                     With lookup-user and order-status, print JSON";
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum CliError {
+    #[error("unknown command `{0}`")]
+    UnknownCommand(String),
+    #[error("`{0}` requires {1}")]
+    MissingArgument(&'static str, &'static str),
+    #[error("unexpected argument `{0}`")]
+    UnexpectedArgument(String),
+    #[error("`{0}` is not a Discord user id")]
+    InvalidUserId(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Serve {
+        prune_commands: bool,
+    },
+    RegisterCommands {
+        prune_commands: bool,
+    },
+    Balance,
+    LookupUser {
+        discord_id: Id<UserMarker>,
+        json: bool,
+    },
+    OrderStatus {
+        order_id: String,
+        json: bool,
+    },
+    ExportOrders {
+        discord_ids: Vec<Id<UserMarker>>,
+    },
+    CheckConfig,
+    Help,
+}
+
+impl Command {
+    /// Parse the arguments after the binary name, without a command the bot
+    /// is served like before there were commands
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        let mut prune_commands = false;
+        let mut json = false;
+        let mut positional = vec![];
+        for arg in args {
+            match arg.as_str() {
+                "--prune-commands" => prune_commands = true,
+                "--json" => json = true,
+                "-h" | "--help" => return Ok(Self::Help),
+                flag if flag.starts_with('-') => return Err(CliError::UnexpectedArgument(arg)),
+                _ => positional.push(arg),
+            }
+        }
+
+        let mut positional = positional.into_iter();
+        let command = match positional.next().as_deref() {
+            None | Some("serve") => Self::Serve { prune_commands },
+            Some("register-commands") => Self::RegisterCommands { prune_commands },
+            Some("balance") => Self::Balance,
+            Some("lookup-user") => Self::LookupUser {
+                discord_id: parse_user_id(positional.next().ok_or(CliError::MissingArgument(
+                    "lookup-user",
+                    "a Discord user id",
+                ))?)?,
+                json,
+            },
+            Some("order-status") => Self::OrderStatus {
+                order_id: positional
+                    .next()
+                    .ok_or(CliError::MissingArgument("order-status", "an order id"))?,
+                json,
+            },
+            Some("export-orders") => {
+                let discord_ids = positional
+                    .by_ref()
+                    .map(parse_user_id)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if discord_ids.is_empty() {
+                    return Err(CliError::MissingArgument(
+                        "export-orders",
+                        "at least one Discord user id",
+                    ));
+                }
+
+                Self::ExportOrders { discord_ids }
+            }
+            Some("check-config") => Self::CheckConfig,
+            Some("help") => Self::Help,
+            Some(command) => return Err(CliError::UnknownCommand(command.to_string())),
+        };
+
+        match positional.next() {
+            Some(arg) => Err(CliError::UnexpectedArgument(arg)),
+            None => Ok(command),
+        }
+    }
+}
+
+fn parse_user_id(arg: String) -> Result<Id<UserMarker>, CliError> {
+    arg.trim().parse().map_err(|_| CliError::InvalidUserId(arg))
+}
+
+/// Run the command, writing what it prints to `out`
+pub async fn run(
+    command: Command,
+    config: Config,
+    out: &mut impl Write,
+) -> Result<(), anyhow::Error> {
+    let sms = || {
+        SmsClient::new(
+            config.provider(),
+            &config.fake_provider,
+            config.timeouts.http,
+        )
+    };
+    let api = || {
+        ApiClient::new(
+            config.api.base_url.clone(),
+            config.secrets.admin_token.clone(),
+            config.timeouts.http,
+        )
+    };
+
+    match command {
+        Command::Serve { prune_commands } => crate::run(config, prune_commands).await?,
+        Command::RegisterCommands { prune_commands } => {
+            // The shards are never started, only the HTTP client is used
+            let (bot, _) = Bot::new(
+                config.secrets.discord_token.clone(),
+                Intents::empty(),
+                EventTypeFlags::empty(),
+            )
+            .await?;
+            let ctx = Context::new(bot, config)?;
+            ctx.create_commands(prune_commands).await?;
+            writeln!(out, "Registered the commands")?;
+        }
+        Command::Balance => {
+            let balance = sms().get_api_balance().await.map_err(sms_error)?;
+            writeln!(out, "${:.2}", balance)?;
+        }
+        Command::LookupUser { discord_id, json } => {
+            let user = api().get_user_data(discord_id).await.map_err(api_error)?;
+            if json {
+                writeln!(out, "{}", serde_json::to_string_pretty(&user)?)?;
+            } else {
+                write_user(out, &user)?;
+            }
+        }
+        Command::OrderStatus { order_id, json } => {
+            let sms_code = sms().get_sms_code(&order_id).await.map_err(sms_error)?;
+            if json {
+                writeln!(out, "{}", serde_json::to_string_pretty(&sms_code)?)?;
+            } else {
+                writeln!(out, "Order:      {}", order_id)?;
+                writeln!(
+                    out,
+                    "Status:     {} ({})",
+                    match sms_code.status {
+                        1 | 2 | 4 => "Waiting for a code",
+                        3 => "Received",
+                        _ => "Expired",
+                    },
+                    sms_code.status
+                )?;
+                writeln!(out, "Expires:    {}", format_timestamp(sms_code.expiration))?;
+                if let Some(sms) = sms_code.sms {
+                    writeln!(out, "Code:       {}", sms)?;
+                }
+                if let Some(full_sms) = sms_code.full_sms {
+                    writeln!(out, "Message:    {}", full_sms)?;
+                }
+            }
+        }
+        Command::ExportOrders { discord_ids } => {
+            let api = api();
+            writeln!(
+                out,
+                "discord_id,order_id,number,service,country,price,received,ordered_at,received_at"
+            )?;
+            for discord_id in discord_ids {
+                let user = api.get_user_data(discord_id).await.map_err(|err| {
+                    anyhow!(
+                        "Unable to get the orders of {}: {}",
+                        discord_id,
+                        api_error(err)
+                    )
+                })?;
+                for number in &user.numbers {
+                    writeln!(
+                        out,
+                        "{},{},{},{},{},{:.2},{},{},{}",
+                        user.discord_id,
+                        csv_field(&number.order_id),
+                        csv_field(&number.number),
+                        csv_field(&number.service),
+                        csv_field(&number.country),
+                        number.price as f64 / 100.0,
+                        number.received,
+                        number.created_at.as_deref().unwrap_or_default(),
+                        number.received_at.as_deref().unwrap_or_default(),
+                    )?;
+                }
+            }
+        }
+        Command::CheckConfig => {
+            // Loading the config validated it already
+            writeln!(out, "The config is valid")?;
+            writeln!(
+                out,
+                "Provider:   {} ({})",
+                config.provider().name,
+                config.provider().base_url
+            )?;
+            writeln!(out, "API:        {}", config.api.base_url)?;
+            writeln!(out, "Deployment: {:?}", config.discord.deployment)?;
+            writeln!(out, "Data:       {}", config.storage.data_dir.display())?;
+        }
+        Command::Help => writeln!(out, "{}", USAGE)?,
+    }
+
+    Ok(())
+}
+
+fn write_user(out: &mut impl Write, user: &User) -> Result<(), anyhow::Error> {
+    let received = user.numbers.iter().filter(|number| number.received).count();
+    let completed = user
+        .invoices
+        .iter()
+        .filter(|invoice| invoice.status == InvoiceStatus::Completed)
+        .count();
+    let pending = user
+        .invoices
+        .iter()
+        .filter(|invoice| invoice.status == InvoiceStatus::Pending)
+        .count();
+
+    writeln!(
+        out,
+        "Account:    {} ({}, {})",
+        user.id, user.name, user.email
+    )?;
+    writeln!(out, "Discord ID: {}", user.discord_id)?;
+    writeln!(out, "Role:       {}", user.role)?;
+    writeln!(out, "Balance:    ${:.2} USD", user.balance as f64 / 100.0)?;
+    writeln!(
+        out,
+        "Orders:     {} ({} received)",
+        user.numbers.len(),
+        received
+    )?;
+    writeln!(
+        out,
+        "Deposits:   {} completed, {} pending",
+        completed, pending
+    )?;
+    writeln!(out, "Created:    {}", user.created_at)?;
+
+    Ok(())
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0).map_or_else(
+        || timestamp.to_string(),
+        |time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+    )
+}
+
+/// Quote the field if it would break the row
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// The backend's message if it refused the request
+fn api_error(err: ApiError) -> anyhow::Error {
+    match err {
+        ApiError::Rejected(response) => anyhow!("{}", response.message),
+        err => err.into(),
+    }
+}
+
+fn sms_error(err: SMSResponseError) -> anyhow::Error {
+    match err.errors.first() {
+        Some(info) => anyhow!("{}", info.message),
+        None => anyhow!("The provider returned an error"),
+    }
+}
@@ -0,0 +1,883 @@
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    path::PathBuf,
+    sync::{Arc, PoisonError},
+    time::Duration,
+};
+use twilight_model::id::{
+    marker::{ChannelMarker, GuildMarker, RoleMarker},
+    Id,
+};
+
+use crate::{api::Role, currencies::Rates, logic, templates::Templates, Context};
+
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+/// The base URL of a provider answered by the fake provider
+pub const FAKE_PROVIDER_URL: &str = "fake://";
+/// The provider the bot used before providers were configurable, for configs
+/// that only set `API_KEY`
+pub const DEFAULT_PROVIDER_URL: &str = "....";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("failed to read config file {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("failed to parse config file {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("environment variable {var} is invalid: {reason}")]
+    Env { var: &'static str, reason: String },
+    #[error("invalid configuration:\n  - {}", .0.join("\n  - "))]
+    Invalid(Vec<String>),
+}
+
+/// The configuration as it is written in the TOML file, before validation
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RawConfig {
+    discord: RawDiscordConfig,
+    api: RawApiConfig,
+    providers: Vec<RawProviderConfig>,
+    pricing: RawPricingConfig,
+    colors: RawColorsConfig,
+    templates: RawTemplatesConfig,
+    storage: RawStorageConfig,
+    timeouts: RawTimeoutsConfig,
+    limits: LimitsConfig,
+    balance_monitor: BalanceMonitorConfig,
+    history: HistoryConfig,
+    deposits: DepositsConfig,
+    currencies: CurrenciesConfig,
+    service_matching: ServiceMatchingConfig,
+    features: FeaturesConfig,
+    fake_provider: FakeProviderConfig,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RawDiscordConfig {
+    token: String,
+    deployment: Deployment,
+    guilds: Vec<u64>,
+    log_channel: u64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct RawApiConfig {
+    base_url: String,
+    admin_token: String,
+}
+
+impl Default for RawApiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://api.alterasms.io".to_string(),
+            admin_token: String::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct RawProviderConfig {
+    name: String,
+    base_url: String,
+    #[serde(default)]
+    api_key: String,
+    #[serde(default = "default_true")]
+    enabled: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct RawPricingConfig {
+    multiplier: f32,
+}
+
+impl Default for RawPricingConfig {
+    fn default() -> Self {
+        Self { multiplier: 1.0 }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct RawColorsConfig {
+    success: String,
+    error: String,
+}
+
+impl Default for RawColorsConfig {
+    fn default() -> Self {
+        Self {
+            success: "#65C97A".to_string(),
+            error: "#E85041".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RawTemplatesConfig {
+    file: Option<PathBuf>,
+    locales: Option<PathBuf>,
+    footer: String,
+    timestamp: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct RawStorageConfig {
+    data_dir: PathBuf,
+}
+
+impl Default for RawStorageConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: PathBuf::from("data"),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct RawTimeoutsConfig {
+    http_secs: u64,
+}
+
+impl Default for RawTimeoutsConfig {
+    fn default() -> Self {
+        Self { http_secs: 30 }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Secrets and connection settings, these are only read once at startup
+#[derive(Debug, Clone, PartialEq)]
+pub struct Secrets {
+    pub discord_token: String,
+    pub admin_token: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderConfig {
+    pub name: String,
+    pub base_url: String,
+    pub api_key: String,
+    pub enabled: bool,
+}
+
+impl ProviderConfig {
+    /// Whether requests are answered in process by the fake provider
+    pub fn is_fake(&self) -> bool {
+        self.base_url.starts_with(FAKE_PROVIDER_URL)
+    }
+}
+
+/// Where the slash commands are registered
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Deployment {
+    /// Registered once for every guild the bot is in, for production
+    Global,
+    /// Registered in each of `discord.guilds`, for testing
+    #[default]
+    Guild,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiscordConfig {
+    pub deployment: Deployment,
+    pub guilds: Vec<Id<GuildMarker>>,
+    pub log_channel: Id<ChannelMarker>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PricingConfig {
+    pub multiplier: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ColorsConfig {
+    pub success: u32,
+    pub error: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiConfig {
+    /// Where the website's API is, such as the mock backend when testing
+    pub base_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageConfig {
+    /// Directory the bot keeps its data files in, such as the blacklist
+    pub data_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeoutsConfig {
+    pub http: Duration,
+}
+
+/// Per user and per guild usage limits, 0 disables a limit
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    pub orders_per_hour: u32,
+    pub guild_orders_per_hour: u32,
+    pub checksms_per_minute: u32,
+    pub guild_checksms_per_minute: u32,
+    /// Numbers a user can have waiting for a code at the same time
+    pub max_active_numbers: u32,
+    /// Users with these roles aren't limited
+    pub exempt_roles: Vec<Role>,
+    /// The most support can credit, debit or refund at once in USD
+    pub support_adjustment_cap: f32,
+    /// The most admins can credit, debit or refund at once in USD, 0 leaves
+    /// them uncapped up to the commands' own limit
+    pub admin_adjustment_cap: f32,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            orders_per_hour: 10,
+            guild_orders_per_hour: 100,
+            checksms_per_minute: 6,
+            guild_checksms_per_minute: 60,
+            max_active_numbers: 2,
+            exempt_roles: vec![Role::Support, Role::Admin],
+            support_adjustment_cap: 25.0,
+            admin_adjustment_cap: 1000.0,
+        }
+    }
+}
+
+impl LimitsConfig {
+    /// The most the role can adjust a balance by at once in cents, `None` if
+    /// there is no cap
+    pub fn adjustment_cap(&self, role: Role) -> Option<i32> {
+        let cap = match role {
+            Role::User => return Some(0),
+            Role::Support => self.support_adjustment_cap,
+            Role::Admin => self.admin_adjustment_cap,
+        };
+
+        (cap > 0.0).then(|| (cap * 100.0).round() as i32)
+    }
+}
+
+/// Watching the balance of the provider account orders are paid from
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct BalanceMonitorConfig {
+    pub enabled: bool,
+    pub interval_secs: u64,
+    /// Balances in USD to warn at, each is only warned about again once the
+    /// balance has gone back above it
+    pub warn_at: Vec<f32>,
+    /// Role pinged with the warnings
+    pub alert_role: Option<Id<RoleMarker>>,
+    /// Balance in USD below which `/getnumber` is paused, 0 disables it
+    pub pause_below: f32,
+}
+
+impl Default for BalanceMonitorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 300,
+            warn_at: vec![50.0, 20.0, 10.0],
+            alert_role: None,
+            pause_below: 0.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    /// How long after ordering `/history` shows the received SMS again, 0
+    /// disables it
+    pub sms_retention_hours: u32,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            sms_retention_hours: 24,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DepositsConfig {
+    /// In USD
+    pub min_amount: f64,
+    /// In USD
+    pub max_amount: f64,
+    /// How often pending deposits are checked for payment
+    pub poll_interval_secs: u64,
+    /// How long a deposit is checked for before it is given up on
+    pub expire_after_hours: u32,
+}
+
+impl Default for DepositsConfig {
+    fn default() -> Self {
+        Self {
+            min_amount: 1.0,
+            max_amount: 500.0,
+            poll_interval_secs: 30,
+            expire_after_hours: 24,
+        }
+    }
+}
+
+/// Showing prices in other currencies next to USD
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CurrenciesConfig {
+    /// The exchange rates users can pick a display currency from, none can
+    /// be picked without it
+    pub file: Option<PathBuf>,
+    /// How often the file is checked for changes
+    pub reload_interval_secs: u64,
+}
+
+impl Default for CurrenciesConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            reload_interval_secs: 300,
+        }
+    }
+}
+
+/// Finding the services users mean from what they typed
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServiceMatchingConfig {
+    /// Service names by what users type for them, such as `tg = "Telegram"`,
+    /// replacing the built-in aliases with the same name
+    pub aliases: HashMap<String, String>,
+    /// How similar out of 100 a service has to be to what was typed to be
+    /// suggested
+    pub min_score: u8,
+    /// The most services suggested at once
+    pub max_results: usize,
+}
+
+impl Default for ServiceMatchingConfig {
+    fn default() -> Self {
+        Self {
+            aliases: HashMap::new(),
+            min_score: 60,
+            max_results: 10,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeaturesConfig {
+    /// Whether order and sms events are posted to the log channel
+    pub log_channel_messages: bool,
+    /// Whether similar services are suggested when a service isn't found
+    pub service_suggestions: bool,
+}
+
+impl Default for FeaturesConfig {
+    fn default() -> Self {
+        Self {
+            log_channel_messages: true,
+            service_suggestions: true,
+        }
+    }
+}
+
+/// A provider operation, for scripting the fake provider
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FakeOperation {
+    Purchase,
+    CheckSms,
+    CountryPrices,
+    ServiceList,
+    Balance,
+}
+
+/// How the fake provider behaves, used by providers with a `fake://` base
+/// URL to run the bot without the real provider
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FakeProviderConfig {
+    /// In USD, taken from for every number that receives a code
+    pub balance: f32,
+    /// Added before every response, to simulate a slow provider
+    pub latency_ms: u64,
+    /// How long after ordering a number receives its code
+    pub sms_delay_secs: u64,
+    /// How long a number can receive a code for before it expires
+    pub order_lifetime_secs: u64,
+    /// Services that are out of stock everywhere
+    pub out_of_stock: Vec<String>,
+    /// Countries no service can be ordered from
+    pub unavailable_countries: Vec<String>,
+    /// Services whose numbers expire without receiving a code
+    pub never_delivered: Vec<String>,
+    /// Operations that answer with a payload the client can't decode
+    pub malformed: Vec<FakeOperation>,
+    /// Operations that fail without saying why, answering `{"success": 0}`
+    pub failing: Vec<FakeOperation>,
+}
+
+impl Default for FakeProviderConfig {
+    fn default() -> Self {
+        Self {
+            balance: 100.0,
+            latency_ms: 0,
+            sms_delay_secs: 15,
+            order_lifetime_secs: 20 * 60,
+            out_of_stock: vec![],
+            unavailable_countries: vec![],
+            never_delivered: vec![],
+            malformed: vec![],
+            failing: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub secrets: Secrets,
+    pub api: ApiConfig,
+    pub providers: Vec<ProviderConfig>,
+    pub timeouts: TimeoutsConfig,
+    pub discord: DiscordConfig,
+    pub pricing: PricingConfig,
+    pub colors: ColorsConfig,
+    /// The text of the embeds, with the overrides from `templates.file` and
+    /// the translations in `templates.locales`
+    pub templates: Templates,
+    pub storage: StorageConfig,
+    pub limits: LimitsConfig,
+    pub balance_monitor: BalanceMonitorConfig,
+    pub history: HistoryConfig,
+    pub deposits: DepositsConfig,
+    pub currencies: CurrenciesConfig,
+    pub service_matching: ServiceMatchingConfig,
+    pub features: FeaturesConfig,
+    pub fake_provider: FakeProviderConfig,
+}
+
+impl Config {
+    /// Load the config from the file at `ALTERA_CONFIG` (or `config.toml`),
+    /// apply the environment variable overrides and validate it
+    ///
+    /// The default file is optional so that deployments configured only
+    /// through environment variables keep working
+    pub fn load() -> Result<Self, ConfigError> {
+        let (path, required) = match env::var("ALTERA_CONFIG") {
+            Ok(path) => (PathBuf::from(path), true),
+            Err(_) => (PathBuf::from(DEFAULT_CONFIG_PATH), false),
+        };
+
+        let mut raw = match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str::<RawConfig>(&contents)
+                .map_err(|source| ConfigError::Parse { path, source })?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound && !required => {
+                RawConfig::default()
+            }
+            Err(source) => return Err(ConfigError::Read { path, source }),
+        };
+
+        raw.apply_env()?;
+        Self::validate(raw)
+    }
+
+    /// Parse and validate the config without the environment variable
+    /// overrides
+    pub fn from_toml(contents: &str) -> Result<Self, ConfigError> {
+        let raw = toml::from_str::<RawConfig>(contents).map_err(|source| ConfigError::Parse {
+            path: PathBuf::new(),
+            source,
+        })?;
+
+        Self::validate(raw)
+    }
+
+    /// The provider orders are sent to, validation guarantees there is one
+    pub fn provider(&self) -> &ProviderConfig {
+        self.providers
+            .iter()
+            .find(|provider| provider.enabled)
+            .expect("validated config has an enabled provider")
+    }
+
+    fn validate(raw: RawConfig) -> Result<Self, ConfigError> {
+        let mut errors = vec![];
+
+        if raw.discord.token.trim().is_empty() {
+            errors.push("discord.token is empty, set it in the config or DISCORD_TOKEN".to_string());
+        }
+        if raw.api.admin_token.trim().is_empty() {
+            errors.push("api.admin_token is empty, set it in the config or ADMIN_TOKEN".to_string());
+        }
+
+        if raw.discord.deployment == Deployment::Guild && raw.discord.guilds.is_empty() {
+            errors.push(
+                "discord.guilds must contain at least one guild id when discord.deployment is \"guild\""
+                    .to_string(),
+            );
+        }
+        let guilds = raw
+            .discord
+            .guilds
+            .iter()
+            .filter_map(|&guild| {
+                let id = Id::new_checked(guild);
+                if id.is_none() {
+                    errors.push("discord.guilds contains an id of 0".to_string());
+                }
+                id
+            })
+            .collect();
+        let log_channel = Id::new_checked(raw.discord.log_channel);
+        if log_channel.is_none() {
+            errors.push("discord.log_channel must be set to a channel id".to_string());
+        }
+
+        if !raw.api.base_url.starts_with("http://") && !raw.api.base_url.starts_with("https://") {
+            errors.push(format!(
+                "api.base_url `{}` must start with http:// or https://",
+                raw.api.base_url
+            ));
+        }
+
+        if !raw.providers.iter().any(|provider| provider.enabled) {
+            errors.push("providers must contain at least one enabled provider".to_string());
+        }
+        for (i, provider) in raw.providers.iter().enumerate() {
+            if provider.name.trim().is_empty() {
+                errors.push(format!("providers[{i}].name is empty"));
+            } else if raw.providers[..i].iter().any(|p| p.name == provider.name) {
+                errors.push(format!("providers[{i}].name `{}` is used more than once", provider.name));
+            }
+            let is_fake = provider.base_url.starts_with(FAKE_PROVIDER_URL);
+            if !provider.base_url.starts_with("http://")
+                && !provider.base_url.starts_with("https://")
+                && !is_fake
+            {
+                errors.push(format!(
+                    "providers[{i}].base_url `{}` must start with http://, https:// or {}",
+                    provider.base_url, FAKE_PROVIDER_URL
+                ));
+            }
+            if provider.enabled && !is_fake && provider.api_key.trim().is_empty() {
+                errors.push(format!("providers[{i}].api_key is empty, set it in the config or API_KEY"));
+            }
+        }
+
+        if !raw.pricing.multiplier.is_finite() || raw.pricing.multiplier <= 0.0 {
+            errors.push(format!(
+                "pricing.multiplier must be a positive number, got {}",
+                raw.pricing.multiplier
+            ));
+        }
+
+        let success = parse_color(&raw.colors.success).unwrap_or_else(|| {
+            errors.push(format!("colors.success `{}` is not a hex color like #65C97A", raw.colors.success));
+            0
+        });
+        let error = parse_color(&raw.colors.error).unwrap_or_else(|| {
+            errors.push(format!("colors.error `{}` is not a hex color like #E85041", raw.colors.error));
+            0
+        });
+
+        let mut templates = match &raw.templates.file {
+            Some(file) => Templates::load(file).unwrap_or_else(|template_errors| {
+                errors.extend(template_errors);
+                Templates::default()
+            }),
+            None => Templates::default(),
+        };
+        if let Some(locales) = &raw.templates.locales {
+            if let Err(locale_errors) = templates.load_locales(locales) {
+                errors.extend(locale_errors);
+            }
+        }
+        templates.footer = Some(raw.templates.footer.trim().to_string()).filter(|footer| !footer.is_empty());
+        templates.timestamp = raw.templates.timestamp;
+
+        for (name, cap) in [
+            ("support_adjustment_cap", raw.limits.support_adjustment_cap),
+            ("admin_adjustment_cap", raw.limits.admin_adjustment_cap),
+        ] {
+            if !cap.is_finite() || cap < 0.0 {
+                errors.push(format!("limits.{} must be 0 or more, got {}", name, cap));
+            }
+        }
+
+        if raw.balance_monitor.enabled && raw.balance_monitor.interval_secs == 0 {
+            errors.push("balance_monitor.interval_secs must be greater than 0".to_string());
+        }
+        for &balance in &raw.balance_monitor.warn_at {
+            if !balance.is_finite() || balance < 0.0 {
+                errors.push(format!("balance_monitor.warn_at must only contain balances of 0 or more, got {}", balance));
+            }
+        }
+        if !raw.balance_monitor.pause_below.is_finite() || raw.balance_monitor.pause_below < 0.0 {
+            errors.push(format!(
+                "balance_monitor.pause_below must be 0 or more, got {}",
+                raw.balance_monitor.pause_below
+            ));
+        }
+
+        let deposits = &raw.deposits;
+        if !deposits.min_amount.is_finite()
+            || !deposits.max_amount.is_finite()
+            || deposits.min_amount <= 0.0
+            || deposits.max_amount < deposits.min_amount
+        {
+            errors.push(format!(
+                "deposits.min_amount must be positive and at most deposits.max_amount, got {} and {}",
+                deposits.min_amount, deposits.max_amount
+            ));
+        }
+        if deposits.poll_interval_secs == 0 {
+            errors.push("deposits.poll_interval_secs must be greater than 0".to_string());
+        }
+
+        if let Some(file) = &raw.currencies.file {
+            if let Err(rates_errors) = Rates::load(file) {
+                errors.extend(rates_errors);
+            }
+        }
+        if raw.currencies.reload_interval_secs == 0 {
+            errors.push("currencies.reload_interval_secs must be greater than 0".to_string());
+        }
+
+        let matching = &raw.service_matching;
+        if matching.min_score == 0 || matching.min_score > 100 {
+            errors.push(format!(
+                "service_matching.min_score must be between 1 and 100, got {}",
+                matching.min_score
+            ));
+        }
+        if matching.max_results == 0 || matching.max_results > 25 {
+            errors.push(format!(
+                "service_matching.max_results must be between 1 and 25, got {}",
+                matching.max_results
+            ));
+        }
+        let mut aliases: Vec<_> = matching.aliases.iter().collect();
+        aliases.sort();
+        let mut normalized_aliases = HashMap::new();
+        for (alias, service) in aliases {
+            if alias.trim().is_empty() || service.trim().is_empty() {
+                errors.push(format!(
+                    "service_matching.aliases must not be empty, got `{}` = `{}`",
+                    alias, service
+                ));
+            }
+            // Otherwise which of them is used would depend on the map's order
+            if let Some(other) = normalized_aliases.insert(logic::normalize(alias), alias) {
+                errors.push(format!(
+                    "service_matching.aliases `{}` and `{}` are the same alias",
+                    other, alias
+                ));
+            }
+        }
+
+        let fake = &raw.fake_provider;
+        if !fake.balance.is_finite() || fake.balance < 0.0 {
+            errors.push(format!("fake_provider.balance must be 0 or more, got {}", fake.balance));
+        }
+        if fake.order_lifetime_secs == 0 {
+            errors.push("fake_provider.order_lifetime_secs must be greater than 0".to_string());
+        }
+
+        if raw.timeouts.http_secs == 0 {
+            errors.push("timeouts.http_secs must be greater than 0".to_string());
+        }
+
+        if raw.storage.data_dir.as_os_str().is_empty() {
+            errors.push("storage.data_dir must not be empty".to_string());
+        }
+
+        if !errors.is_empty() {
+            return Err(ConfigError::Invalid(errors));
+        }
+
+        Ok(Self {
+            secrets: Secrets {
+                discord_token: raw.discord.token,
+                admin_token: raw.api.admin_token,
+            },
+            api: ApiConfig {
+                base_url: raw.api.base_url.trim_end_matches('/').to_string(),
+            },
+            providers: raw
+                .providers
+                .into_iter()
+                .map(|provider| ProviderConfig {
+                    name: provider.name,
+                    base_url: if provider.base_url.starts_with(FAKE_PROVIDER_URL) {
+                        provider.base_url
+                    } else {
+                        provider.base_url.trim_end_matches('/').to_string()
+                    },
+                    api_key: provider.api_key,
+                    enabled: provider.enabled,
+                })
+                .collect(),
+            timeouts: TimeoutsConfig {
+                http: Duration::from_secs(raw.timeouts.http_secs),
+            },
+            discord: DiscordConfig {
+                deployment: raw.discord.deployment,
+                guilds,
+                log_channel: log_channel.unwrap(),
+            },
+            pricing: PricingConfig {
+                multiplier: raw.pricing.multiplier,
+            },
+            colors: ColorsConfig { success, error },
+            templates,
+            storage: StorageConfig {
+                data_dir: raw.storage.data_dir,
+            },
+            limits: raw.limits,
+            balance_monitor: raw.balance_monitor,
+            history: raw.history,
+            deposits: raw.deposits,
+            currencies: raw.currencies,
+            service_matching: raw.service_matching,
+            features: raw.features,
+            fake_provider: raw.fake_provider,
+        })
+    }
+
+    /// Keep the fields that can't change at runtime from `current`, returning
+    /// the names of the ones that were changed in the file
+    fn retain_static(&mut self, current: &Config) -> Vec<&'static str> {
+        let mut ignored = vec![];
+
+        if self.secrets != current.secrets {
+            ignored.push("secrets");
+            self.secrets = current.secrets.clone();
+        }
+        if self.api != current.api {
+            ignored.push("api");
+            self.api = current.api.clone();
+        }
+        if self.providers != current.providers {
+            ignored.push("providers");
+            self.providers = current.providers.clone();
+        }
+        if self.timeouts != current.timeouts {
+            ignored.push("timeouts");
+            self.timeouts = current.timeouts.clone();
+        }
+        if self.storage != current.storage {
+            ignored.push("storage");
+            self.storage = current.storage.clone();
+        }
+        if self.fake_provider != current.fake_provider {
+            ignored.push("fake_provider");
+            self.fake_provider = current.fake_provider.clone();
+        }
+
+        ignored
+    }
+}
+
+impl RawConfig {
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        if let Ok(token) = env::var("DISCORD_TOKEN") {
+            self.discord.token = token;
+        }
+        if let Ok(token) = env::var("ADMIN_TOKEN") {
+            self.api.admin_token = token;
+        }
+        if let Ok(api_key) = env::var("API_KEY") {
+            match self.providers.first_mut() {
+                Some(provider) => provider.api_key = api_key,
+                None => self.providers.push(RawProviderConfig {
+                    name: "primary".to_string(),
+                    base_url: env::var("API_URL")
+                        .unwrap_or_else(|_| DEFAULT_PROVIDER_URL.to_string()),
+                    api_key,
+                    enabled: true,
+                }),
+            }
+        }
+        if let Some(guild) = parse_env("DEBUG_SCOPE")? {
+            self.discord.guilds = vec![guild];
+        }
+        if let Some(channel) = parse_env("LOG_CHANNEL")? {
+            self.discord.log_channel = channel;
+        }
+        if let Some(multiplier) = parse_env("PRICE_MULTIPLIER")? {
+            self.pricing.multiplier = multiplier;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_env<T: std::str::FromStr>(var: &'static str) -> Result<Option<T>, ConfigError>
+where
+    T::Err: std::fmt::Display,
+{
+    match env::var(var) {
+        Ok(value) => value.trim().parse().map(Some).map_err(|err: T::Err| ConfigError::Env {
+            var,
+            reason: err.to_string(),
+        }),
+        Err(_) => Ok(None),
+    }
+}
+
+fn parse_color(color: &str) -> Option<u32> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    // `from_str_radix` also accepts a leading `+`
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok()
+}
+
+impl Context {
+    pub fn config(&self) -> Arc<Config> {
+        Arc::clone(&self.config.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Reload the config file, only replacing the fields that can change at
+    /// runtime
+    ///
+    /// Returns the names of the changed fields that require a restart
+    pub fn reload_config(&self) -> Result<Vec<&'static str>, ConfigError> {
+        let mut config = Config::load()?;
+        let ignored = config.retain_static(&self.config());
+
+        *self.config.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(config);
+
+        tracing::info!("Reloaded config");
+        if !ignored.is_empty() {
+            tracing::warn!("Changes to {} require a restart and were ignored", ignored.join(", "));
+        }
+
+        Ok(ignored)
+    }
+}
@@ -0,0 +1,203 @@
+//! Exchange rates for showing prices in the currency a user prefers, orders
+//! and balances are always in USD
+//!
+//! The rates are read from `currencies.file`, which is checked for changes
+//! every `currencies.reload_interval_secs`
+
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError},
+    time::{Duration, SystemTime},
+};
+
+use crate::Context;
+
+/// The currency everything is charged in
+pub const BASE_CURRENCY: &str = "USD";
+
+/// A currency as it is written in the rates file
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct RawCurrency {
+    rate: f64,
+    #[serde(default)]
+    symbol: Option<String>,
+    #[serde(default = "default_decimals")]
+    decimals: u8,
+}
+
+fn default_decimals() -> u8 {
+    2
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Currency {
+    /// The ISO 4217 code, such as `EUR`
+    pub code: String,
+    /// Shown before the amount in locales that write the symbol first
+    pub symbol: Option<String>,
+    pub decimals: u8,
+    /// How much of the currency one USD is worth
+    pub rate: f64,
+}
+
+impl Currency {
+    /// The amount in USD converted to this currency
+    pub fn convert(&self, usd: f64) -> f64 {
+        usd * self.rate
+    }
+}
+
+/// The currencies prices can be shown in
+#[derive(Debug, Default)]
+pub struct Rates {
+    currencies: BTreeMap<String, Currency>,
+    path: Option<PathBuf>,
+    /// When the file was last modified and its size, to notice it changed
+    version: Option<(SystemTime, u64)>,
+}
+
+impl Rates {
+    /// Read the rates file
+    ///
+    /// Returns every problem found with the file, such as codes that aren't
+    /// currency codes or rates that aren't positive
+    pub fn load(file: &Path) -> Result<Self, Vec<String>> {
+        let version = version(file);
+        let contents = fs::read_to_string(file).map_err(|err| {
+            vec![format!(
+                "currencies.file `{}` can't be read: {}",
+                file.display(),
+                err
+            )]
+        })?;
+        let raw: HashMap<String, RawCurrency> = toml::from_str(&contents).map_err(|err| {
+            vec![format!(
+                "currencies.file `{}` is invalid: {}",
+                file.display(),
+                err
+            )]
+        })?;
+
+        let mut currencies = BTreeMap::new();
+        let mut errors = vec![];
+        for (code, currency) in raw {
+            if code.len() != 3 || !code.chars().all(|c| c.is_ascii_uppercase()) {
+                errors.push(format!(
+                    "currencies.file: `{}` isn't a currency code such as `EUR`",
+                    code
+                ));
+            } else if code == BASE_CURRENCY {
+                errors.push(format!(
+                    "currencies.file: `{}` is what prices are charged in and can't have a rate",
+                    code
+                ));
+            }
+            if !currency.rate.is_finite() || currency.rate <= 0.0 {
+                errors.push(format!(
+                    "currencies.file: the rate of `{}` must be a positive number, got {}",
+                    code, currency.rate
+                ));
+            }
+            if currency.decimals > 4 {
+                errors.push(format!(
+                    "currencies.file: the decimals of `{}` must be at most 4, got {}",
+                    code, currency.decimals
+                ));
+            }
+
+            currencies.insert(
+                code.clone(),
+                Currency {
+                    code,
+                    symbol: currency.symbol.filter(|symbol| !symbol.trim().is_empty()),
+                    decimals: currency.decimals,
+                    rate: currency.rate,
+                },
+            );
+        }
+
+        if !errors.is_empty() {
+            errors.sort();
+            return Err(errors);
+        }
+
+        Ok(Self {
+            currencies,
+            path: Some(file.to_path_buf()),
+            version,
+        })
+    }
+
+    /// The currency with the code, ignoring its case
+    pub fn get(&self, code: &str) -> Option<&Currency> {
+        self.currencies.get(&code.to_ascii_uppercase())
+    }
+
+    /// Every currency, ordered by code
+    pub fn all(&self) -> impl Iterator<Item = &Currency> {
+        self.currencies.values()
+    }
+
+    /// Whether the file is the one these rates were read from and it hasn't
+    /// changed since
+    fn is_current(&self, file: Option<&Path>) -> bool {
+        let Some(file) = file else {
+            return self.path.is_none();
+        };
+        let version = version(file);
+
+        self.path.as_deref() == Some(file) && version.is_some() && version == self.version
+    }
+}
+
+fn version(file: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(file).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+impl Context {
+    pub fn rates(&self) -> Arc<Rates> {
+        Arc::clone(&self.rates.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Read the rates file again if it changed, keeping the current rates if
+    /// it is invalid
+    ///
+    /// Returns whether the rates were replaced
+    pub fn reload_rates(&self) -> Result<bool, Vec<String>> {
+        let file = self.config().currencies.file.clone();
+        if self.rates().is_current(file.as_deref()) {
+            return Ok(false);
+        }
+
+        let rates = match &file {
+            Some(file) => Rates::load(file)?,
+            None => Rates::default(),
+        };
+        *self.rates.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(rates);
+
+        tracing::info!("Reloaded exchange rates");
+        Ok(true)
+    }
+
+    /// Check the rates file for changes every `currencies.reload_interval_secs`
+    pub fn spawn_rates_reloader(self: Arc<Self>) {
+        tokio::spawn(async move {
+            loop {
+                let interval = self.config().currencies.reload_interval_secs.max(1);
+                tokio::time::sleep(Duration::from_secs(interval)).await;
+
+                if let Err(errors) = self.reload_rates() {
+                    tracing::error!(
+                        "Failed to reload exchange rates, keeping the current ones:\n  - {}",
+                        errors.join("\n  - ")
+                    );
+                }
+            }
+        });
+    }
+}
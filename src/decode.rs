@@ -0,0 +1,89 @@
+//! Lenient deserializers for the upstream APIs, which aren't consistent about
+//! whether numbers are sent as numbers or strings
+
+use serde::{de::Error, Deserialize, Deserializer};
+use std::{fmt::Display, str::FromStr};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberRepr<T> {
+    Number(T),
+    String(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringRepr {
+    String(String),
+    Integer(i64),
+    Float(f64),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FlagRepr {
+    Bool(bool),
+    Integer(i64),
+    String(String),
+}
+
+/// A number sent as a number or a string like `"0.50"`
+pub fn number<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr,
+    T::Err: Display,
+{
+    match NumberRepr::<T>::deserialize(deserializer)? {
+        NumberRepr::Number(number) => Ok(number),
+        NumberRepr::String(string) => string.trim().parse().map_err(D::Error::custom),
+    }
+}
+
+/// A number that may also be null or an empty string
+pub fn optional_number<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr,
+    T::Err: Display,
+{
+    match Option::<NumberRepr<T>>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(NumberRepr::Number(number)) => Ok(Some(number)),
+        Some(NumberRepr::String(string)) if string.trim().is_empty() => Ok(None),
+        Some(NumberRepr::String(string)) => {
+            string.trim().parse().map(Some).map_err(D::Error::custom)
+        }
+    }
+}
+
+/// A string that may be sent as a number, such as IDs and phone numbers
+pub fn string<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match StringRepr::deserialize(deserializer)? {
+        StringRepr::String(string) => string,
+        StringRepr::Integer(integer) => integer.to_string(),
+        StringRepr::Float(float) => float.to_string(),
+    })
+}
+
+/// A boolean sent as `true`, `1` or `"true"`
+pub fn flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match FlagRepr::deserialize(deserializer)? {
+        FlagRepr::Bool(flag) => Ok(flag),
+        FlagRepr::Integer(integer) => Ok(integer != 0),
+        FlagRepr::String(string) => match string.trim().to_lowercase().as_str() {
+            "true" | "1" => Ok(true),
+            "false" | "0" | "" => Ok(false),
+            _ => Err(D::Error::custom(format!(
+                "expected a boolean, got `{}`",
+                string
+            ))),
+        },
+    }
+}
@@ -0,0 +1,457 @@
+use anyhow::anyhow;
+use sparkle_convenience::{
+    interaction::{extract::InteractionExt, DeferBehavior, DeferVisibility},
+    reply::Reply,
+};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    channel::message::{
+        component::{ActionRow, Button, ButtonStyle},
+        Component, Embed,
+    },
+    user::User,
+};
+use twilight_util::builder::embed::EmbedFieldBuilder;
+
+use crate::{
+    adjustment::{Adjustment, AdjustmentKind},
+    api::Role,
+    audit::AuditEvent,
+    interaction::InteractionContext,
+    permissions::Capability,
+    templates::Args,
+};
+
+/// The custom id prefix of the confirmation buttons
+pub const COMPONENT_PREFIX: &str = "adjustment";
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "credit", desc = "add to a user's balance")]
+pub struct CreditCommand {
+    #[command(desc = "the user to credit")]
+    user: User,
+    #[command(
+        desc = "the amount to add in USD",
+        min_value = 0.01,
+        max_value = 10000.0
+    )]
+    amount: f64,
+    #[command(desc = "why the user is credited", max_length = 512)]
+    reason: String,
+}
+
+impl CreditCommand {
+    pub async fn execute(
+        self,
+        ictx: &InteractionContext<'_>,
+        role: Role,
+    ) -> Result<(), anyhow::Error> {
+        propose_adjustment(
+            ictx,
+            role,
+            AdjustmentKind::Credit,
+            &self.user,
+            AdjustmentAmount::Usd(self.amount),
+            self.reason,
+        )
+        .await
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "debit", desc = "take from a user's balance")]
+pub struct DebitCommand {
+    #[command(desc = "the user to debit")]
+    user: User,
+    #[command(
+        desc = "the amount to take in USD",
+        min_value = 0.01,
+        max_value = 10000.0
+    )]
+    amount: f64,
+    #[command(desc = "why the user is debited", max_length = 512)]
+    reason: String,
+}
+
+impl DebitCommand {
+    pub async fn execute(
+        self,
+        ictx: &InteractionContext<'_>,
+        role: Role,
+    ) -> Result<(), anyhow::Error> {
+        propose_adjustment(
+            ictx,
+            role,
+            AdjustmentKind::Debit,
+            &self.user,
+            AdjustmentAmount::Usd(self.amount),
+            self.reason,
+        )
+        .await
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(
+    name = "refund",
+    desc = "give a user back what they were charged for an order"
+)]
+pub struct RefundCommand {
+    #[command(desc = "the user who placed the order")]
+    user: User,
+    #[command(desc = "the id of the order to refund", min_length = 1)]
+    order: String,
+    #[command(desc = "why the order is refunded", max_length = 512)]
+    reason: String,
+}
+
+impl RefundCommand {
+    pub async fn execute(
+        self,
+        ictx: &InteractionContext<'_>,
+        role: Role,
+    ) -> Result<(), anyhow::Error> {
+        propose_adjustment(
+            ictx,
+            role,
+            AdjustmentKind::Refund,
+            &self.user,
+            AdjustmentAmount::Order(self.order.trim().to_string()),
+            self.reason,
+        )
+        .await
+    }
+}
+
+enum AdjustmentAmount {
+    Usd(f64),
+    /// The price of the order
+    Order(String),
+}
+
+/// Check the adjustment and ask the moderator to confirm it
+async fn propose_adjustment(
+    ictx: &InteractionContext<'_>,
+    role: Role,
+    kind: AdjustmentKind,
+    user: &User,
+    amount: AdjustmentAmount,
+    reason: String,
+) -> Result<(), anyhow::Error> {
+    let Ok(user_data) = ictx.ctx.api.get_user_data(user.id).await else {
+        return ictx
+            .reply_template("no_account", &[("user", &user.name)])
+            .await;
+    };
+
+    let (amount, order_id) = match amount {
+        // Can't saturate, the amount is at most the command's `max_value`
+        AdjustmentAmount::Usd(usd) => ((usd * 100.0).round() as i32, None),
+        AdjustmentAmount::Order(order_id) => {
+            let Some(number) = user_data
+                .numbers
+                .iter()
+                .find(|number| number.order_id == order_id)
+            else {
+                return ictx
+                    .reply_template(
+                        "no_such_order",
+                        &[("user", &user.name), ("order", &order_id)],
+                    )
+                    .await;
+            };
+            if !number.received {
+                return ictx
+                    .reply_template("order_not_charged", &[("order", &order_id)])
+                    .await;
+            }
+
+            if ictx.ctx.refunded_orders.contains(&order_id) {
+                return ictx
+                    .reply_template("order_already_refunded", &[("order", &order_id)])
+                    .await;
+            }
+
+            (number.price, Some(order_id))
+        }
+    };
+
+    if let Some(cap) = ictx.ctx.config().limits.adjustment_cap(role) {
+        if amount > cap {
+            return ictx
+                .reply_template(
+                    "adjustment_over_cap",
+                    &[
+                        ("role", &ictx.label(&role.to_string())),
+                        ("cap", &ictx.usd(f64::from(cap) / 100.0)),
+                    ],
+                )
+                .await;
+        }
+    }
+
+    let Some(balance_after) = kind.apply(user_data.balance, amount) else {
+        return ictx
+            .reply_template("adjustment_overflow", &[("user", &user.name)])
+            .await;
+    };
+    if balance_after < 0 {
+        return ictx
+            .reply_template(
+                "debit_over_balance",
+                &[
+                    ("user", &user.name),
+                    ("balance", &ictx.usd(f64::from(user_data.balance) / 100.0)),
+                ],
+            )
+            .await;
+    }
+
+    let adjustment = Adjustment {
+        kind,
+        moderator_id: ictx
+            .interaction
+            .author_id()
+            .ok_or_else(|| anyhow!("No author"))?,
+        user_id: user.id,
+        user_name: user.name.clone(),
+        account_id: user_data.id,
+        amount,
+        balance_before: user_data.balance,
+        balance_after,
+        reason: reason.trim().to_string(),
+        order_id,
+    };
+
+    let confirm_embed = adjustment_embed(ictx, &adjustment, false)?;
+    let id = ictx.interaction.id;
+    ictx.ctx.pending_adjustments.insert(id, adjustment);
+
+    let buttons = Component::ActionRow(ActionRow {
+        components: vec![
+            Component::Button(Button {
+                custom_id: Some(format!("{}:confirm:{}", COMPONENT_PREFIX, id)),
+                disabled: false,
+                emoji: None,
+                label: Some(ictx.label("Confirm")),
+                style: ButtonStyle::Success,
+                url: None,
+            }),
+            Component::Button(Button {
+                custom_id: Some(format!("{}:cancel:{}", COMPONENT_PREFIX, id)),
+                disabled: false,
+                emoji: None,
+                label: Some(ictx.label("Cancel")),
+                style: ButtonStyle::Secondary,
+                url: None,
+            }),
+        ],
+    });
+
+    ictx.handle
+        .reply(
+            Reply::new()
+                .embed(confirm_embed)
+                .component(buttons)
+                .ephemeral(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+impl InteractionContext<'_> {
+    /// Handle the confirm and cancel buttons of a balance adjustment
+    pub async fn handle_adjustment_component(self) -> Result<(), anyhow::Error> {
+        let custom_id = self
+            .interaction
+            .name()
+            .ok_or_else(|| anyhow!("No custom id"))?;
+        let Some((action, id)) = custom_id
+            .strip_prefix(COMPONENT_PREFIX)
+            .and_then(|rest| rest.strip_prefix(':'))
+            .and_then(|rest| rest.split_once(':'))
+        else {
+            return Err(anyhow!("Invalid adjustment custom id {}", custom_id));
+        };
+        let confirmed = action == "confirm";
+        let id = id.parse()?;
+
+        self.handle
+            .defer_component(DeferVisibility::Ephemeral, DeferBehavior::Update)
+            .await?;
+
+        // Only look at the adjustment until the clicker is known to be the
+        // moderator, so that nobody else can cancel it
+        let Some(adjustment) = self.ctx.pending_adjustments.get(id) else {
+            return self.adjustment_expired().await;
+        };
+        if Some(adjustment.moderator_id) != self.interaction.author_id() {
+            return self
+                .update_adjustment_message("adjustment_not_moderator", &[])
+                .await;
+        }
+
+        if !confirmed {
+            self.ctx.pending_adjustments.take(id);
+            return self
+                .update_adjustment_message("adjustment_cancelled", &[])
+                .await;
+        }
+
+        // Check again, the user's role might have changed since they ran the
+        // command
+        let Some(role) = self.authorize(Capability::AdjustBalances).await? else {
+            return Ok(());
+        };
+
+        let Some(adjustment) = self.ctx.pending_adjustments.take(id) else {
+            return self.adjustment_expired().await;
+        };
+        if let Some(cap) = self.ctx.config().limits.adjustment_cap(role) {
+            if adjustment.amount > cap {
+                return self
+                    .update_adjustment_message(
+                        "adjustment_over_cap",
+                        &[
+                            ("role", &self.label(&role.to_string())),
+                            ("cap", &self.usd(f64::from(cap) / 100.0)),
+                        ],
+                    )
+                    .await;
+            }
+        }
+
+        // Another refund of the order might have been confirmed since, the
+        // order stays reserved until the API answers so that two
+        // confirmations can't both refund it
+        if let Some(order_id) = &adjustment.order_id {
+            if !self.ctx.refunded_orders.reserve(order_id) {
+                return self
+                    .update_adjustment_message("order_already_refunded", &[("order", order_id)])
+                    .await;
+            }
+        }
+
+        let result = match (&adjustment.kind, &adjustment.order_id) {
+            (AdjustmentKind::Refund, Some(order_id)) => {
+                self.ctx
+                    .api
+                    .refund_order(order_id, &adjustment.reason)
+                    .await
+            }
+            (AdjustmentKind::Debit, _) => {
+                self.ctx
+                    .api
+                    .adjust_balance(
+                        adjustment.account_id,
+                        -adjustment.amount,
+                        &adjustment.reason,
+                    )
+                    .await
+            }
+            _ => {
+                self.ctx
+                    .api
+                    .adjust_balance(adjustment.account_id, adjustment.amount, &adjustment.reason)
+                    .await
+            }
+        };
+
+        if let Some(order_id) = &adjustment.order_id {
+            if result.is_ok() {
+                if let Err(err) = self.ctx.refunded_orders.finish(order_id) {
+                    tracing::error!("Failed to save the refund of {}: {}", order_id, err);
+                }
+            } else {
+                self.ctx.refunded_orders.release(order_id);
+            }
+        }
+
+        if let Err(err) = result {
+            tracing::error!("{:#?}", err);
+            return self
+                .update_adjustment_message("adjustment_failed", &[("error", &err)])
+                .await;
+        }
+
+        self.ctx
+            .audit(
+                self.interaction.author(),
+                AuditEvent::BalanceAdjusted {
+                    kind: adjustment.kind,
+                    user_id: adjustment.user_id,
+                    amount: adjustment.amount,
+                    balance_before: adjustment.balance_before,
+                    balance_after: adjustment.balance_after,
+                    reason: adjustment.reason.clone(),
+                    order_id: adjustment.order_id.clone(),
+                },
+            )
+            .await;
+
+        let embed = adjustment_embed(&self, &adjustment, true)?;
+        self.handle
+            .reply(Reply::new().embed(embed).update_last())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn adjustment_expired(&self) -> Result<(), anyhow::Error> {
+        self.update_adjustment_message("adjustment_expired", &[])
+            .await
+    }
+
+    /// Replace the confirmation message with the template
+    async fn update_adjustment_message(
+        &self,
+        name: &str,
+        args: Args<'_>,
+    ) -> Result<(), anyhow::Error> {
+        let embed = self.render_template(name, args)?;
+
+        self.handle
+            .reply(Reply::new().embed(embed).update_last())
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn adjustment_embed(
+    ictx: &InteractionContext<'_>,
+    adjustment: &Adjustment,
+    made: bool,
+) -> Result<Embed, anyhow::Error> {
+    let name = match (adjustment.kind, made) {
+        (AdjustmentKind::Credit, false) => "confirm_credit",
+        (AdjustmentKind::Debit, false) => "confirm_debit",
+        (AdjustmentKind::Refund, false) => "confirm_refund",
+        (AdjustmentKind::Credit, true) => "credited",
+        (AdjustmentKind::Debit, true) => "debited",
+        (AdjustmentKind::Refund, true) => "refunded",
+    };
+
+    let usd = |cents: i32| format!("`{}`", ictx.usd(f64::from(cents) / 100.0));
+    let mut embed = ictx
+        .template_embed(name, &[("user", &adjustment.user_name)])
+        .field(EmbedFieldBuilder::new(ictx.label("Amount:"), usd(adjustment.amount)).inline())
+        .field(
+            EmbedFieldBuilder::new(ictx.label("Before:"), usd(adjustment.balance_before)).inline(),
+        )
+        .field(
+            EmbedFieldBuilder::new(ictx.label("After:"), usd(adjustment.balance_after)).inline(),
+        );
+    if let Some(order_id) = &adjustment.order_id {
+        embed = embed.field(
+            EmbedFieldBuilder::new(ictx.label("Order:"), format!("`{}`", order_id)).inline(),
+        );
+    }
+    embed = embed.field(EmbedFieldBuilder::new(
+        ictx.label("Reason:"),
+        &adjustment.reason,
+    ));
+
+    Ok(embed.validate()?.build())
+}
@@ -0,0 +1,175 @@
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::user::User;
+
+use crate::{
+    api::{ApiError, Role},
+    audit::AuditEvent,
+    bans::{parse_duration, Ban},
+    interaction::InteractionContext,
+};
+
+/// The longest suspension, anything longer should be a ban
+const MAX_SUSPENSION_SECS: i64 = 365 * 24 * 60 * 60;
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "ban", desc = "stop a user from using the bot")]
+pub struct BanCommand {
+    #[command(desc = "the user to ban")]
+    user: User,
+    #[command(desc = "why the user is banned, shown to them", max_length = 512)]
+    reason: String,
+}
+
+impl BanCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>, role: Role) -> Result<(), anyhow::Error> {
+        ban_user(ictx, role, &self.user, self.reason, None).await
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "suspend", desc = "stop a user from using the bot for a while")]
+pub struct SuspendCommand {
+    #[command(desc = "the user to suspend")]
+    user: User,
+    #[command(desc = "how long to suspend the user for, e.g. 30m, 12h, 7d or 1d12h")]
+    duration: String,
+    #[command(desc = "why the user is suspended, shown to them", max_length = 512)]
+    reason: String,
+}
+
+impl SuspendCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>, role: Role) -> Result<(), anyhow::Error> {
+        let Some(secs) = parse_duration(&self.duration) else {
+            return ictx
+                .reply_template("invalid_duration", &[("duration", &self.duration)])
+                .await;
+        };
+        if secs > MAX_SUSPENSION_SECS {
+            return ictx.reply_template("suspension_too_long", &[]).await;
+        }
+
+        let expires_at = chrono::Utc::now().timestamp() + secs;
+        ban_user(ictx, role, &self.user, self.reason, Some(expires_at)).await
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "unban", desc = "lift a user's ban or suspension")]
+pub struct UnbanCommand {
+    #[command(desc = "the user to unban")]
+    user: User,
+}
+
+impl UnbanCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        let result = ictx.ctx.bans_mut().unban(self.user.id);
+        match result {
+            Err(err) => ictx.reply_error(err.to_string()).await,
+            Ok(None) => {
+                ictx.reply_template("not_banned", &[("user", &self.user.name)])
+                    .await
+            }
+            Ok(Some(ban)) => {
+                ictx.ctx
+                    .audit(
+                        ictx.interaction.author(),
+                        AuditEvent::UserUnbanned {
+                            user_id: ban.user_id,
+                            reason: ban.reason,
+                        },
+                    )
+                    .await;
+
+                ictx.reply_template("user_unbanned", &[("user", &self.user.name)])
+                    .await
+            }
+        }
+    }
+}
+
+async fn ban_user(
+    ictx: &InteractionContext<'_>,
+    role: Role,
+    user: &User,
+    reason: String,
+    expires_at: Option<i64>,
+) -> Result<(), anyhow::Error> {
+    let Some(moderator) = ictx.interaction.author() else {
+        return ictx.reply_template("ban_unknown_moderator", &[]).await;
+    };
+    if user.id == moderator.id {
+        return ictx.reply_template("ban_self", &[]).await;
+    }
+    if user.bot {
+        return ictx.reply_template("ban_bot", &[]).await;
+    }
+
+    // Users without an account can't have a role
+    let user_role = match ictx.ctx.api.get_user_data(user.id).await {
+        Ok(user_data) => user_data.role,
+        Err(ApiError::Rejected(_)) => Role::User,
+        Err(err) => return Err(err.into()),
+    };
+    if user_role >= role {
+        return ictx
+            .reply_template(
+                "ban_higher_role",
+                &[
+                    ("user", &user.name),
+                    ("role", &ictx.label(&user_role.to_string())),
+                ],
+            )
+            .await;
+    }
+
+    let ban = Ban {
+        user_id: user.id,
+        reason: reason.trim().to_string(),
+        moderator_id: moderator.id,
+        moderator_name: moderator.name.clone(),
+        created_at: chrono::Utc::now().timestamp(),
+        expires_at,
+    };
+
+    let result = ictx.ctx.bans_mut().ban(ban.clone());
+    if let Err(err) = result {
+        return ictx.reply_error(err.to_string()).await;
+    }
+
+    ictx.ctx
+        .audit(
+            Some(moderator),
+            AuditEvent::UserBanned {
+                user_id: ban.user_id,
+                reason: ban.reason.clone(),
+                expires_at,
+            },
+        )
+        .await;
+
+    let notified = ictx.label(match ictx.ctx.notify_banned_user(&ban).await {
+        Ok(()) => "They were notified in their DMs.",
+        Err(err) => {
+            tracing::info!("Unable to notify {} of their ban: {}", user.id, err);
+            "They couldn't be notified in their DMs and will see the reason when they use the bot."
+        }
+    });
+
+    match expires_at {
+        Some(expires_at) => {
+            ictx.reply_template(
+                "user_suspended",
+                &[
+                    ("user", &user.name),
+                    ("expires_at", &expires_at),
+                    ("notified", &notified),
+                ],
+            )
+            .await
+        }
+        None => {
+            ictx.reply_template("user_banned", &[("user", &user.name), ("notified", &notified)])
+                .await
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use sparkle_convenience::reply::Reply;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_util::builder::embed::EmbedFieldBuilder;
+
+use crate::{
+    audit::AuditEvent,
+    blacklist::{BlacklistError, BlacklistRule, Category, RuleKind},
+    interaction::InteractionContext,
+    templates::fill,
+};
+
+/// Discord's limit on the length of an embed field value
+const FIELD_VALUE_LIMIT: usize = 1024;
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "blacklist", desc = "Manage the service blacklist")]
+pub enum BlacklistCommand {
+    #[command(name = "add")]
+    Add(AddCommand),
+    #[command(name = "remove")]
+    Remove(RemoveCommand),
+    #[command(name = "allow")]
+    Allow(AllowCommand),
+    #[command(name = "disallow")]
+    Disallow(DisallowCommand),
+    #[command(name = "list")]
+    List(ListCommand),
+    #[command(name = "check")]
+    Check(CheckCommand),
+}
+
+impl BlacklistCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        match self {
+            Self::Add(command) => command.execute(ictx).await,
+            Self::Remove(command) => command.execute(ictx).await,
+            Self::Allow(command) => command.execute(ictx).await,
+            Self::Disallow(command) => command.execute(ictx).await,
+            Self::List(command) => command.execute(ictx).await,
+            Self::Check(command) => command.execute(ictx).await,
+        }
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "add", desc = "block services matching a pattern")]
+pub struct AddCommand {
+    #[command(desc = "the service name, text or regex to block", min_length = 2)]
+    pattern: String,
+    #[command(desc = "how the pattern is matched against service names")]
+    kind: RuleKind,
+    #[command(desc = "why the services are blocked")]
+    category: Category,
+}
+
+impl AddCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        let rule = BlacklistRule {
+            pattern: self.pattern.trim().to_string(),
+            kind: self.kind,
+            category: self.category,
+        };
+
+        let result = ictx.ctx.blacklist_mut().add_rule(rule.clone());
+        match result {
+            Err(err) => reply_blacklist_error(ictx, err).await,
+            Ok(()) => {
+                ictx.ctx
+                    .audit(
+                        ictx.interaction.author(),
+                        AuditEvent::BlacklistRuleAdded {
+                            pattern: rule.pattern.clone(),
+                            kind: rule.kind,
+                            category: rule.category,
+                        },
+                    )
+                    .await;
+
+                ictx.reply_template(
+                    "blacklist_rule_added",
+                    &[
+                        ("kind", &rule.kind),
+                        ("pattern", &rule.pattern),
+                        ("category", &rule.category),
+                    ],
+                )
+                .await
+            }
+        }
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "remove", desc = "stop blocking services matching a pattern")]
+pub struct RemoveCommand {
+    #[command(desc = "the pattern of the rule to remove", min_length = 2)]
+    pattern: String,
+}
+
+impl RemoveCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        let pattern = self.pattern.trim();
+
+        let result = ictx.ctx.blacklist_mut().remove_rule(pattern);
+        match result {
+            Err(err) => reply_blacklist_error(ictx, err).await,
+            Ok(removed) if removed.is_empty() => {
+                ictx.reply_template("blacklist_rule_not_found", &[("pattern", &pattern)])
+                    .await
+            }
+            Ok(removed) => {
+                for rule in &removed {
+                    ictx.ctx
+                        .audit(
+                            ictx.interaction.author(),
+                            AuditEvent::BlacklistRuleRemoved {
+                                pattern: rule.pattern.clone(),
+                                kind: rule.kind,
+                                category: rule.category,
+                            },
+                        )
+                        .await;
+                }
+
+                ictx.reply_template(
+                    "blacklist_rules_removed",
+                    &[("count", &removed.len()), ("pattern", &pattern)],
+                )
+                .await
+            }
+        }
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "allow", desc = "never block a service, even if a rule matches it")]
+pub struct AllowCommand {
+    #[command(desc = "the exact service name to allow", min_length = 2)]
+    service: String,
+}
+
+impl AllowCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        let service = self.service.trim();
+
+        let result = ictx.ctx.blacklist_mut().allow(service);
+        match result {
+            Err(err) => reply_blacklist_error(ictx, err).await,
+            Ok(false) => {
+                ictx.reply_template("already_allowlisted", &[("service", &service)])
+                    .await
+            }
+            Ok(true) => {
+                ictx.ctx
+                    .audit(
+                        ictx.interaction.author(),
+                        AuditEvent::AllowlistAdded {
+                            service: service.to_string(),
+                        },
+                    )
+                    .await;
+
+                ictx.reply_template("allowlisted", &[("service", &service)])
+                    .await
+            }
+        }
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "disallow", desc = "remove a service from the allowlist")]
+pub struct DisallowCommand {
+    #[command(desc = "the exact service name to remove", min_length = 2)]
+    service: String,
+}
+
+impl DisallowCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        let service = self.service.trim();
+
+        let result = ictx.ctx.blacklist_mut().disallow(service);
+        match result {
+            Err(err) => reply_blacklist_error(ictx, err).await,
+            Ok(false) => {
+                ictx.reply_template("not_allowlisted", &[("service", &service)])
+                    .await
+            }
+            Ok(true) => {
+                ictx.ctx
+                    .audit(
+                        ictx.interaction.author(),
+                        AuditEvent::AllowlistRemoved {
+                            service: service.to_string(),
+                        },
+                    )
+                    .await;
+
+                ictx.reply_template("allowlist_removed", &[("service", &service)])
+                    .await
+            }
+        }
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "list", desc = "list the blacklist rules")]
+pub struct ListCommand {
+    #[command(desc = "only list the rules in this category")]
+    category: Option<Category>,
+}
+
+impl ListCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        let categories = match self.category {
+            Some(category) => vec![category],
+            None => vec![
+                Category::Banking,
+                Category::Crypto,
+                Category::Payments,
+                Category::Trading,
+                Category::Other,
+            ],
+        };
+
+        let mut embed = ictx.template_embed("blacklist_rules", &[]);
+
+        {
+            let blacklist = ictx.ctx.blacklist();
+            for category in categories {
+                let patterns: Vec<String> = blacklist
+                    .rules()
+                    .iter()
+                    .filter(|rule| rule.category == category)
+                    .map(|rule| match rule.kind {
+                        RuleKind::Exact => rule.pattern.clone(),
+                        RuleKind::Substring => format!("`{}`", rule.pattern),
+                        RuleKind::Regex => format!("`/{}/`", rule.pattern),
+                    })
+                    .collect();
+
+                if !patterns.is_empty() {
+                    embed = embed.field(EmbedFieldBuilder::new(
+                        fill(
+                            &ictx.label("{category} ({count}):"),
+                            &[("category", &category), ("count", &patterns.len())],
+                        ),
+                        join_truncated(&patterns),
+                    ));
+                }
+            }
+
+            if !blacklist.allowlist().is_empty() {
+                embed = embed.field(EmbedFieldBuilder::new(
+                    fill(
+                        &ictx.label("Allowlist ({count}):"),
+                        &[("count", &blacklist.allowlist().len())],
+                    ),
+                    join_truncated(blacklist.allowlist()),
+                ));
+            }
+        }
+
+        ictx.handle
+            .reply(Reply::new().embed(embed.validate()?.build()).ephemeral())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "check", desc = "check whether a service is blocked and why")]
+pub struct CheckCommand {
+    #[command(desc = "the service name to check", min_length = 2)]
+    service: String,
+}
+
+impl CheckCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        let service = self.service.trim();
+
+        let embed = {
+            let blacklist = ictx.ctx.blacklist();
+            match blacklist.check(service) {
+                Some(rule) => ictx.render_template(
+                    "service_blocked_by",
+                    &[
+                        ("service", &service),
+                        ("category", &rule.category),
+                        ("kind", &rule.kind),
+                        ("pattern", &rule.pattern),
+                    ],
+                ),
+                None if blacklist.is_allowlisted(service) => {
+                    ictx.render_template("service_allowlisted", &[("service", &service)])
+                }
+                None => ictx.render_template("service_not_blocked", &[("service", &service)]),
+            }
+        }?;
+
+        ictx.handle
+            .reply(Reply::new().embed(embed).ephemeral())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Reply with the error, invalid and duplicate rules are the moderator's
+/// mistake and have templates, failing to save the blacklist doesn't
+async fn reply_blacklist_error(
+    ictx: &InteractionContext<'_>,
+    err: BlacklistError,
+) -> Result<(), anyhow::Error> {
+    match err {
+        BlacklistError::Regex { pattern, source } => {
+            ictx.reply_template(
+                "invalid_blacklist_regex",
+                &[("pattern", &pattern), ("error", &source)],
+            )
+            .await
+        }
+        BlacklistError::Duplicate { pattern, kind } => {
+            ictx.reply_template(
+                "duplicate_blacklist_rule",
+                &[("kind", &kind), ("pattern", &pattern)],
+            )
+            .await
+        }
+        BlacklistError::Store(_) => ictx.reply_error(err.to_string()).await,
+    }
+}
+
+fn join_truncated(items: &[String]) -> String {
+    let mut value = String::new();
+    for (i, item) in items.iter().enumerate() {
+        let more = format!("… and {} more", items.len() - i);
+        if value.len() + item.len() + 2 + more.len() > FIELD_VALUE_LIMIT {
+            value += &more;
+            break;
+        }
+        if i > 0 {
+            value += ", ";
+        }
+        value += item;
+    }
+    value
+}
@@ -0,0 +1,25 @@
+use sparkle_convenience::{interaction::DeferVisibility, reply::Reply};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::user::User;
+
+use crate::interaction::InteractionContext;
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "deposits", desc = "see a user's deposits")]
+pub struct DepositsCommand {
+    #[command(desc = "the user to see the deposits of")]
+    user: User,
+}
+
+impl DepositsCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        ictx.handle.defer(DeferVisibility::Ephemeral).await?;
+
+        let embed = ictx.deposits_embed(&self.user).await?;
+        ictx.handle
+            .reply(Reply::new().embed(embed).ephemeral())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+use twilight_interactions::command::{CommandModel, CreateCommand};
+
+use crate::{audit::AuditEvent, interaction::InteractionContext, templates::fill};
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "maintenance", desc = "Turn the bot or single commands off and on")]
+pub enum MaintenanceCommand {
+    #[command(name = "on")]
+    On(OnCommand),
+    #[command(name = "off")]
+    Off(OffCommand),
+    #[command(name = "disable")]
+    Disable(DisableCommand),
+    #[command(name = "enable")]
+    Enable(EnableCommand),
+    #[command(name = "status")]
+    Status(StatusCommand),
+}
+
+impl MaintenanceCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        match self {
+            Self::On(command) => command.execute(ictx).await,
+            Self::Off(command) => command.execute(ictx).await,
+            Self::Disable(command) => command.execute(ictx).await,
+            Self::Enable(command) => command.execute(ictx).await,
+            Self::Status(command) => command.execute(ictx).await,
+        }
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "on", desc = "make every command but /admin unavailable")]
+pub struct OnCommand {
+    #[command(desc = "shown to users, such as what is happening or when it ends", max_length = 512)]
+    message: Option<String>,
+}
+
+impl OnCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        let message = self.message.map(|message| message.trim().to_string());
+
+        let result = ictx
+            .ctx
+            .maintenance_mut()
+            .set_enabled(true, message.clone());
+        if let Err(err) = result {
+            return ictx.reply_error(err.to_string()).await;
+        }
+
+        ictx.ctx
+            .audit(
+                ictx.interaction.author(),
+                AuditEvent::MaintenanceChanged {
+                    enabled: true,
+                    message,
+                },
+            )
+            .await;
+
+        ictx.reply_template("maintenance_on", &[]).await
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "off", desc = "make the commands available again")]
+pub struct OffCommand;
+
+impl OffCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        if !ictx.ctx.maintenance().is_enabled() {
+            return ictx.reply_template("maintenance_not_on", &[]).await;
+        }
+
+        let result = ictx.ctx.maintenance_mut().set_enabled(false, None);
+        if let Err(err) = result {
+            return ictx.reply_error(err.to_string()).await;
+        }
+
+        ictx.ctx
+            .audit(
+                ictx.interaction.author(),
+                AuditEvent::MaintenanceChanged {
+                    enabled: false,
+                    message: None,
+                },
+            )
+            .await;
+
+        ictx.reply_template("maintenance_off", &[]).await
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "disable", desc = "make a single command unavailable")]
+pub struct DisableCommand {
+    #[command(desc = "the name of the command, such as getnumber")]
+    command: String,
+}
+
+impl DisableCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        let command = match toggleable_command(&self.command) {
+            Ok(command) => command,
+            Err((template, command)) => {
+                return ictx
+                    .reply_template(template, &[("command", &command)])
+                    .await
+            }
+        };
+
+        let result = ictx.ctx.maintenance_mut().disable_command(&command);
+        match result {
+            Err(err) => ictx.reply_error(err.to_string()).await,
+            Ok(false) => {
+                ictx.reply_template("command_already_disabled", &[("command", &command)])
+                    .await
+            }
+            Ok(true) => {
+                ictx.ctx
+                    .audit(
+                        ictx.interaction.author(),
+                        AuditEvent::CommandToggled {
+                            command: command.clone(),
+                            enabled: false,
+                        },
+                    )
+                    .await;
+
+                ictx.reply_template("command_now_disabled", &[("command", &command)])
+                    .await
+            }
+        }
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "enable", desc = "make a disabled command available again")]
+pub struct EnableCommand {
+    #[command(desc = "the name of the command, such as getnumber")]
+    command: String,
+}
+
+impl EnableCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        let command = match toggleable_command(&self.command) {
+            Ok(command) => command,
+            Err((template, command)) => {
+                return ictx
+                    .reply_template(template, &[("command", &command)])
+                    .await
+            }
+        };
+
+        let result = ictx.ctx.maintenance_mut().enable_command(&command);
+        match result {
+            Err(err) => ictx.reply_error(err.to_string()).await,
+            Ok(false) => {
+                ictx.reply_template("command_not_disabled", &[("command", &command)])
+                    .await
+            }
+            Ok(true) => {
+                ictx.ctx
+                    .audit(
+                        ictx.interaction.author(),
+                        AuditEvent::CommandToggled {
+                            command: command.clone(),
+                            enabled: true,
+                        },
+                    )
+                    .await;
+
+                ictx.reply_template("command_now_enabled", &[("command", &command)])
+                    .await
+            }
+        }
+    }
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "status", desc = "show what is turned off")]
+pub struct StatusCommand;
+
+impl StatusCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        let (mode, disabled) = {
+            let maintenance = ictx.ctx.maintenance();
+
+            let mode = match (maintenance.is_enabled(), maintenance.message()) {
+                (true, Some(message)) => fill(
+                    &ictx.label("Maintenance mode is **on**: {message}"),
+                    &[("message", &message)],
+                ),
+                (true, None) => ictx.label("Maintenance mode is **on**."),
+                (false, _) => ictx.label("Maintenance mode is **off**."),
+            };
+            let disabled = if maintenance.disabled_commands().is_empty() {
+                ictx.label("No commands are disabled.")
+            } else {
+                let commands = format!("`/{}`", maintenance.disabled_commands().join("`, `/"));
+                fill(
+                    &ictx.label("Disabled commands: {commands}"),
+                    &[("commands", &commands)],
+                )
+            };
+
+            (mode, disabled)
+        };
+
+        ictx.reply_template(
+            "maintenance_status",
+            &[("mode", &mode), ("disabled", &disabled)],
+        )
+        .await
+    }
+}
+
+/// The command name without the slash, if it is a command that can be
+/// disabled, or the template explaining why it can't be with the name
+fn toggleable_command(input: &str) -> Result<String, (&'static str, String)> {
+    let command = input.trim().trim_start_matches('/').to_lowercase();
+    if command == "admin" {
+        return Err(("admin_not_toggleable", command));
+    }
+
+    if crate::interaction::commands()
+        .iter()
+        .any(|existing| existing.name == command)
+    {
+        Ok(command)
+    } else {
+        Err(("unknown_command", command))
+    }
+}
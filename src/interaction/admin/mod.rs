@@ -0,0 +1,100 @@
+use anyhow::{anyhow, Context};
+use std::mem;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{application::interaction::InteractionData, guild::Permissions};
+
+use super::InteractionContext;
+use crate::permissions::Capability;
+
+mod balance;
+mod ban;
+mod blacklist;
+mod deposits;
+mod maintenance;
+mod reload;
+
+fn default_permissions() -> Permissions {
+    Permissions::MANAGE_GUILD
+}
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(
+    name = "admin",
+    desc = "Manage the bot",
+    dm_permission = false,
+    default_permissions = "default_permissions"
+)]
+pub enum AdminCommand {
+    #[command(name = "reload")]
+    Reload(reload::ReloadCommand),
+    #[command(name = "blacklist")]
+    Blacklist(blacklist::BlacklistCommand),
+    #[command(name = "ban")]
+    Ban(ban::BanCommand),
+    #[command(name = "suspend")]
+    Suspend(ban::SuspendCommand),
+    #[command(name = "unban")]
+    Unban(ban::UnbanCommand),
+    #[command(name = "credit")]
+    Credit(balance::CreditCommand),
+    #[command(name = "debit")]
+    Debit(balance::DebitCommand),
+    #[command(name = "refund")]
+    Refund(balance::RefundCommand),
+    #[command(name = "maintenance")]
+    Maintenance(maintenance::MaintenanceCommand),
+    #[command(name = "deposits")]
+    Deposits(deposits::DepositsCommand),
+}
+
+pub use balance::COMPONENT_PREFIX as ADJUSTMENT_COMPONENT;
+
+impl AdminCommand {
+    fn capability(&self) -> Capability {
+        match self {
+            Self::Reload(_) | Self::Maintenance(_) => Capability::ManageBot,
+            Self::Blacklist(_) => Capability::ManageBlacklist,
+            Self::Ban(_) | Self::Suspend(_) | Self::Unban(_) => Capability::ManageBans,
+            Self::Credit(_) | Self::Debit(_) | Self::Refund(_) => Capability::AdjustBalances,
+            Self::Deposits(_) => Capability::ViewUserData,
+        }
+    }
+}
+
+impl InteractionContext<'_> {
+    pub async fn handle_admin_command(mut self) -> Result<(), anyhow::Error> {
+        let data = match mem::take(&mut self.interaction.data) {
+            Some(InteractionData::ApplicationCommand(data)) => *data,
+            _ => {
+                tracing::warn!("ignoring non-command interaction");
+                return Err(anyhow!("Unable to get slash command info"));
+            }
+        };
+
+        let command =
+            AdminCommand::from_interaction(data.into()).context("Failed to parse command data");
+
+        let Ok(command) = command else {
+            tracing::error!("Error matching admin command");
+            return Ok(());
+        };
+        let Some(role) = self.authorize(command.capability()).await? else {
+            return Ok(());
+        };
+
+        match command {
+            AdminCommand::Reload(command) => command.execute(&self).await?,
+            AdminCommand::Blacklist(command) => command.execute(&self).await?,
+            AdminCommand::Ban(command) => command.execute(&self, role).await?,
+            AdminCommand::Suspend(command) => command.execute(&self, role).await?,
+            AdminCommand::Unban(command) => command.execute(&self).await?,
+            AdminCommand::Credit(command) => command.execute(&self, role).await?,
+            AdminCommand::Debit(command) => command.execute(&self, role).await?,
+            AdminCommand::Refund(command) => command.execute(&self, role).await?,
+            AdminCommand::Maintenance(command) => command.execute(&self).await?,
+            AdminCommand::Deposits(command) => command.execute(&self).await?,
+        }
+
+        Ok(())
+    }
+}
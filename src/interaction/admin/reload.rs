@@ -0,0 +1,25 @@
+use twilight_interactions::command::{CommandModel, CreateCommand};
+
+use crate::interaction::InteractionContext;
+
+#[derive(CommandModel, CreateCommand, Debug)]
+#[command(name = "reload", desc = "reload the config file without restarting the bot")]
+pub struct ReloadCommand;
+
+impl ReloadCommand {
+    pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
+        match ictx.ctx.reload_config() {
+            Ok(ignored) if ignored.is_empty() => ictx.reply_template("config_reloaded", &[]).await,
+            Ok(ignored) => {
+                let fields = ignored.join("`, `");
+                ictx.reply_template("config_reloaded_partly", &[("fields", &fields)])
+                    .await
+            }
+            Err(err) => {
+                tracing::error!("Failed to reload config: {}", err);
+                ictx.reply_template("config_reload_failed", &[("error", &err)])
+                    .await
+            }
+        }
+    }
+}
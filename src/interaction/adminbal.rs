@@ -1,9 +1,10 @@
 use sparkle_convenience::reply::Reply;
 use twilight_interactions::command::{CommandModel, CreateCommand};
 use twilight_model::guild::Permissions;
-use twilight_util::builder::embed::EmbedBuilder;
+use twilight_util::builder::embed::EmbedFieldBuilder;
 
 use super::InteractionContext;
+use crate::{permissions::Capability, templates::fill};
 
 fn default_permissions() -> Permissions {
     Permissions::MANAGE_GUILD
@@ -19,30 +20,39 @@ pub struct AdminBalCommand;
 
 impl InteractionContext<'_> {
     pub async fn handle_adminbal_command(self) -> Result<(), anyhow::Error> {
+        if self.authorize(Capability::ViewBotBalance).await?.is_none() {
+            return Ok(());
+        }
+
         let balance_request = self.ctx.sms.clone().get_api_balance().await;
         match balance_request {
             Ok(balance) => {
-                let embed = EmbedBuilder::new()
-                    .title("Success")
-                    .description(format!("The total balance of the bot is `${:.2}`", balance))
-                    .color(self.ctx.config.success_color)
-                    .validate()?
-                    .build();
-                self.handle
-                    .reply(Reply::new().embed(embed).ephemeral())
-                    .await?;
-            }
-            Err(err) => {
-                let embed = EmbedBuilder::new()
-                    .title("Error")
-                    .description(err.errors.iter().next().unwrap().message.as_str())
-                    .color(self.ctx.config.error_color)
+                let embed = self
+                    .template_embed("bot_balance", &[("balance", &self.usd(balance))])
+                    .field(EmbedFieldBuilder::new(
+                        self.label("Time Left:"),
+                        match self.ctx.balance_monitor.hours_left() {
+                            Some(hours) => fill(
+                                &self.label("About `{hours}` hours at the recent rate"),
+                                &[("hours", &format!("{:.1}", hours))],
+                            ),
+                            None => self.label("Unknown, nothing was spent recently"),
+                        },
+                    ))
                     .validate()?
                     .build();
                 self.handle
                     .reply(Reply::new().embed(embed).ephemeral())
                     .await?;
             }
+            Err(err) => match err.errors.first() {
+                Some(error) => self.reply_error(error.message.as_str()).await?,
+                // The provider doesn't always say what went wrong
+                None => {
+                    tracing::error!("{:?}", err);
+                    self.reply_template("request_failed", &[]).await?;
+                }
+            },
         }
         Ok(())
     }
@@ -0,0 +1,51 @@
+use super::InteractionContext;
+use crate::{
+    api::{ApiError, Role},
+    permissions::Capability,
+};
+
+impl InteractionContext<'_> {
+    /// Check the user's role on the website for the capability, replying with
+    /// an error if they don't have it
+    ///
+    /// Returns the user's role if they may continue, the guild's permissions
+    /// for the command are not taken into account. Fails if the backend can't
+    /// be reached, rather than treating the user as one without a role
+    pub async fn authorize(&self, capability: Capability) -> Result<Option<Role>, anyhow::Error> {
+        let Some(user_id) = self.interaction.author_id() else {
+            return Ok(None);
+        };
+
+        let role = match self.ctx.api.get_user_data(user_id).await {
+            Ok(user) => user.role,
+            // Users without an account get the least access
+            Err(ApiError::Rejected(response)) => {
+                tracing::info!("Unable to get the role of {}: {:?}", user_id, response);
+                Role::User
+            }
+            Err(err) => {
+                self.reply_template("request_failed", &[]).await?;
+                return Err(err.into());
+            }
+        };
+
+        if role.can(capability) {
+            return Ok(Some(role));
+        }
+
+        tracing::warn!(
+            "Refused {} to {} with the {} role",
+            capability,
+            user_id,
+            role
+        );
+
+        self.reply_template(
+            "permission_denied",
+            &[("action", &self.label(&capability.to_string()))],
+        )
+        .await?;
+
+        Ok(None)
+    }
+}
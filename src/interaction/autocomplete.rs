@@ -0,0 +1,102 @@
+use anyhow::anyhow;
+use twilight_model::application::{
+    command::{CommandOptionChoice, CommandOptionChoiceValue},
+    interaction::{
+        application_command::{CommandDataOption, CommandOptionValue},
+        InteractionData,
+    },
+};
+
+use super::InteractionContext;
+use crate::{currencies::BASE_CURRENCY, logic::find_similar_services};
+
+/// Discord's limit on the number of autocomplete choices
+const MAX_CHOICES: usize = 25;
+
+impl InteractionContext<'_> {
+    pub async fn handle_autocomplete(self) -> Result<(), anyhow::Error> {
+        let Some(InteractionData::ApplicationCommand(data)) = &self.interaction.data else {
+            return Err(anyhow!("Autocomplete interaction without command data"));
+        };
+
+        let choices = match focused_option(&data.options) {
+            Some(("service", value)) => self.service_choices(value).await?,
+            Some(("currency", value)) => self.currency_choices(value),
+            _ => vec![],
+        };
+
+        self.handle.autocomplete(choices).await?;
+
+        Ok(())
+    }
+
+    /// Services matching the user's input, leaving out the ones the service
+    /// policy doesn't allow
+    async fn service_choices(&self, input: &str) -> Result<Vec<CommandOptionChoice>, anyhow::Error> {
+        let services = self
+            .ctx
+            .service_list()
+            .await
+            .map_err(|err| anyhow!("Unable to obtain service list: {:?}", err))?;
+
+        let names: Vec<String> = if input.trim().is_empty() {
+            services
+                .iter()
+                .filter(|service| service.favourite > 0)
+                .filter(|service| self.ctx.is_service_allowed(&service.name))
+                .map(|service| service.name.clone())
+                .collect()
+        } else {
+            find_similar_services(
+                input.trim(),
+                &services,
+                &self.ctx.blacklist(),
+                &self.ctx.config().service_matching,
+            )
+                .into_iter()
+                .map(|similar| similar.service_info.name)
+                .collect()
+        };
+
+        Ok(names
+            .into_iter()
+            .take(MAX_CHOICES)
+            .map(|name| CommandOptionChoice {
+                name: name.clone(),
+                name_localizations: None,
+                value: CommandOptionChoiceValue::String(name),
+            })
+            .collect())
+    }
+
+    /// The currencies starting with the user's input, USD first to go back to
+    /// showing only USD
+    fn currency_choices(&self, input: &str) -> Vec<CommandOptionChoice> {
+        let input = input.trim().to_ascii_uppercase();
+
+        let mut codes = vec![BASE_CURRENCY.to_string()];
+        codes.extend(self.ctx.rates().all().map(|currency| currency.code.clone()));
+        codes
+            .into_iter()
+            .filter(|code| code.starts_with(&input))
+            .take(MAX_CHOICES)
+            .map(|code| CommandOptionChoice {
+                name: code.clone(),
+                name_localizations: None,
+                value: CommandOptionChoiceValue::String(code),
+            })
+            .collect()
+    }
+}
+
+/// The name and current value of the option the user is typing in, looking
+/// inside subcommands
+fn focused_option(options: &[CommandDataOption]) -> Option<(&str, &str)> {
+    options.iter().find_map(|option| match &option.value {
+        CommandOptionValue::Focused(value, _) => Some((option.name.as_str(), value.as_str())),
+        CommandOptionValue::SubCommand(options) | CommandOptionValue::SubCommandGroup(options) => {
+            focused_option(options)
+        }
+        _ => None,
+    })
+}
@@ -1,8 +1,5 @@
-use sparkle_convenience::{error::IntoError, reply::Reply};
+use sparkle_convenience::error::IntoError;
 use twilight_interactions::command::{CommandModel, CreateCommand};
-use twilight_util::builder::embed::EmbedBuilder;
-
-use crate::api::get_user_data;
 
 use super::InteractionContext;
 
@@ -14,34 +11,16 @@ impl InteractionContext<'_> {
     pub async fn handle_balance_command(self) -> Result<(), anyhow::Error> {
         let user = self.interaction.author().ok()?;
 
-        let user_data = get_user_data(user.id).await;
+        let user_data = self.ctx.api.get_user_data(user.id).await;
 
         match user_data {
             Err(_) => {
-                let error_embed = EmbedBuilder::new()
-                    .title("Error")
-                    .color(self.ctx.config.error_color)
-                    .description(format!("No account could be found for **@{}**", self.interaction.author().unwrap().name))
-                    .validate()?
-                    .build();
-
-                self.handle
-                    .reply(Reply::new().embed(error_embed).ephemeral())
+                self.reply_template("no_account", &[("user", &user.name)])
                     .await?;
             }
             Ok(data) => {
-                let balance_embed = EmbedBuilder::new()
-                    .title("Success")
-                    .color(self.ctx.config.success_color)
-                    .description(format!(
-                        "Your balance is `${:.2} USD`",
-                        (data.balance as f32) / 100.00
-                    ))
-                    .validate()?
-                    .build();
-
-                self.handle
-                    .reply(Reply::new().embed(balance_embed).ephemeral())
+                let balance = self.usd(f64::from(data.balance) / 100.0);
+                self.reply_template("balance", &[("balance", &balance)])
                     .await?;
             }
         }
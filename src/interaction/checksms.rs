@@ -1,9 +1,12 @@
-use crate::api::{get_user_data, mark_number_received};
-use sparkle_convenience::reply::Reply;
+use sparkle_convenience::{error::IntoError, reply::Reply};
+use std::slice;
 use twilight_interactions::command::{CommandModel, CreateCommand};
-use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder};
+use twilight_model::channel::message::Embed;
+use twilight_util::builder::embed::EmbedFieldBuilder;
 
 use super::InteractionContext;
+use crate::preferences::CodeDelivery;
+use crate::ratelimit::LimitedAction;
 
 #[derive(CreateCommand, CommandModel, Debug)]
 #[command(
@@ -15,60 +18,41 @@ pub struct CheckSMSCommand {}
 
 impl InteractionContext<'_> {
     pub async fn handle_checksms_command(self) -> Result<(), anyhow::Error> {
-        let user_data_request = get_user_data(self.interaction.author_id().unwrap()).await;
-        if let Err(_) = user_data_request {
-            let no_user_embed = EmbedBuilder::new()
-                .title("Error")
-                .color(self.ctx.config.error_color)
-                .description(format!(
-                    "No user could be found for **@{}**",
-                    self.interaction.author().unwrap().name
-                ))
-                .validate()?
-                .build();
+        let user_data_request = self.ctx.api.get_user_data(self.interaction.author_id().unwrap()).await;
+        if user_data_request.is_err() {
+            return self
+                .reply_template("no_account", &[("user", &self.interaction.author().unwrap().name)])
+                .await;
+        }
+
+        let user_data = user_data_request.unwrap();
 
+        if let Some(cooldown_embed) = self.check_rate_limit(LimitedAction::CheckSms, user_data.role)? {
             self.handle
-                .reply(Reply::new().embed(no_user_embed).ephemeral())
+                .reply(Reply::new().embed(cooldown_embed).ephemeral())
                 .await?;
 
             return Ok(());
         }
 
-        let user_data = user_data_request.unwrap();
-        let user_number = user_data.numbers.into_iter().rev().into_iter().nth(0);
+        let user_number = user_data.numbers.into_iter().next_back();
 
         match user_number {
             None => {
-                let no_number_embed = EmbedBuilder::new()
-                    .title("Error")
-                    .color(self.ctx.config.error_color)
-                    .description(format!(
-                        "No number could be found for **@{}**",
-                        self.interaction.author().unwrap().name
-                    ))
-                    .validate()?
-                    .build();
-
-                self.handle
-                    .reply(Reply::new().embed(no_number_embed).ephemeral())
+                self.reply_template("no_number", &[("user", &self.interaction.author().unwrap().name)])
                     .await?;
-
-                return Ok(());
             }
             Some(number) => {
                 if user_data.balance < number.price {
-                    let no_balance_embed = EmbedBuilder::new()
-                        .title("Error")
-                        .color(self.ctx.config.error_color)
-                        .description(format!("You don't have enough balance to check for an sms code. Your balance is **{}** and the price for this number is **{}**", user_data.balance, number.price))
-                        .validate()?
-                        .build();
-
-                    self.handle
-                        .reply(Reply::new().embed(no_balance_embed).ephemeral())
-                        .await?;
-
-                    return Ok(());
+                    return self
+                        .reply_template(
+                            "checksms_insufficient_balance",
+                            &[
+                                ("balance", &self.usd(f64::from(user_data.balance) / 100.0)),
+                                ("price", &self.usd(f64::from(number.price) / 100.0)),
+                            ],
+                        )
+                        .await;
                 }
 
                 let sms_code_request = self.ctx.sms.clone().get_sms_code(&number.order_id).await;
@@ -77,30 +61,16 @@ impl InteractionContext<'_> {
                     Err(err) => {
                         tracing::error!("{:#?}", err);
 
-                        let sms_code_embed = EmbedBuilder::new()
-                            .title("Error")
-                            .color(self.ctx.config.error_color)
-                            .description(format!("An error occured while trying to get the sms code for **{}**. Please try again later.", number.number))
-                            .validate()?
-                            .build();
-
-                        self.handle
-                            .reply(Reply::new().embed(sms_code_embed).ephemeral())
+                        self.reply_template("sms_code_failed", &[("number", &number.number)])
                             .await?;
-
-                        return Ok(());
                     }
                     Ok(sms_code) => {
                         match sms_code.status {
                             1 | 2 | 4 => {
-                                let no_incomming_embed = EmbedBuilder::new()
-                                    .title("Pending")
-                                    .color(self.ctx.config.success_color)
-                                    .description(format!("Incoming texts to +{}:\n```glsl\nMessage Not Received\n```", number.number))
-                                    .field(EmbedFieldBuilder::new("Still Waiting?", "If you requested an SMS code and you have not 
-                                            received it, remember it can take up to 5 minutes to receive the SMS code. You will not be
-                                            charged until you receive a message"))
-                                    .field(EmbedFieldBuilder::new("Number Expiration:", format!("<t:{}:R>", sms_code.expiration)))
+                                let no_incomming_embed = self
+                                    .template_embed("sms_pending", &[("number", &number.number)])
+                                    .field(EmbedFieldBuilder::new(self.label("Still Waiting?"), self.label("If you requested an SMS code and you have not received it, remember it can take up to 5 minutes to receive the SMS code. You will not be charged until you receive a message")))
+                                    .field(EmbedFieldBuilder::new(self.label("Number Expiration:"), format!("<t:{}:R>", sms_code.expiration)))
                                     .validate()?
                                     .build();
 
@@ -109,61 +79,50 @@ impl InteractionContext<'_> {
                                     .await?;
                             }
                             3 => {
+                                self.ctx.active_numbers.remove(self.interaction.author_id().unwrap(), &number.order_id);
+
                                 if !number.received {
                                     if let Err(err) =
-                                        mark_number_received(number.number.clone(), self.interaction.author_id().unwrap().to_string()).await
+                                        self.ctx.api.mark_number_received(number.number.clone(), self.interaction.author_id().unwrap().to_string()).await
                                     {
                                         tracing::error!("{:#?}", err);
 
-                                        let db_err_embed = EmbedBuilder::new()
-                                            .title("Error")
-                                            .color(self.ctx.config.error_color)
-                                            .description("An error occured while processing your request. Please try again later.")
-                                            .validate()?
-                                            .build();
-
-                                        self.handle
-                                            .reply(Reply::new().embed(db_err_embed).ephemeral())
-                                            .await?;
-
-                                        return Ok(());
+                                        return self.reply_template("request_failed", &[]).await;
                                     }
                                 }
 
-                                let log_embed = EmbedBuilder::new()
-                                    .title("2fa Code Received")
-                                    .color(self.ctx.config.success_color)
-                                    .description(format!(
-                                        "**@{}** has just received a 2fa code.",
-                                        self.interaction.author().unwrap().name
-                                    ))
+                                // The log channel is shared, so it stays in English
+                                let log_embed = self
+                                    .ctx
+                                    .template_embed(
+                                        None,
+                                        "sms_received_log",
+                                        &[("user", &self.interaction.author().unwrap().name)],
+                                    )
                                     .field(EmbedFieldBuilder::new("Service:", number.service))
                                     .field(EmbedFieldBuilder::new("Country:", number.country))
                                     .validate()?
                                     .build();
 
-                                let _ = self
-                                    .ctx
-                                    .bot
-                                    .http
-                                    .create_message(self.ctx.config.log_channel)
-                                    .embeds(&vec![log_embed]);
-
-                                let sms_embed = EmbedBuilder::new()
-                                    .title("Success")
-                                    .color(self.ctx.config.success_color)
-                                    .description(format!(
-                                        "Incoming texts to +{}:\n```glsl\n{}\n```",
-                                        number.number,
-                                        sms_code.full_sms.unwrap()
-                                    ))
+                                if self.ctx.config().features.log_channel_messages {
+                                    self.ctx.send_log(log_embed).await;
+                                }
+
+                                let sms_embed = self
+                                    .template_embed(
+                                        "sms_received",
+                                        &[
+                                            ("number", &number.number),
+                                            ("message", &sms_code.full_sms.unwrap()),
+                                        ],
+                                    )
                                     .field(
-                                        EmbedFieldBuilder::new("SMS Code:", sms_code.sms.unwrap())
+                                        EmbedFieldBuilder::new(self.label("SMS Code:"), sms_code.sms.unwrap())
                                             .inline(),
                                     )
                                     .field(
                                         EmbedFieldBuilder::new(
-                                            "Expires:",
+                                            self.label("Expires:"),
                                             format!("<t:{}:R>", sms_code.expiration),
                                         )
                                         .inline(),
@@ -171,30 +130,57 @@ impl InteractionContext<'_> {
                                     .validate()?
                                     .build();
 
+                                if self.preferences.code_delivery == CodeDelivery::Dm {
+                                    match self.send_dm(&sms_embed).await {
+                                        Ok(()) => {
+                                            return self
+                                                .reply_template("sms_sent_to_dms", &[("number", &number.number)])
+                                                .await;
+                                        }
+                                        Err(err) => {
+                                            tracing::info!("Unable to DM {} their code: {}", self.interaction.author_id().unwrap(), err);
+                                        }
+                                    }
+                                }
+
                                 self.handle
                                     .reply(Reply::new().embed(sms_embed).ephemeral())
                                     .await?;
                             }
 
                             _ => {
-                                let expired_embed = EmbedBuilder::new()
-                                    .title("Error")
-                                    .color(self.ctx.config.error_color)
-                                    .description(
-                                        "Your number has expired. Please generate a new one",
-                                    )
-                                    .validate()?
-                                    .build();
+                                self.ctx.active_numbers.remove(self.interaction.author_id().unwrap(), &number.order_id);
 
-                                self.handle
-                                    .reply(Reply::new().embed(expired_embed).ephemeral())
-                                    .await?;
+                                self.reply_template("number_expired", &[]).await?;
                             }
                         };
-                        return Ok(());
                     }
                 }
             }
         }
+
+        Ok(())
+    }
+
+    /// Send the embed to the user in their DMs, which fails if they don't
+    /// accept DMs from the bot
+    async fn send_dm(&self, embed: &Embed) -> Result<(), anyhow::Error> {
+        let channel = self
+            .ctx
+            .bot
+            .http
+            .create_private_channel(self.interaction.author_id().ok()?)
+            .await?
+            .model()
+            .await?;
+
+        self.ctx
+            .bot
+            .http
+            .create_message(channel.id)
+            .embeds(slice::from_ref(embed))?
+            .await?;
+
+        Ok(())
     }
 }
@@ -0,0 +1,76 @@
+use sparkle_convenience::{error::IntoError, interaction::extract::InteractionDataExt};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+
+use super::InteractionContext;
+use crate::currencies::BASE_CURRENCY;
+
+#[derive(CreateCommand, CommandModel, Debug)]
+#[command(
+    name = "currency",
+    desc = "show prices in another currency next to USD"
+)]
+pub struct CurrencyCommand {
+    #[command(
+        desc = "the currency code, such as EUR, or USD to only show USD",
+        autocomplete = true
+    )]
+    pub currency: Option<String>,
+}
+
+impl InteractionContext<'_> {
+    pub async fn handle_currency_command(self) -> Result<(), anyhow::Error> {
+        let options = CurrencyCommand::from_interaction(
+            self.interaction.data.clone().ok()?.command().ok()?.into(),
+        )?;
+        let user = self.interaction.author().ok()?;
+        let rates = self.ctx.rates();
+
+        let Some(code) = options
+            .currency
+            .map(|code| code.trim().to_ascii_uppercase())
+        else {
+            return match self.display_currency() {
+                Some(currency) => {
+                    self.reply_template(
+                        "display_currency",
+                        &[("currency", &currency.code), ("rate", &currency.rate)],
+                    )
+                    .await
+                }
+                None => self.reply_template("display_currency_none", &[]).await,
+            };
+        };
+
+        if code == BASE_CURRENCY {
+            self.ctx
+                .preferences_mut()
+                .update(user.id, |preferences| preferences.currency = None)?;
+            return self.reply_template("display_currency_none", &[]).await;
+        }
+
+        let Some(currency) = rates.get(&code) else {
+            let currencies = rates
+                .all()
+                .map(|currency| format!("`{}`", currency.code))
+                .collect::<Vec<_>>();
+            return if currencies.is_empty() {
+                self.reply_template("currencies_unavailable", &[]).await
+            } else {
+                self.reply_template(
+                    "unknown_currency",
+                    &[("currency", &code), ("currencies", &currencies.join(", "))],
+                )
+                .await
+            };
+        };
+
+        self.ctx.preferences_mut().update(user.id, |preferences| {
+            preferences.currency = Some(currency.code.clone());
+        })?;
+        self.reply_template(
+            "display_currency",
+            &[("currency", &currency.code), ("rate", &currency.rate)],
+        )
+        .await
+    }
+}
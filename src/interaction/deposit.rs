@@ -0,0 +1,114 @@
+use sparkle_convenience::{
+    error::IntoError,
+    interaction::{extract::InteractionDataExt, DeferVisibility},
+    reply::Reply,
+};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::channel::message::{
+    component::{ActionRow, Button, ButtonStyle},
+    Component,
+};
+use twilight_util::builder::embed::EmbedFieldBuilder;
+
+use super::InteractionContext;
+use crate::pending_deposits::PendingDeposit;
+
+#[derive(CreateCommand, CommandModel, Debug)]
+#[command(name = "deposit", desc = "add funds to your balance", dm_permission = false)]
+pub struct DepositCommand {
+    #[command(desc = "the amount to deposit in USD")]
+    pub amount: f64,
+}
+
+impl InteractionContext<'_> {
+    pub async fn handle_deposit_command(self) -> Result<(), anyhow::Error> {
+        let options = DepositCommand::from_interaction(
+            self.interaction.data.clone().ok()?.command().ok()?.into(),
+        )?;
+        let user = self.interaction.author().ok()?;
+
+        self.handle.defer(DeferVisibility::Ephemeral).await?;
+
+        let Ok(user_data) = self.ctx.api.get_user_data(user.id).await else {
+            return self
+                .reply_template("no_account", &[("user", &user.name)])
+                .await;
+        };
+
+        let (min_amount, max_amount) = {
+            let config = self.ctx.config();
+            (config.deposits.min_amount, config.deposits.max_amount)
+        };
+        let amount = (options.amount * 100.0).round() / 100.0;
+        if !(min_amount..=max_amount).contains(&amount) {
+            return self
+                .reply_template(
+                    "deposit_out_of_range",
+                    &[
+                        ("min_amount", &self.usd(min_amount)),
+                        ("max_amount", &self.usd(max_amount)),
+                    ],
+                )
+                .await;
+        }
+
+        let invoice = match self.ctx.api.create_invoice(user_data.id, amount).await {
+            Ok(invoice) => invoice,
+            Err(err) => {
+                tracing::error!("{:#?}", err);
+                return self.reply_template("deposit_failed", &[]).await;
+            }
+        };
+        let Some(url) = invoice.url.clone() else {
+            tracing::error!("Invoice {} has no payment link", invoice.id);
+            return self.reply_template("deposit_failed", &[]).await;
+        };
+
+        let deposit = PendingDeposit {
+            invoice_id: invoice.id.clone(),
+            user_id: user.id,
+            amount,
+            created_at: chrono::Utc::now().timestamp(),
+            interaction_token: self.interaction.token.clone(),
+            locale: self.locale().map(str::to_string),
+        };
+        if let Err(err) = self.ctx.pending_deposits().insert(deposit) {
+            // The invoice can still be paid, the user just won't be notified
+            tracing::error!("Failed to save pending deposit {}: {}", invoice.id, err);
+        }
+
+        let deposit_embed = self
+            .template_embed("deposit", &[])
+            .field(
+                EmbedFieldBuilder::new(self.label("Amount:"), format!("`{}`", self.usd(amount)))
+                    .inline(),
+            )
+            .field(
+                EmbedFieldBuilder::new(self.label("Invoice:"), format!("`{}`", invoice.id))
+                    .inline(),
+            )
+            .validate()?
+            .build();
+        let pay_button = Component::ActionRow(ActionRow {
+            components: vec![Component::Button(Button {
+                custom_id: None,
+                disabled: false,
+                emoji: None,
+                label: Some(self.label("Pay")),
+                style: ButtonStyle::Link,
+                url: Some(url),
+            })],
+        });
+
+        self.handle
+            .reply(
+                Reply::new()
+                    .embed(deposit_embed)
+                    .component(pay_button)
+                    .ephemeral(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use sparkle_convenience::{error::IntoError, interaction::DeferVisibility, reply::Reply};
+use std::fmt::Write;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{channel::message::Embed, user::User};
+use twilight_util::builder::embed::EmbedFieldBuilder;
+
+use super::InteractionContext;
+use crate::{api::InvoiceStatus, templates::fill};
+
+/// How many of the latest invoices are listed, the totals include every one
+const MAX_LISTED: usize = 15;
+
+#[derive(CreateCommand, CommandModel, Debug)]
+#[command(name = "deposits", desc = "see your deposits", dm_permission = false)]
+pub struct DepositsCommand;
+
+impl InteractionContext<'_> {
+    pub async fn handle_deposits_command(self) -> Result<(), anyhow::Error> {
+        let user = self.interaction.author().ok()?;
+
+        self.handle.defer(DeferVisibility::Ephemeral).await?;
+
+        let embed = self.deposits_embed(user).await?;
+        self.handle
+            .reply(Reply::new().embed(embed).ephemeral())
+            .await?;
+
+        Ok(())
+    }
+
+    /// The user's invoices, newest first, and their totals by status
+    pub async fn deposits_embed(&self, user: &User) -> Result<Embed, anyhow::Error> {
+        let Ok(user_data) = self.ctx.api.get_user_data(user.id).await else {
+            return self.render_template("no_account", &[("user", &user.name)]);
+        };
+
+        if user_data.invoices.is_empty() {
+            return self.render_template("no_deposits", &[("user", &user.name)]);
+        }
+
+        let mut invoices = user_data.invoices;
+        invoices.sort_by_key(|invoice| std::cmp::Reverse(invoice.created_at()));
+
+        let unknown_method = self.label("Unknown method");
+        let mut listed = String::new();
+        for invoice in invoices.iter().take(MAX_LISTED) {
+            writeln!(
+                listed,
+                "`{}` | {} | {} | {}",
+                self.usd(invoice.amount),
+                self.label(&invoice.status.to_string()),
+                invoice.method.as_deref().unwrap_or(&unknown_method),
+                match invoice.created_at() {
+                    Some(created_at) => format!("<t:{}:f>", created_at),
+                    None => "`N/A`".to_string(),
+                }
+            )?;
+        }
+        if invoices.len() > MAX_LISTED {
+            let older = fill(
+                &self.label("… and `{count}` older ones"),
+                &[("count", &(invoices.len() - MAX_LISTED))],
+            );
+            writeln!(listed, "{}", older)?;
+        }
+
+        let mut embed =
+            self.template_embed("deposits", &[("user_id", &user.id), ("invoices", &listed)]);
+        for status in [
+            InvoiceStatus::Completed,
+            InvoiceStatus::Pending,
+            InvoiceStatus::Expired,
+            InvoiceStatus::Cancelled,
+            InvoiceStatus::Unknown,
+        ] {
+            let (count, total) = invoices
+                .iter()
+                .filter(|invoice| invoice.status == status)
+                .fold((0, 0.0), |(count, total), invoice| (count + 1, total + invoice.amount));
+            if count > 0 {
+                embed = embed.field(
+                    EmbedFieldBuilder::new(
+                        self.label(&format!("{}:", status)),
+                        format!(
+                            "`{}` {} `{}`",
+                            count,
+                            self.label("totaling"),
+                            self.usd(total)
+                        ),
+                    )
+                    .inline(),
+                );
+            }
+        }
+
+        Ok(embed.validate()?.build())
+    }
+}
@@ -1,12 +1,15 @@
-use crate::api::{get_user_data, post_user_number};
-use crate::logic::{find_similar_countries, find_similar_services};
+use crate::api::OrderPrice;
+use crate::logic::{find_similar_countries, find_similar_services, resolve_alias};
+use crate::money;
+use crate::policy::ServiceAction;
+use crate::ratelimit::LimitedAction;
 use crate::sms::get_country_prices::CountryPriceInfo;
 use sparkle_convenience::interaction::DeferVisibility;
 use sparkle_convenience::{
     error::IntoError, interaction::extract::InteractionDataExt, reply::Reply,
 };
 use twilight_interactions::command::{CommandModel, CreateCommand};
-use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder};
+use twilight_util::builder::embed::EmbedFieldBuilder;
 
 use super::InteractionContext;
 
@@ -17,9 +20,12 @@ use super::InteractionContext;
     dm_permission = false
 )]
 pub struct GetNumberCommand {
-    #[command(desc = "the service to use the number for", min_length = 2)]
+    #[command(desc = "the service to use the number for", min_length = 2, autocomplete = true)]
     pub service: String,
-    #[command(desc = "the country the number should be from", min_length = 2)]
+    #[command(
+        desc = "the country the number should be from, your default country from /settings if left out",
+        min_length = 2
+    )]
     pub country: Option<String>,
 }
 
@@ -31,8 +37,40 @@ impl InteractionContext<'_> {
 
         self.handle.defer(DeferVisibility::Ephemeral).await.unwrap();
 
-        let service = options.service;
-        let country = options.country.unwrap_or(String::new());
+        // The provider only knows services by their name, not their aliases
+        let services = self.ctx.service_list().await.unwrap_or_default();
+        let service = resolve_alias(
+            &options.service,
+            &services,
+            &self.ctx.config().service_matching,
+        )
+        .unwrap_or(options.service);
+        let country = options
+            .country
+            .or_else(|| self.preferences.country.clone())
+            .unwrap_or_default();
+
+        if let Some(refusal_embed) = self
+            .ctx
+            .enforce_service_policy(
+                self.interaction.author(),
+                self.locale(),
+                &service,
+                ServiceAction::Order,
+            )
+            .await?
+        {
+            self.handle
+                .reply(Reply::new().embed(refusal_embed).ephemeral())
+                .await?;
+
+            return Ok(());
+        }
+
+        // The monitor only forgets the pause on its next tick after being turned off
+        if self.ctx.config().balance_monitor.enabled && self.ctx.balance_monitor.is_paused() {
+            return self.reply_template("orders_paused", &[]).await;
+        }
 
         let country_prices = self
             .ctx
@@ -41,51 +79,39 @@ impl InteractionContext<'_> {
             .get_country_prices(service.as_str())
             .await
             .unwrap_or([].to_vec());
+        self.ctx.country_codes.extend(&country_prices);
 
         // If service is invalid and request for prices fails
         if country_prices.is_empty() {
-            let sms_services = self.ctx.sms.clone().get_service_list().await.unwrap();
+            let sms_services = self.ctx.sms.clone().get_service_list().await.unwrap_or_default();
             if sms_services.is_empty() {
-                let request_error_embed = EmbedBuilder::new()
-                    .title("Error")
-                    .color(self.ctx.config.error_color)
-                    .description("An error occured when making your request.")
-                    .validate()?
-                    .build();
-
-                self.handle
-                    .reply(Reply::new().embed(request_error_embed).ephemeral())
-                    .await?;
+                self.reply_template("service_list_failed", &[]).await?;
 
                 tracing::error!("Unable to obtain service list");
                 return Ok(());
             }
 
-            let similar_services = find_similar_services(service.as_str(), &sms_services);
+            let config = self.ctx.config();
+            let similar_services = if config.features.service_suggestions {
+                find_similar_services(
+                    service.as_str(),
+                    &sms_services,
+                    &self.ctx.blacklist(),
+                    &config.service_matching,
+                )
+            } else {
+                vec![]
+            };
 
             if similar_services.is_empty() {
-                let no_services_embed = EmbedBuilder::new()
-                    .title("Error")
-                    .color(self.ctx.config.error_color)
-                    .description(format!(
-                        "No services similar to **{}** could be found.",
-                        service
-                    ))
-                    .validate()?
-                    .build();
-
-                self.handle
-                    .reply(Reply::new().embed(no_services_embed).ephemeral())
-                    .await?;
-
-                return Ok(());
+                return self
+                    .reply_template("no_similar_services", &[("service", &service)])
+                    .await;
             }
 
-            let mut embed_desc =
-                "The service you provided was invalid. Here are some similar ones that you might be interested in:\n".to_string();
-
+            let mut services = String::new();
             for (i, s) in similar_services.iter().enumerate() {
-                embed_desc += format!(
+                services += format!(
                     "**{}:** {} | `{}%`\n",
                     i + 1,
                     s.service_info.name,
@@ -94,19 +120,55 @@ impl InteractionContext<'_> {
                 .as_str();
             }
 
-            let invalid_service_error = EmbedBuilder::new()
-                .title("Error")
-                .color(self.ctx.config.error_color)
-                .description(embed_desc)
-                .validate()?
-                .build();
+            return self
+                .reply_template("similar_services", &[("services", &services)])
+                .await;
+        }
+
+        let user_data_request = self.ctx.api.get_user_data(self.interaction.author_id().unwrap())
+            .await;
+
+        if user_data_request.is_err() {
+            return self
+                .reply_template("no_account", &[("user", &self.interaction.author().unwrap().name)])
+                .await;
+        }
+
+        let user_data = user_data_request.unwrap();
+
+        // Ordering buys the number from the provider, so users who can't pay
+        // for it are turned away before, the cheapest country is ordered if
+        // none is given
+        let expected_price = country_prices
+            .iter()
+            .filter(|c| {
+                country.trim().is_empty()
+                    || c.name.eq_ignore_ascii_case(country.trim())
+                    || c.iso.eq_ignore_ascii_case(country.trim())
+            })
+            .map(|c| c.price)
+            .min_by(f32::total_cmp);
+        if let Some(price) = expected_price {
+            if user_data.balance < (price * 100.00 * self.ctx.config().pricing.multiplier) as i32 {
+                let balance = self.usd(f64::from(user_data.balance) / 100.0);
+                return self
+                    .reply_template("insufficient_funds", &[("balance", &balance)])
+                    .await;
+            }
+        }
+
+        let cooldown_embed = match self.check_active_numbers(user_data.role)? {
+            Some(embed) => Some(embed),
+            None => self.check_rate_limit(LimitedAction::Order, user_data.role)?,
+        };
+        if let Some(cooldown_embed) = cooldown_embed {
             self.handle
-                .reply(Reply::new().embed(invalid_service_error).ephemeral())
+                .reply(Reply::new().embed(cooldown_embed).ephemeral())
                 .await?;
 
             return Ok(());
         }
-        
+
         let number_info = self
             .ctx
             .sms
@@ -117,35 +179,19 @@ impl InteractionContext<'_> {
         match number_info {
             Err(err) => {
                 tracing::error!("{:#?}", err);
+                self.refund_rate_limit(LimitedAction::Order, user_data.role);
                 if err.error_type == "COUNTRY_NOT_AVAILABLE_FOR_SERVICE" {
                     let supported_countries = country_prices.clone();
                     let similar_countries =
                         find_similar_countries(country.as_str(), &supported_countries);
     
                     if similar_countries.is_empty() {
-                        let no_countries_embed = EmbedBuilder::new()
-                            .title("Error")
-                            .color(self.ctx.config.error_color)
-                            .description(format!(
-                                "No countries similar to **{}** could be found",
-                                country
-                            ))
-                            .validate()?
-                            .build();
-    
-                        self.handle
-                            .reply(Reply::new().embed(no_countries_embed).ephemeral())
+                        self.reply_template("no_similar_countries", &[("country", &country)])
                             .await?;
                     } else {
-                        let mut embed_desc = format!(
-                            "Out of `{}` countries supporting this product, `{}` matched your input of **{}**.\n\n",
-                            supported_countries.len(),
-                            similar_countries.len(),
-                            country
-                        );
-    
+                        let mut countries = String::new();
                         for (i, c) in similar_countries.iter().enumerate() {
-                            embed_desc += format!(
+                            countries += format!(
                                 "**{}:** {} | `{}%`\n",
                                 i + 1,
                                 c.country_info.name,
@@ -154,71 +200,41 @@ impl InteractionContext<'_> {
                             .as_str();
                         }
     
-                        let similar_countries_embed = EmbedBuilder::new()
-                            .title("Success")
-                            .color(self.ctx.config.success_color)
-                            .description(embed_desc)
-                            .validate()?
-                            .build();
+                        let similar_countries_embed = self.render_template(
+                            "similar_countries",
+                            &[
+                                ("total", &supported_countries.len()),
+                                ("matched", &similar_countries.len()),
+                                ("country", &country),
+                                ("countries", &countries),
+                            ],
+                        )?;
     
                         self.handle
                             .reply(Reply::new().embed(similar_countries_embed))
                             .await?;
                     }
                 } else if err.error_type == "OUT_OF_STOCK" {
-                    let invalid_response_embed = EmbedBuilder::new()
-                        .title("Error")
-                        .color(self.ctx.config.error_color)
-                        .description("We are currently out of stock of numbers from the country you tried to order. Please try a different country or try again later!")
-                        .validate()?
-                        .build();
-
-                    self.handle
-                        .reply(Reply::new().embed(invalid_response_embed).ephemeral())
-                        .await?;
+                    self.reply_template("out_of_stock", &[]).await?;
                 } else {
-                    let invalid_response_embed = EmbedBuilder::new()
-                        .title("Error")
-                        .color(self.ctx.config.error_color)
-                        .description("An error occurred while processing your request. Please try again later.")
-                        .validate()?
-                        .build();
-
-                    self.handle
-                        .reply(Reply::new().embed(invalid_response_embed).ephemeral())
-                        .await?;
+                    self.reply_template("request_failed", &[]).await?;
                 }
             }
             Ok(info) => {
-                let user_data_request = get_user_data(self.interaction.author_id().unwrap())
-                    .await;
-
-                if let Err(_) = user_data_request {
-                    let no_user_embed = EmbedBuilder::new()
-                        .title("Error")
-                        .color(self.ctx.config.error_color)
-                        .description(format!("No account could be found for **@{}**", self.interaction.author().unwrap().name))
-                        .validate()?
-                        .build();
-
-                    self.handle.reply(Reply::new().embed(no_user_embed).ephemeral()).await?;
-
-                    return Ok(())
-                }
-
-                let user_data = user_data_request.unwrap();
-
-                if user_data.balance < (info.cost * 100.00 * self.ctx.config.price_multiplier) as i32 {
-                    let insufficient_funds_embed = EmbedBuilder::new()
-                        .title("Error")
-                        .color(self.ctx.config.error_color)
-                        .description(format!("You do not have enough funds to purchase this number. Your balance is `${:.2} USD`", (user_data.balance as f32) / 100.00))
-                        .validate()?
-                        .build();
-
-                    self.handle.reply(Reply::new().embed(insufficient_funds_embed).ephemeral()).await?;
-
-                    return Ok(())
+                // The base cost is kept with the order, the multiplier may
+                // change before its receipt is asked for
+                let price = OrderPrice {
+                    price: (info.cost * 100.00 * self.ctx.config().pricing.multiplier) as i32,
+                    base_cost: (info.cost * 100.00) as i32,
+                };
+
+                // The number was bought, so the order still counts towards
+                // the rate limit
+                if user_data.balance < price.price {
+                    let balance = self.usd(f64::from(user_data.balance) / 100.0);
+                    return self
+                        .reply_template("insufficient_funds", &[("balance", &balance)])
+                        .await;
                 }
 
                 let country_price_info: CountryPriceInfo = country_prices
@@ -227,39 +243,41 @@ impl InteractionContext<'_> {
                 .cloned()
                 .unwrap();
 
-                let sms_number = post_user_number(
+                let sms_number = self.ctx.api.post_user_number(
                     &info.number.to_string(),
                     &info.service,
                     &info.country,
-                    (info.cost * 100.00 * self.ctx.config.price_multiplier) as i32,
+                    price,
                     &info.order_id,
                     user_data.id,
                 )
                 .await;
 
                 match sms_number {
+                    // The number was bought, so the order still counts
+                    // towards the rate limit
                     Err(err) => {
                         tracing::error!("{:#?}", err);
-                        let error_embed = EmbedBuilder::new()
-                            .title("Error")
-                            .color(self.ctx.config.error_color)
-                            .description("An error occurred while processing your request. Please try again later.")
-                            .validate()?
-                            .build();
-
-                        self.handle
-                            .reply(Reply::new().embed(error_embed).ephemeral())
-                            .await?;
+                        self.reply_template("request_failed", &[]).await?;
                     }
                     Ok(_) => {
-                        let log_embed = EmbedBuilder::new()
-                            .title("Number Generated")
-                            .color(self.ctx.config.success_color)
-                            .description(format!(
-                                "**@{}** | `{}` has just generated a number",
-                                self.interaction.author().unwrap().name,
-                                self.interaction.author_id().unwrap()
-                            ))
+                        self.ctx.active_numbers.insert(
+                            self.interaction.author_id().unwrap(),
+                            info.order_id.clone(),
+                            info.expiration,
+                        );
+
+                        // The log channel is shared, so it stays in English
+                        let log_embed = self
+                            .ctx
+                            .template_embed(
+                                None,
+                                "number_ordered_log",
+                                &[
+                                    ("user", &self.interaction.author().unwrap().name),
+                                    ("user_id", &self.interaction.author_id().unwrap()),
+                                ],
+                            )
                             .field(
                                 EmbedFieldBuilder::new("Service:", &info.service).inline(),
                             )
@@ -278,8 +296,8 @@ impl InteractionContext<'_> {
                                 EmbedFieldBuilder::new(
                                     "Message rate:",
                                     format!(
-                                        "`${:.2} / sms`",
-                                        &info.cost * self.ctx.config.price_multiplier
+                                        "`{} / sms`",
+                                        money::usd(info.cost * self.ctx.config().pricing.multiplier, None)
                                     ),
                                 )
                                 .inline(),
@@ -287,24 +305,30 @@ impl InteractionContext<'_> {
                             .validate()?
                             .build();
 
-                        let _ = self.ctx.bot.http.create_message(self.ctx.config.log_channel).embeds(&vec![log_embed]).unwrap();
+                        if self.ctx.config().features.log_channel_messages {
+                            self.ctx.send_log(log_embed).await;
+                        }
 
-                        let number_embed = EmbedBuilder::new()
-                            .title("Success")
-                            .color(self.ctx.config.success_color)
-                            .description(format!(
-                                    "You will only be charged once a message has been received.```py\n+{} {}\n```",
-                                    &info.area_code, &info.phonenumber))
-                            .field(EmbedFieldBuilder::new("Service:", 
+                        let number_embed = self
+                            .template_embed(
+                                "number_ordered",
+                                &[
+                                    ("area_code", &info.area_code),
+                                    ("phone_number", &info.phonenumber),
+                                ],
+                            )
+                            .field(EmbedFieldBuilder::new(self.label("Service:"), 
                                     &info.service).inline())
-                            .field(EmbedFieldBuilder::new("Country:", format!("{}  :flag_{}:", 
+                            .field(EmbedFieldBuilder::new(self.label("Country:"), format!("{}  :flag_{}:", 
                                         &info.country, &country_price_info.iso.to_lowercase())).inline())
-                            .field(EmbedFieldBuilder::new("Message rate:", format!("`${:.2} / sms`",
-                                        &info.cost * self.ctx.config.price_multiplier)).inline())
-                            .field(EmbedFieldBuilder::new("Number:", &info.number.to_string()).inline())
-                            .field(EmbedFieldBuilder::new("Expires:", format!("<t:{}:R>", &info.expiration)).inline())
-                            .field(EmbedFieldBuilder::new("Balance:", format!("`${:.2} USD`", 
-                                        (user_data.balance as f32) / 100.00)).inline()) 
+                            .field(EmbedFieldBuilder::new(self.label("Message rate:"), format!("`{} / sms`",
+                                        self.price(info.cost * self.ctx.config().pricing.multiplier))).inline())
+                            .field(EmbedFieldBuilder::new(self.label("Number:"), info.number.to_string()).inline())
+                            .field(EmbedFieldBuilder::new(self.label("Expires:"), format!("<t:{}:R>", &info.expiration)).inline())
+                            .field(EmbedFieldBuilder::new(self.label("Balance:"), format!("`{}`", 
+                                        self.usd(f64::from(user_data.balance) / 100.0))).inline());
+                        let number_embed = self
+                            .with_billing_note(number_embed)
                             .validate()?
                             .build();
 
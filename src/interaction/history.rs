@@ -0,0 +1,425 @@
+use anyhow::anyhow;
+use sparkle_convenience::{
+    error::IntoError,
+    interaction::{
+        extract::{InteractionDataExt, InteractionExt},
+        DeferBehavior, DeferVisibility,
+    },
+    reply::Reply,
+};
+use std::collections::HashSet;
+use twilight_interactions::command::{CommandModel, CommandOption, CreateCommand, CreateOption};
+use twilight_model::{
+    channel::message::{
+        component::{ActionRow, Button, ButtonStyle, SelectMenu, SelectMenuOption},
+        Component, Embed,
+    },
+    id::{marker::UserMarker, Id},
+};
+use twilight_util::builder::embed::EmbedFieldBuilder;
+
+use super::InteractionContext;
+use crate::{api::Number, templates::fill};
+
+/// The custom id prefix of the page buttons and the order menu
+pub const COMPONENT_PREFIX: &str = "history";
+const PAGE_SIZE: usize = 10;
+
+#[derive(CommandOption, CreateOption, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryStatus {
+    #[option(name = "received", value = "received")]
+    Received,
+    #[option(name = "pending", value = "pending")]
+    Pending,
+}
+
+#[derive(CreateCommand, CommandModel, Debug)]
+#[command(name = "history", desc = "see the numbers you ordered", dm_permission = false)]
+pub struct HistoryCommand {
+    #[command(desc = "only show orders for this service", max_length = 32)]
+    pub service: Option<String>,
+    #[command(desc = "only show orders that received a code or didn't")]
+    pub status: Option<HistoryStatus>,
+}
+
+/// The filters and page being shown, kept in the custom id of the page
+/// buttons
+#[derive(Debug, Default)]
+struct HistoryQuery {
+    service: Option<String>,
+    status: Option<HistoryStatus>,
+    page: usize,
+}
+
+impl HistoryQuery {
+    fn matches(&self, number: &Number) -> bool {
+        let service_matches = self.service.as_ref().is_none_or(|service| {
+            number
+                .service
+                .to_lowercase()
+                .contains(&service.to_lowercase())
+        });
+        let status_matches = match self.status {
+            Some(HistoryStatus::Received) => number.received,
+            Some(HistoryStatus::Pending) => !number.received,
+            None => true,
+        };
+
+        service_matches && status_matches
+    }
+
+    fn page_custom_id(&self, user_id: Id<UserMarker>, page: usize) -> String {
+        format!(
+            "{}:page:{}:{}:{}:{}",
+            COMPONENT_PREFIX,
+            user_id,
+            page,
+            match self.status {
+                Some(HistoryStatus::Received) => "received",
+                Some(HistoryStatus::Pending) => "pending",
+                None => "",
+            },
+            self.service.as_deref().unwrap_or_default()
+        )
+    }
+
+    /// Parse what comes after `history:page:`
+    fn parse(args: &str) -> Option<(Id<UserMarker>, Self)> {
+        let mut args = args.splitn(4, ':');
+        let user_id = args.next()?.parse().ok()?;
+        let page = args.next()?.parse().ok()?;
+        let status = match args.next()? {
+            "received" => Some(HistoryStatus::Received),
+            "pending" => Some(HistoryStatus::Pending),
+            _ => None,
+        };
+        let service = Some(args.next()?.to_string()).filter(|service| !service.is_empty());
+
+        Some((
+            user_id,
+            Self {
+                service,
+                status,
+                page,
+            },
+        ))
+    }
+}
+
+impl InteractionContext<'_> {
+    pub async fn handle_history_command(self) -> Result<(), anyhow::Error> {
+        let options = HistoryCommand::from_interaction(
+            self.interaction.data.clone().ok()?.command().ok()?.into(),
+        )?;
+
+        self.handle.defer(DeferVisibility::Ephemeral).await?;
+
+        let query = HistoryQuery {
+            service: options.service.map(|service| service.trim().to_string()),
+            status: options.status,
+            page: 0,
+        };
+        let reply = self.history_page(query).await?;
+        self.handle.reply(reply.ephemeral()).await?;
+
+        Ok(())
+    }
+
+    /// Handle the page buttons and the order menu
+    pub async fn handle_history_component(self) -> Result<(), anyhow::Error> {
+        let custom_id = self
+            .interaction
+            .name()
+            .ok_or_else(|| anyhow!("No custom id"))?
+            .to_string();
+        let args = custom_id
+            .strip_prefix(COMPONENT_PREFIX)
+            .and_then(|rest| rest.strip_prefix(':'))
+            .ok_or_else(|| anyhow!("Invalid history custom id {}", custom_id))?;
+
+        if let Some(args) = args.strip_prefix("page:") {
+            let (user_id, query) = HistoryQuery::parse(args)
+                .ok_or_else(|| anyhow!("Invalid history custom id {}", custom_id))?;
+            if Some(user_id) != self.interaction.author_id() {
+                return self.reply_template("history_not_owner", &[]).await;
+            }
+
+            self.handle
+                .defer_component(DeferVisibility::Ephemeral, DeferBehavior::Update)
+                .await?;
+            let reply = self.history_page(query).await?;
+            self.handle.reply(reply.update_last()).await?;
+        } else if let Some(user_id) = args.strip_prefix("detail:") {
+            if user_id.parse().ok() != self.interaction.author_id() {
+                return self.reply_template("history_not_owner", &[]).await;
+            }
+
+            let order_id = self
+                .interaction
+                .data
+                .clone()
+                .and_then(InteractionDataExt::component)
+                .and_then(|data| data.values.into_iter().next())
+                .ok_or_else(|| anyhow!("Order menu without a value"))?;
+
+            self.handle
+                .defer_component(DeferVisibility::Ephemeral, DeferBehavior::Followup)
+                .await?;
+            let embed = self.order_details(&order_id).await?;
+            self.handle.reply(Reply::new().embed(embed).ephemeral()).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn history_page(&self, query: HistoryQuery) -> Result<Reply, anyhow::Error> {
+        let user = self.interaction.author().ok()?;
+        let Ok(user_data) = self.ctx.api.get_user_data(user.id).await else {
+            return Ok(
+                Reply::new().embed(self.render_template("no_account", &[("user", &user.name)])?)
+            );
+        };
+
+        let ordered_any = !user_data.numbers.is_empty();
+        let numbers: Vec<Number> = user_data
+            .numbers
+            .into_iter()
+            .rev()
+            .filter(|number| query.matches(number))
+            .collect();
+        if numbers.is_empty() {
+            return Ok(Reply::new().embed(self.render_template(
+                if ordered_any {
+                    "no_matching_orders"
+                } else {
+                    "no_orders"
+                },
+                &[],
+            )?));
+        }
+
+        let pages = numbers.len().div_ceil(PAGE_SIZE);
+        let page = query.page.min(pages - 1);
+        let page_numbers = &numbers[page * PAGE_SIZE..numbers.len().min((page + 1) * PAGE_SIZE)];
+        self.learn_country_codes(page_numbers).await;
+
+        let mut orders = String::new();
+        for (i, number) in page_numbers.iter().enumerate() {
+            orders += &format!(
+                "**{}.** {} | {}{} | `{}` | {} | {}\n",
+                page * PAGE_SIZE + i + 1,
+                number.service,
+                number.country,
+                self.flag(&number.country),
+                self.usd(f64::from(number.price) / 100.0),
+                self.status(number),
+                match number.ordered_at() {
+                    Some(ordered_at) => format!("<t:{}:d>", ordered_at),
+                    None => "`N/A`".to_string(),
+                }
+            );
+        }
+
+        let embed = self
+            .template_embed("order_history", &[("orders", &orders)])
+            .field(EmbedFieldBuilder::new(
+                self.label("Page:"),
+                fill(
+                    &self.label("`{page}` of `{pages}`, `{count}` orders"),
+                    &[
+                        ("page", &(page + 1)),
+                        ("pages", &pages),
+                        ("count", &numbers.len()),
+                    ],
+                ),
+            ))
+            .validate()?
+            .build();
+
+        let order_menu = Component::ActionRow(ActionRow {
+            components: vec![Component::SelectMenu(SelectMenu {
+                custom_id: format!("{}:detail:{}", COMPONENT_PREFIX, user.id),
+                disabled: false,
+                max_values: Some(1),
+                min_values: Some(1),
+                options: page_numbers
+                    .iter()
+                    .map(|number| SelectMenuOption {
+                        default: false,
+                        description: Some(format!("+{} | {}", number.number, number.country)),
+                        emoji: None,
+                        label: number.service.clone(),
+                        value: number.order_id.clone(),
+                    })
+                    .collect(),
+                placeholder: Some(self.label("See the details of an order")),
+            })],
+        });
+        let page_buttons = Component::ActionRow(ActionRow {
+            components: vec![
+                page_button(
+                    query.page_custom_id(user.id, page.saturating_sub(1)),
+                    self.label("Previous"),
+                    page == 0,
+                ),
+                page_button(
+                    query.page_custom_id(user.id, page + 1),
+                    self.label("Next"),
+                    page + 1 >= pages,
+                ),
+            ],
+        });
+
+        Ok(Reply::new()
+            .embed(embed)
+            .component(order_menu)
+            .component(page_buttons))
+    }
+
+    async fn order_details(&self, order_id: &str) -> Result<Embed, anyhow::Error> {
+        let user = self.interaction.author().ok()?;
+        let Ok(user_data) = self.ctx.api.get_user_data(user.id).await else {
+            return self.render_template("no_account", &[("user", &user.name)]);
+        };
+        let Some(number) = user_data
+            .numbers
+            .into_iter()
+            .find(|number| number.order_id == order_id)
+        else {
+            return self.render_template("order_not_found", &[("order", &order_id)]);
+        };
+
+        let retention_hours = self.ctx.config().history.sms_retention_hours;
+        let within_retention = number.ordered_at().is_some_and(|ordered_at| {
+            chrono::Utc::now().timestamp() - ordered_at < i64::from(retention_hours) * 60 * 60
+        });
+
+        let mut message = String::new();
+        let template = if !number.received {
+            "order_details_not_received"
+        } else if within_retention {
+            match self.ctx.sms.clone().get_sms_code(&number.order_id).await {
+                Ok(sms_code) if sms_code.full_sms.is_some() => {
+                    message = sms_code.full_sms.unwrap_or_default();
+                    "order_details_message"
+                }
+                Ok(_) => "order_details_message_gone",
+                Err(err) => {
+                    tracing::error!("{:#?}", err);
+                    "order_details_message_failed"
+                }
+            }
+        } else if retention_hours == 0 {
+            "order_details_message_hidden"
+        } else {
+            "order_details_message_expired"
+        };
+
+        self.learn_country_codes(std::slice::from_ref(&number)).await;
+
+        Ok(self
+            .template_embed(
+                template,
+                &[
+                    ("number", &number.number),
+                    ("message", &message),
+                    ("hours", &retention_hours),
+                ],
+            )
+            .field(EmbedFieldBuilder::new(self.label("Service:"), &number.service).inline())
+            .field(
+                EmbedFieldBuilder::new(
+                    self.label("Country:"),
+                    format!("{}{}", number.country, self.flag(&number.country)),
+                )
+                .inline(),
+            )
+            .field(
+                EmbedFieldBuilder::new(self.label("Number:"), format!("+{}", number.number))
+                    .inline(),
+            )
+            .field(
+                EmbedFieldBuilder::new(
+                    self.label("Price:"),
+                    format!("`{}`", self.usd(f64::from(number.price) / 100.0)),
+                )
+                .inline(),
+            )
+            .field(EmbedFieldBuilder::new(self.label("Status:"), self.status(&number)).inline())
+            .field(
+                EmbedFieldBuilder::new(
+                    self.label("Ordered:"),
+                    match number.ordered_at() {
+                        Some(ordered_at) => format!("<t:{}:f>", ordered_at),
+                        None => "`N/A`".to_string(),
+                    },
+                )
+                .inline(),
+            )
+            .field(EmbedFieldBuilder::new(
+                self.label("Order:"),
+                format!("`{}`", number.order_id),
+            ))
+            .validate()?
+            .build())
+    }
+
+    /// Look up the ISO codes of the countries that weren't looked up yet,
+    /// from the country prices of their services
+    ///
+    /// Countries whose services don't list them are remembered too, so that
+    /// turning pages doesn't ask the provider for them again
+    async fn learn_country_codes(&self, numbers: &[Number]) {
+        let codes = &self.ctx.country_codes;
+        let mut tried = HashSet::new();
+        let mut looked_up = HashSet::new();
+        for number in numbers {
+            let service = number.service.as_str();
+            // Countries found by an earlier service are skipped too
+            if codes.looked_up(&number.country) || !tried.insert(service) {
+                continue;
+            }
+
+            match self.ctx.sms.clone().get_country_prices(service).await {
+                Ok(countries) => {
+                    codes.extend(&countries);
+                    looked_up.insert(service);
+                }
+                Err(err) => tracing::info!("Unable to get the countries of {}: {:?}", service, err),
+            }
+        }
+
+        for number in numbers {
+            if looked_up.contains(number.service.as_str()) {
+                codes.mark_looked_up(&number.country);
+            }
+        }
+    }
+
+    fn flag(&self, country: &str) -> String {
+        self.ctx
+            .country_codes
+            .get(country)
+            .map(|iso| format!("  :flag_{}:", iso))
+            .unwrap_or_default()
+    }
+
+    fn status(&self, number: &Number) -> String {
+        if number.received {
+            format!("✅ {}", self.label("Received"))
+        } else {
+            format!("⏳ {}", self.label("Not received"))
+        }
+    }
+}
+
+fn page_button(custom_id: String, label: String, disabled: bool) -> Component {
+    Component::Button(Button {
+        custom_id: Some(custom_id),
+        disabled,
+        emoji: None,
+        label: Some(label),
+        style: ButtonStyle::Secondary,
+        url: None,
+    })
+}
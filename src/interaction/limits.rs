@@ -0,0 +1,120 @@
+use std::time::Duration;
+use twilight_model::channel::message::Embed;
+
+use super::InteractionContext;
+use crate::{
+    api::Role,
+    ratelimit::{BucketKey, LimitedAction, Limit},
+};
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+const MINUTE: Duration = Duration::from_secs(60);
+
+impl InteractionContext<'_> {
+    /// Take one use of the action from the user's and the guild's limits
+    ///
+    /// Returns the cooldown embed to reply with if either limit is reached
+    pub fn check_rate_limit(
+        &self,
+        action: LimitedAction,
+        role: Role,
+    ) -> Result<Option<Embed>, anyhow::Error> {
+        let limits = self.rate_limits(action, role);
+        let Err((key, wait_time)) = self.ctx.rate_limiter.try_acquire(&limits) else {
+            return Ok(None);
+        };
+
+        let limit = limits
+            .iter()
+            .find(|(limited_key, _)| *limited_key == key)
+            .map_or(0, |(_, limit)| limit.amount);
+        let template = match key {
+            BucketKey::User(LimitedAction::Order, _) => "order_limit",
+            BucketKey::Guild(LimitedAction::Order, _) => "server_order_limit",
+            BucketKey::User(LimitedAction::CheckSms, _) => "checksms_limit",
+            BucketKey::Guild(LimitedAction::CheckSms, _) => "server_checksms_limit",
+        };
+        let retry_at = chrono::Utc::now().timestamp() + wait_time.as_secs_f64().ceil() as i64;
+
+        tracing::info!("Rate limited {:?} for {:?}", key, wait_time);
+        Ok(Some(self.render_template(
+            template,
+            &[("limit", &limit), ("retry_at", &retry_at)],
+        )?))
+    }
+
+    /// Give back the use [`Self::check_rate_limit`] took, for when the action
+    /// failed
+    pub fn refund_rate_limit(&self, action: LimitedAction, role: Role) {
+        self.ctx
+            .rate_limiter
+            .refund(&self.rate_limits(action, role));
+    }
+
+    /// The user's and the guild's limits of the action, none if the role is
+    /// exempt
+    fn rate_limits(&self, action: LimitedAction, role: Role) -> Vec<(BucketKey, Limit)> {
+        let config = self.ctx.config();
+        if config.limits.exempt_roles.contains(&role) {
+            return vec![];
+        }
+        let Some(user_id) = self.interaction.author_id() else {
+            return vec![];
+        };
+
+        let (user_amount, guild_amount, period) = match action {
+            LimitedAction::Order => (
+                config.limits.orders_per_hour,
+                config.limits.guild_orders_per_hour,
+                HOUR,
+            ),
+            LimitedAction::CheckSms => (
+                config.limits.checksms_per_minute,
+                config.limits.guild_checksms_per_minute,
+                MINUTE,
+            ),
+        };
+
+        let mut limits = vec![(
+            BucketKey::User(action, user_id),
+            Limit {
+                amount: user_amount,
+                period,
+            },
+        )];
+        if let Some(guild_id) = self.interaction.guild_id {
+            limits.push((
+                BucketKey::Guild(action, guild_id),
+                Limit {
+                    amount: guild_amount,
+                    period,
+                },
+            ));
+        }
+
+        limits
+    }
+
+    /// Returns the cooldown embed to reply with if the user can't order
+    /// another number until one of theirs receives a code or expires
+    pub fn check_active_numbers(&self, role: Role) -> Result<Option<Embed>, anyhow::Error> {
+        let config = self.ctx.config();
+        let max_active_numbers = config.limits.max_active_numbers as usize;
+        if max_active_numbers == 0 || config.limits.exempt_roles.contains(&role) {
+            return Ok(None);
+        }
+        let Some(user_id) = self.interaction.author_id() else {
+            return Ok(None);
+        };
+
+        match self.ctx.active_numbers.active(user_id) {
+            (active, Some(first_expiry)) if active >= max_active_numbers => {
+                Ok(Some(self.render_template(
+                    "active_numbers_limit",
+                    &[("active", &active), ("retry_at", &first_expiry)],
+                )?))
+            }
+            _ => Ok(None),
+        }
+    }
+}
@@ -1,73 +1,343 @@
-use sparkle_convenience::{
-    error::IntoError,
-    interaction::{extract::InteractionExt, InteractionHandle},
-};
+use sparkle_convenience::{error::IntoError, interaction::extract::InteractionExt, reply::Reply};
 
 use twilight_interactions::command::CreateCommand;
-use twilight_model::{application::interaction::Interaction, id::Id};
+use twilight_model::{
+    application::{
+        command::{Command, CommandOption},
+        interaction::{Interaction, InteractionType},
+    },
+    channel::message::{AllowedMentions, Embed},
+    id::{marker::RoleMarker, Id},
+};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder};
 
-use crate::{Context, Error};
+use crate::{
+    currencies::Currency,
+    money,
+    preferences::UserPreferences,
+    templates::{fill, Args, Templates},
+    Context, Error,
+};
+use respond::Responder;
+pub(crate) use settings::{MAX_MODAL_LABEL_LENGTH, MODAL_LABELS};
 
+mod admin;
 mod adminbal;
+mod authorize;
+mod autocomplete;
 mod balance;
+mod checksms;
+mod currency;
+mod deposit;
+mod deposits;
 mod getnumber;
+mod history;
+mod limits;
+mod receipt;
+mod register;
+pub mod respond;
 mod search;
+mod settings;
+mod stats;
 mod userdata;
-mod checksms;
 
 #[derive(Debug)]
 struct InteractionContext<'ctx> {
     ctx: &'ctx Context,
-    handle: InteractionHandle<'ctx>,
+    handle: Responder<'ctx>,
     interaction: Interaction,
+    /// The preferences of the user the interaction is from
+    preferences: UserPreferences,
 }
 
 impl<'ctx> InteractionContext<'ctx> {
     async fn handle(self) -> Result<(), anyhow::Error> {
+        let ban = self
+            .interaction
+            .author_id()
+            .and_then(|user_id| self.ctx.bans().active(user_id).cloned());
+        if let Some(ban) = ban {
+            tracing::info!("Ignoring interaction from banned user {}", ban.user_id);
+            if self.interaction.kind == InteractionType::ApplicationCommandAutocomplete {
+                self.handle.autocomplete(vec![]).await?;
+            } else {
+                let notice_embed = self.ctx.ban_notice_embed(self.locale(), &ban)?;
+                self.handle
+                    .reply(Reply::new().embed(notice_embed).ephemeral())
+                    .await?;
+            }
+
+            return Ok(());
+        }
+
+        if self.interaction.kind == InteractionType::ApplicationCommandAutocomplete {
+            return self.handle_autocomplete().await;
+        }
+        if self.interaction.kind == InteractionType::MessageComponent {
+            return match self.interaction.name().ok()?.split_once(':') {
+                Some((admin::ADJUSTMENT_COMPONENT, _)) => self.handle_adjustment_component().await,
+                Some((history::COMPONENT_PREFIX, _)) => self.handle_history_component().await,
+                _ => Err(Error::UnknownInteraction(self.interaction).into()),
+            };
+        }
+        if self.interaction.kind == InteractionType::ModalSubmit {
+            return match self.interaction.name().ok()? {
+                settings::MODAL_ID => self.handle_settings_modal().await,
+                _ => Err(Error::UnknownInteraction(self.interaction).into()),
+            };
+        }
+
         tracing::info!("Processing command {}", &self.interaction.name().ok()?);
 
+        if let Some(unavailable_embed) = self
+            .ctx
+            .unavailable_command_embed(self.locale(), self.interaction.name().ok()?)?
+        {
+            self.handle
+                .reply(Reply::new().embed(unavailable_embed).ephemeral())
+                .await?;
+
+            return Ok(());
+        }
+
         match self.interaction.name().ok()? {
+            admin::AdminCommand::NAME => self.handle_admin_command().await,
             adminbal::AdminBalCommand::NAME => self.handle_adminbal_command().await,
             balance::BalanceCommand::NAME => self.handle_balance_command().await,
             search::SearchCommand::NAME => self.handle_search_command().await,
             userdata::UserDataCommand::NAME => self.handle_user_data_command().await,
             getnumber::GetNumberCommand::NAME => self.handle_getnumber_command().await,
             checksms::CheckSMSCommand::NAME => self.handle_checksms_command().await,
+            currency::CurrencyCommand::NAME => self.handle_currency_command().await,
+            history::HistoryCommand::NAME => self.handle_history_command().await,
+            deposits::DepositsCommand::NAME => self.handle_deposits_command().await,
+            deposit::DepositCommand::NAME => self.handle_deposit_command().await,
+            stats::StatsCommand::NAME => self.handle_stats_command().await,
+            receipt::ReceiptCommand::NAME => self.handle_receipt_command().await,
+            settings::SettingsCommand::NAME => self.handle_settings_command().await,
             _ => Err(Error::UnknownInteraction(self.interaction).into()),
         }
     }
-}
 
-impl Context {
-    pub async fn create_commands(&self) -> Result<(), anyhow::Error> {
-        let commands = [
-            adminbal::AdminBalCommand::create_command().into(),
-            balance::BalanceCommand::create_command().into(),
-            search::SearchCommand::create_command().into(),
-            userdata::UserDataCommand::create_command().into(),
-            getnumber::GetNumberCommand::create_command().into(),
-            checksms::CheckSMSCommand::create_command().into(),
-        ];
-
-        self.bot
-            .interaction_client()
-            .set_guild_commands(Id::new(self.config.debug_scope), &commands)
+    /// The language the user picked in `/settings`, otherwise the locale of
+    /// their Discord client, such as `de` or `pt-BR`
+    fn locale(&self) -> Option<&str> {
+        self.preferences
+            .language
+            .as_deref()
+            .or(self.interaction.locale.as_deref())
+    }
+
+    /// The embed of the template in the user's locale, fields can still be
+    /// added to it
+    fn template_embed(&self, name: &str, args: Args<'_>) -> EmbedBuilder {
+        self.ctx.template_embed(self.locale(), name, args)
+    }
+
+    /// The embed of the template in the user's locale without any fields
+    fn render_template(&self, name: &str, args: Args<'_>) -> Result<Embed, anyhow::Error> {
+        self.ctx.render_template(self.locale(), name, args)
+    }
+
+    /// The name of an embed field in the user's locale
+    fn label(&self, label: &str) -> String {
+        self.ctx.config().templates.label(self.locale(), label)
+    }
+
+    /// The amount in USD written the way the user's locale writes it
+    fn usd(&self, amount: impl Into<f64>) -> String {
+        money::usd(amount, self.locale())
+    }
+
+    /// The currency the user picked to see prices in next to USD, `None` if
+    /// they didn't pick one or it was removed from the rates file
+    fn display_currency(&self) -> Option<Currency> {
+        let code = self.preferences.currency.as_deref()?;
+        self.ctx.rates().get(code).cloned()
+    }
+
+    /// A price in USD followed by what it is in the user's display currency,
+    /// such as `$0.70 USD (≈ €0.64 EUR)`
+    fn price(&self, amount: impl Into<f64>) -> String {
+        let amount = amount.into();
+        match self.display_currency() {
+            Some(currency) => format!(
+                "{} (≈ {})",
+                self.usd(amount),
+                money::amount(currency.convert(amount), &currency, self.locale())
+            ),
+            None => self.usd(amount),
+        }
+    }
+
+    /// Say that the converted prices in the embed are only estimates and USD
+    /// is what is charged, if the user has a display currency
+    fn with_billing_note(&self, embed: EmbedBuilder) -> EmbedBuilder {
+        let Some(currency) = self.display_currency() else {
+            return embed;
+        };
+        let note = fill(
+            &self.label("`USD`, amounts in {currency} are estimates at `1 USD = {rate} {currency}`"),
+            &[("currency", &currency.code), ("rate", &currency.rate)],
+        );
+
+        embed.field(EmbedFieldBuilder::new(self.label("Charged in:"), note))
+    }
+
+    /// Reply with the template, only visible to the user
+    async fn reply_template(&self, name: &str, args: Args<'_>) -> Result<(), anyhow::Error> {
+        let embed = self.render_template(name, args)?;
+        self.handle
+            .reply(Reply::new().embed(embed).ephemeral())
             .await?;
 
-        tracing::info!("Created slash commands");
         Ok(())
     }
 
-    pub async fn handle_interaction(&self, interaction: Interaction) {
-        let handle = self.bot.interaction_handle(&interaction);
-        let ctx = InteractionContext {
-            ctx: self,
-            handle: handle.clone(),
-            interaction,
+    /// Reply with an error that has no template, only visible to the user
+    async fn reply_error(&self, description: impl Into<String>) -> Result<(), anyhow::Error> {
+        let embed = self.ctx.error_embed(self.locale(), description)?;
+        self.handle
+            .reply(Reply::new().embed(embed).ephemeral())
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn commands() -> Vec<Command> {
+    vec![
+        admin::AdminCommand::create_command().into(),
+        adminbal::AdminBalCommand::create_command().into(),
+        balance::BalanceCommand::create_command().into(),
+        search::SearchCommand::create_command().into(),
+        userdata::UserDataCommand::create_command().into(),
+        getnumber::GetNumberCommand::create_command().into(),
+        checksms::CheckSMSCommand::create_command().into(),
+        currency::CurrencyCommand::create_command().into(),
+        history::HistoryCommand::create_command().into(),
+        deposits::DepositsCommand::create_command().into(),
+        deposit::DepositCommand::create_command().into(),
+        stats::StatsCommand::create_command().into(),
+        receipt::ReceiptCommand::create_command().into(),
+        settings::SettingsCommand::create_command().into(),
+    ]
+}
+
+/// The commands with the names and descriptions the locale files translate
+/// them to
+pub fn localized_commands(templates: &Templates) -> Vec<Command> {
+    let mut commands = commands();
+    for command in &mut commands {
+        (
+            command.name_localizations,
+            command.description_localizations,
+        ) = templates.command_localizations(&command.name);
+        localize_options(&command.name, &mut command.options, templates);
+    }
+
+    commands
+}
+
+fn localize_options(parent: &str, options: &mut [CommandOption], templates: &Templates) {
+    for option in options {
+        let path = format!("{} {}", parent, option.name);
+        (option.name_localizations, option.description_localizations) =
+            templates.command_localizations(&path);
+        for choice in option.choices.iter_mut().flatten() {
+            choice.name_localizations = templates
+                .command_localizations(&format!("{} {}", path, choice.name))
+                .0;
+        }
+        if let Some(options) = &mut option.options {
+            localize_options(&path, options, templates);
+        }
+    }
+}
+
+/// The space separated paths of the commands, subcommands, options and
+/// choices such as `search prices country`, with whether it's a choice
+pub(crate) fn command_paths() -> Vec<(String, bool)> {
+    fn option_paths(parent: &str, options: &[CommandOption], paths: &mut Vec<(String, bool)>) {
+        for option in options {
+            let path = format!("{} {}", parent, option.name);
+            for choice in option.choices.iter().flatten() {
+                paths.push((format!("{} {}", path, choice.name), true));
+            }
+            option_paths(&path, option.options.as_deref().unwrap_or_default(), paths);
+            paths.push((path, false));
+        }
+    }
+
+    let mut paths = vec![];
+    for command in commands() {
+        option_paths(&command.name, &command.options, &mut paths);
+        paths.push((command.name, false));
+    }
+
+    paths
+}
+
+impl Context {
+    /// Post an embed to the log channel
+    pub async fn send_log(&self, embed: Embed) {
+        self.send_log_pinging(None, embed).await;
+    }
+
+    /// Post an embed to the log channel, mentioning the role if there is one
+    pub async fn send_log_pinging(&self, role: Option<Id<RoleMarker>>, embed: Embed) {
+        let config = self.config();
+        let embeds = [embed];
+        let content = role.map(|role| format!("<@&{}>", role)).unwrap_or_default();
+        let allowed_mentions = AllowedMentions {
+            roles: role.into_iter().collect(),
+            ..AllowedMentions::default()
+        };
+        let message = match self
+            .bot
+            .http
+            .create_message(config.discord.log_channel)
+            .content(&content)
+            .and_then(|message| message.embeds(&embeds))
+        {
+            Ok(message) => message.allowed_mentions(Some(&allowed_mentions)),
+            Err(err) => {
+                tracing::error!("Invalid log message: {}", err);
+                return;
+            }
         };
 
-        if let Err(err) = ctx.handle().await {
+        if let Err(err) = message.await {
+            tracing::error!("Failed to send log message: {}", err);
+        }
+    }
+
+    pub async fn handle_interaction(&self, interaction: Interaction) {
+        let handle = Responder::Discord(self.bot.interaction_handle(&interaction));
+
+        if let Err(err) = self.respond_to_interaction(interaction, handle).await {
             tracing::error!("Interaction handler panicked with error message: {}\nSee the backtrace below for more information", err);
         }
     }
+
+    /// Handle the interaction, sending the responses to `handle`
+    pub async fn respond_to_interaction(
+        &self,
+        interaction: Interaction,
+        handle: Responder<'_>,
+    ) -> Result<(), anyhow::Error> {
+        let preferences = interaction
+            .author_id()
+            .map(|user_id| self.preferences().get(user_id))
+            .unwrap_or_default();
+
+        InteractionContext {
+            ctx: self,
+            handle,
+            interaction,
+            preferences,
+        }
+        .handle()
+        .await
+    }
 }
@@ -0,0 +1,162 @@
+use sparkle_convenience::{
+    error::IntoError,
+    interaction::{extract::InteractionDataExt, DeferVisibility},
+    reply::Reply,
+};
+use std::fmt::Write;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::http::attachment::Attachment;
+use twilight_util::builder::embed::EmbedFieldBuilder;
+
+use super::InteractionContext;
+use crate::api::Number;
+
+#[derive(CreateCommand, CommandModel, Debug)]
+#[command(name = "receipt", desc = "get a receipt for an order", dm_permission = false)]
+pub struct ReceiptCommand {
+    #[command(desc = "the id of the order, shown in /history", min_length = 1)]
+    pub order: String,
+}
+
+/// What an order cost, in cents
+#[derive(Debug)]
+struct Receipt {
+    /// `None` for orders placed before their base cost was recorded
+    base_cost: Option<i32>,
+    markup: Option<i32>,
+    charge: i32,
+}
+
+impl Receipt {
+    /// Split the price into the provider's cost and the markup, orders that
+    /// didn't receive a code aren't charged
+    fn new(number: &Number) -> Self {
+        if !number.received {
+            return Self {
+                base_cost: Some(0),
+                markup: Some(0),
+                charge: 0,
+            };
+        }
+
+        Self {
+            base_cost: number.base_cost,
+            markup: number.base_cost.map(|base_cost| number.price - base_cost),
+            charge: number.price,
+        }
+    }
+}
+
+impl InteractionContext<'_> {
+    pub async fn handle_receipt_command(self) -> Result<(), anyhow::Error> {
+        let options = ReceiptCommand::from_interaction(
+            self.interaction.data.clone().ok()?.command().ok()?.into(),
+        )?;
+        let user = self.interaction.author().ok()?;
+        let order_id = options.order.trim();
+
+        self.handle.defer(DeferVisibility::Ephemeral).await?;
+
+        let number = match self.ctx.api.get_user_data(user.id).await {
+            Ok(user_data) => user_data
+                .numbers
+                .into_iter()
+                .find(|number| number.order_id == order_id),
+            Err(_) => {
+                return self
+                    .reply_template("no_account", &[("user", &user.name)])
+                    .await;
+            }
+        };
+        let Some(number) = number else {
+            return self
+                .reply_template("order_not_found", &[("order", &order_id)])
+                .await;
+        };
+
+        let receipt = Receipt::new(&number);
+        let ordered = number
+            .ordered_at()
+            .and_then(|ordered_at| chrono::DateTime::from_timestamp(ordered_at, 0));
+
+        let mut text = String::new();
+        writeln!(text, "AlteraSMS receipt")?;
+        writeln!(text, "Order:      {}", number.order_id)?;
+        if let Some(ordered) = ordered {
+            writeln!(text, "Date:       {}", ordered.format("%Y-%m-%d %H:%M:%S UTC"))?;
+        }
+        writeln!(text, "Customer:   {} ({})", user.name, user.id)?;
+        writeln!(text, "Service:    {}", number.service)?;
+        writeln!(text, "Country:    {}", number.country)?;
+        writeln!(text, "Number:     +{}", number.number)?;
+        writeln!(text)?;
+        writeln!(text, "Base cost:  {:>10}", plain_usd(receipt.base_cost))?;
+        writeln!(text, "Markup:     {:>10}", plain_usd(receipt.markup))?;
+        writeln!(text, "Charged:    {:>10}", plain_usd(Some(receipt.charge)))?;
+        if !number.received {
+            writeln!(text)?;
+            writeln!(text, "This number never received a code, so it wasn't charged.")?;
+        }
+
+        let amount = |cents: Option<i32>| match cents {
+            Some(cents) => format!("`{}`", self.usd(f64::from(cents) / 100.0)),
+            None => "`N/A`".to_string(),
+        };
+        let receipt_embed = self
+            .template_embed(
+                if number.received {
+                    "receipt"
+                } else {
+                    "receipt_not_charged"
+                },
+                &[("order", &number.order_id)],
+            )
+            .field(EmbedFieldBuilder::new(self.label("Service:"), &number.service).inline())
+            .field(EmbedFieldBuilder::new(self.label("Country:"), &number.country).inline())
+            .field(
+                EmbedFieldBuilder::new(self.label("Number:"), format!("+{}", number.number))
+                    .inline(),
+            )
+            .field(
+                EmbedFieldBuilder::new(self.label("Base Cost:"), amount(receipt.base_cost))
+                    .inline(),
+            )
+            .field(EmbedFieldBuilder::new(self.label("Markup:"), amount(receipt.markup)).inline())
+            .field(
+                EmbedFieldBuilder::new(self.label("Charged:"), amount(Some(receipt.charge)))
+                    .inline(),
+            )
+            .field(EmbedFieldBuilder::new(
+                self.label("Ordered:"),
+                match ordered {
+                    Some(ordered) => format!("<t:{}:f>", ordered.timestamp()),
+                    None => "`N/A`".to_string(),
+                },
+            ))
+            .validate()?
+            .build();
+        let attachment = Attachment::from_bytes(
+            format!("receipt-{}.txt", number.order_id),
+            text.into_bytes(),
+            0,
+        );
+
+        self.handle
+            .reply(
+                Reply::new()
+                    .embed(receipt_embed)
+                    .attachment(attachment)
+                    .ephemeral(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn plain_usd(cents: Option<i32>) -> String {
+    match cents {
+        Some(cents) => format!("${:.2}", cents as f32 / 100.00),
+        None => "N/A".to_string(),
+    }
+}
@@ -0,0 +1,198 @@
+use serde_json::Value;
+use twilight_model::{
+    application::command::Command,
+    id::{marker::GuildMarker, Id},
+};
+
+use crate::{config::Deployment, Context};
+
+#[derive(Clone, Copy, Debug)]
+enum Scope {
+    Global,
+    Guild(Id<GuildMarker>),
+}
+
+impl Context {
+    /// Register the slash commands according to `discord.deployment`, only
+    /// sending the commands that are new or changed
+    ///
+    /// Registered commands that the bot no longer has are deleted if `prune`
+    /// is set, otherwise they are only reported
+    pub async fn create_commands(&self, prune: bool) -> Result<(), anyhow::Error> {
+        let config = self.config();
+        let commands = super::localized_commands(&config.templates);
+
+        match config.discord.deployment {
+            Deployment::Global => self.sync_commands(Scope::Global, &commands, prune).await?,
+            Deployment::Guild => {
+                for guild in &config.discord.guilds {
+                    self.sync_commands(Scope::Guild(*guild), &commands, prune)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn sync_commands(
+        &self,
+        scope: Scope,
+        commands: &[Command],
+        prune: bool,
+    ) -> Result<(), anyhow::Error> {
+        let client = self.bot.interaction_client();
+        let existing = match scope {
+            Scope::Global => client.global_commands().with_localizations(true).await?,
+            Scope::Guild(guild) => {
+                client
+                    .guild_commands(guild)
+                    .with_localizations(true)
+                    .await?
+            }
+        }
+        .models()
+        .await?;
+
+        let (mut created, mut updated) = (0, 0);
+        for command in commands {
+            match existing.iter().find(|e| is_same_command(e, command)) {
+                Some(registered) if !command_changed(registered, command, scope) => {}
+                registered => {
+                    self.upsert_command(scope, command).await?;
+                    if registered.is_some() {
+                        updated += 1;
+                    } else {
+                        created += 1;
+                    }
+                }
+            }
+        }
+
+        let stale: Vec<&Command> = existing
+            .iter()
+            .filter(|registered| !commands.iter().any(|c| is_same_command(registered, c)))
+            .collect();
+        if prune {
+            for command in &stale {
+                let Some(id) = command.id else { continue };
+                match scope {
+                    Scope::Global => client.delete_global_command(id).await?,
+                    Scope::Guild(guild) => client.delete_guild_command(guild, id).await?,
+                };
+            }
+        } else if !stale.is_empty() {
+            tracing::warn!(
+                "{:?} has stale commands ({}), run with --prune-commands to delete them",
+                scope,
+                stale.iter().map(|c| c.name.as_str()).collect::<Vec<_>>().join(", ")
+            );
+        }
+
+        tracing::info!(
+            "Synced slash commands for {:?}: {} created, {} updated, {} unchanged, {} {}",
+            scope,
+            created,
+            updated,
+            commands.len() - created - updated,
+            stale.len(),
+            if prune { "deleted" } else { "stale" }
+        );
+        Ok(())
+    }
+
+    /// Create the command, Discord overwrites an existing command with the
+    /// same name
+    async fn upsert_command(&self, scope: Scope, command: &Command) -> Result<(), anyhow::Error> {
+        let client = self.bot.interaction_client();
+        let name_localizations = command.name_localizations.clone().unwrap_or_default();
+        let description_localizations =
+            command.description_localizations.clone().unwrap_or_default();
+
+        macro_rules! send {
+            ($builder:expr) => {{
+                let mut builder = $builder
+                    .chat_input(&command.name, &command.description)?
+                    .command_options(&command.options)?
+                    .name_localizations(&name_localizations)?
+                    .description_localizations(&description_localizations)?;
+                if let Some(permissions) = command.default_member_permissions {
+                    builder = builder.default_member_permissions(permissions);
+                }
+                if let Some(nsfw) = command.nsfw {
+                    builder = builder.nsfw(nsfw);
+                }
+                builder
+            }};
+        }
+
+        match scope {
+            Scope::Global => {
+                let mut builder = send!(client.create_global_command());
+                if let Some(dm_permission) = command.dm_permission {
+                    builder = builder.dm_permission(dm_permission);
+                }
+                builder.await?;
+            }
+            Scope::Guild(guild) => {
+                send!(client.create_guild_command(guild)).await?;
+            }
+        }
+
+        tracing::info!("Registered /{} for {:?}", command.name, scope);
+        Ok(())
+    }
+}
+
+fn is_same_command(a: &Command, b: &Command) -> bool {
+    a.name == b.name && a.kind == b.kind
+}
+
+/// Compare the parts of a command that are sent to Discord, ignoring the
+/// fields Discord fills in and the defaults it leaves out
+fn command_changed(registered: &Command, desired: &Command, scope: Scope) -> bool {
+    let normalize = |command: &Command| {
+        let mut value = serde_json::to_value(command).unwrap_or_default();
+        if let Value::Object(fields) = &mut value {
+            for field in ["id", "application_id", "guild_id", "version", "type"] {
+                fields.remove(field);
+            }
+            match scope {
+                // `dm_permission` has no effect on guild commands
+                Scope::Guild(_) => {
+                    fields.remove("dm_permission");
+                }
+                Scope::Global => {
+                    if fields.get("dm_permission") == Some(&Value::Bool(true)) {
+                        fields.remove("dm_permission");
+                    }
+                }
+            }
+        }
+        strip_defaults(&mut value);
+        value
+    };
+
+    normalize(registered) != normalize(desired)
+}
+
+/// Recursively remove nulls, `false`, and empty arrays or objects
+fn strip_defaults(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            fields.values_mut().for_each(strip_defaults);
+            fields.retain(|_, v| !is_default(v));
+        }
+        Value::Array(values) => values.iter_mut().for_each(strip_defaults),
+        _ => {}
+    }
+}
+
+fn is_default(value: &Value) -> bool {
+    match value {
+        Value::Null | Value::Bool(false) => true,
+        Value::Array(values) => values.is_empty(),
+        Value::Object(fields) => fields.is_empty(),
+        _ => false,
+    }
+}
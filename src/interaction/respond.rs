@@ -0,0 +1,141 @@
+use sparkle_convenience::{
+    interaction::{DeferBehavior, DeferVisibility, InteractionHandle},
+    reply::Reply,
+};
+use std::sync::{Mutex, PoisonError};
+use twilight_model::{
+    application::command::CommandOptionChoice,
+    channel::message::{component::TextInput, Embed},
+};
+
+/// Where the responses to an interaction go, Discord or a recording so
+/// handlers can be run without a gateway
+#[derive(Debug, Clone)]
+pub enum Responder<'a> {
+    Discord(InteractionHandle<'a>),
+    Recording(&'a Recording),
+}
+
+impl Responder<'_> {
+    pub async fn defer(&self, visibility: DeferVisibility) -> Result<(), anyhow::Error> {
+        match self {
+            Self::Discord(handle) => handle.defer(visibility).await?,
+            Self::Recording(recording) => recording.push(Response::Defer(visibility)),
+        }
+
+        Ok(())
+    }
+
+    pub async fn defer_component(
+        &self,
+        visibility: DeferVisibility,
+        behavior: DeferBehavior,
+    ) -> Result<(), anyhow::Error> {
+        match self {
+            Self::Discord(handle) => handle.defer_component(visibility, behavior).await?,
+            Self::Recording(recording) => {
+                recording.push(Response::DeferComponent(visibility, behavior));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Respond, follow up or edit the last response, see
+    /// [`InteractionHandle::reply`]
+    pub async fn reply(&self, reply: Reply) -> Result<(), anyhow::Error> {
+        match self {
+            Self::Discord(handle) => {
+                handle.reply(reply).await?;
+            }
+            Self::Recording(recording) => recording.push(Response::Reply(Box::new(reply))),
+        }
+
+        Ok(())
+    }
+
+    pub async fn modal(
+        &self,
+        custom_id: String,
+        title: String,
+        text_inputs: Vec<TextInput>,
+    ) -> Result<(), anyhow::Error> {
+        match self {
+            Self::Discord(handle) => handle.modal(custom_id, title, text_inputs).await?,
+            Self::Recording(recording) => recording.push(Response::Modal {
+                custom_id,
+                title,
+                text_inputs,
+            }),
+        }
+
+        Ok(())
+    }
+
+    pub async fn autocomplete(
+        &self,
+        choices: Vec<CommandOptionChoice>,
+    ) -> Result<(), anyhow::Error> {
+        match self {
+            Self::Discord(handle) => handle.autocomplete(choices).await?,
+            Self::Recording(recording) => recording.push(Response::Autocomplete(choices)),
+        }
+
+        Ok(())
+    }
+}
+
+/// A response recorded instead of being sent to Discord
+#[derive(Debug, Clone, PartialEq)]
+pub enum Response {
+    Defer(DeferVisibility),
+    DeferComponent(DeferVisibility, DeferBehavior),
+    Reply(Box<Reply>),
+    Autocomplete(Vec<CommandOptionChoice>),
+    Modal {
+        custom_id: String,
+        title: String,
+        text_inputs: Vec<TextInput>,
+    },
+}
+
+/// The responses a handler sent, in order
+#[derive(Debug, Default)]
+pub struct Recording {
+    responses: Mutex<Vec<Response>>,
+}
+
+impl Recording {
+    pub fn responses(&self) -> Vec<Response> {
+        self.responses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// The replies sent, without the deferrals, modals and autocomplete choices
+    pub fn replies(&self) -> Vec<Reply> {
+        self.responses()
+            .into_iter()
+            .filter_map(|response| match response {
+                Response::Reply(reply) => Some(*reply),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The embeds of every reply, in order
+    pub fn embeds(&self) -> Vec<Embed> {
+        self.replies()
+            .into_iter()
+            .flat_map(|reply| reply.embeds)
+            .collect()
+    }
+
+    fn push(&self, response: Response) {
+        self.responses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(response);
+    }
+}
@@ -3,10 +3,12 @@ use sparkle_convenience::reply::Reply;
 use std::mem;
 use twilight_interactions::command::{CommandModel, CreateCommand, CommandOption, CreateOption};
 use twilight_model::application::interaction::InteractionData;
-use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder};
+use twilight_util::builder::embed::EmbedFieldBuilder;
 
 use super::InteractionContext;
-use crate::logic::{find_similar_countries, find_similar_services, is_service_blacklisted};
+use crate::logic::{find_similar_countries, find_similar_services, resolve_alias};
+use crate::policy::ServiceAction;
+use crate::preferences::PriceSort;
 use crate::sms::get_country_prices::CountryPriceInfo;
 
 #[derive(CommandModel, CreateCommand, Debug)]
@@ -44,93 +46,75 @@ impl InteractionContext<'_> {
 #[derive(CommandModel, CreateCommand, Debug)]
 #[command(name = "services", desc = "search the suppored services for a service", dm_permission = false)]
 pub struct ServicesCommand {
-    #[command(desc = "the service to search for", min_length = 2)]
+    #[command(desc = "the service to search for", min_length = 2, autocomplete = true)]
     service: String,
 }
 
 impl ServicesCommand {
     pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
-        let service: String = self.service.try_into()?;
-
-        if is_service_blacklisted(service.as_str()) {
-            let blacklisted_service_embed = EmbedBuilder::new()
-                .title("Error")
-                .color(ictx.ctx.config.error_color)
-                .description(format!(
-                    "No services similar to **{}** could be found.",
-                    &service
-                ))
-                .validate()?
-                .build();
+        let service = self.service;
 
+        if let Some(refusal_embed) = ictx
+            .ctx
+            .enforce_service_policy(
+                ictx.interaction.author(),
+                ictx.locale(),
+                &service,
+                ServiceAction::Search,
+            )
+            .await?
+        {
             ictx.handle
-                .reply(Reply::new().embed(blacklisted_service_embed).ephemeral())
+                .reply(Reply::new().embed(refusal_embed).ephemeral())
                 .await?;
 
-            return Ok(()); 
+            return Ok(());
         }
 
         let sms_services = ictx.ctx.sms.clone().get_service_list().await;
 
         match sms_services {
             Err(error) => {
-                let request_error_embed = EmbedBuilder::new()
-                    .title("Error")
-                    .color(ictx.ctx.config.error_color)
-                    .description("An error occurred when making your request.")
-                    .validate()?
-                    .build();
+                ictx.reply_template("service_list_failed", &[]).await?;
 
-                ictx.handle
-                    .reply(Reply::new().embed(request_error_embed).ephemeral())
-                    .await?;
-                
-                tracing::error!("{}", error.errors.into_iter().nth(0).unwrap().message);  
+                tracing::error!("{:?}", error);
             }
             Ok(services) => {
-                let similar_services = find_similar_services(&service, &services);
+                let similar_services = find_similar_services(
+                    &service,
+                    &services,
+                    &ictx.ctx.blacklist(),
+                    &ictx.ctx.config().service_matching,
+                );
 
                 if similar_services.is_empty() {
-                    let no_similar_embed = EmbedBuilder::new()
-                        .title("Error")
-                        .color(ictx.ctx.config.error_color)
-                        .description(format!(
-                            "No services similar to **{}** could be found.",
-                            &service
-                        ))
-                        .validate()?
-                        .build();
-
-                    ictx.handle
-                        .reply(Reply::new().embed(no_similar_embed).ephemeral())
-                        .await?;
-
-                    return Ok(())
+                    return ictx
+                        .reply_template("no_similar_services", &[("service", &service)])
+                        .await;
                 }
 
-                let mut embed_desc = format!(
-                    "Out of `{}` services, `{}` services matched your input of **{}**:\n\n",
-                    services.len(),
-                    similar_services.len(),
-                    service,
-                );
-
+                let similarity = ictx.label("similarity");
+                let mut similar = String::new();
                 for (i, s) in similar_services.iter().enumerate() {
-                    embed_desc += format!(
-                        "**{}:** {} | `{}%` similarity\n",
+                    similar += format!(
+                        "**{}:** {} | `{}%` {}\n",
                         i + 1,
                         s.service_info.name,
-                        s.similarity_score
+                        s.similarity_score,
+                        similarity
                     )
                     .as_str();
                 }
 
-                let services_embed = EmbedBuilder::new()
-                    .title("Success")
-                    .color(ictx.ctx.config.success_color)
-                    .description(embed_desc)
-                    .validate()?
-                    .build();
+                let services_embed = ictx.render_template(
+                    "service_search",
+                    &[
+                        ("total", &services.len()),
+                        ("matched", &similar_services.len()),
+                        ("service", &service),
+                        ("services", &similar),
+                    ],
+                )?;
 
                 ictx.handle
                     .reply(Reply::new().embed(services_embed))
@@ -151,10 +135,19 @@ enum SortByOption {
     SuccessRate,
 }
 
+impl From<PriceSort> for SortByOption {
+    fn from(sort: PriceSort) -> Self {
+        match sort {
+            PriceSort::Price => Self::Price,
+            PriceSort::SuccessRate => Self::SuccessRate,
+        }
+    }
+}
+
 #[derive(CommandModel, CreateCommand, Debug)]
 #[command(name = "prices", desc = "Search for the price of a number", dm_permission = false)]
 pub struct PricesCommand {
-    #[command(desc = "the service to get the prices of", min_length = 2)]
+    #[command(desc = "the service to get the prices of", min_length = 2, autocomplete = true)]
     service: String,
     #[command(desc = "how to sort the countries that support the specified service")]
     sort_by: Option<SortByOption>,
@@ -167,8 +160,28 @@ pub struct PricesCommand {
 
 impl PricesCommand {
     pub async fn execute(self, ictx: &InteractionContext<'_>) -> Result<(), anyhow::Error> {
-        let service: String = self.service.try_into().unwrap();
-        let optional_country: Option<String> = self.country.try_into().unwrap();
+        // The provider only knows services by their name, not their aliases
+        let services = ictx.ctx.service_list().await.unwrap_or_default();
+        let service = resolve_alias(&self.service, &services, &ictx.ctx.config().service_matching)
+            .unwrap_or(self.service);
+        let optional_country = self.country;
+
+        if let Some(refusal_embed) = ictx
+            .ctx
+            .enforce_service_policy(
+                ictx.interaction.author(),
+                ictx.locale(),
+                &service,
+                ServiceAction::PriceLookup,
+            )
+            .await?
+        {
+            ictx.handle
+                .reply(Reply::new().embed(refusal_embed).ephemeral())
+                .await?;
+
+            return Ok(());
+        }
 
         let mut country_prices = ictx
             .ctx
@@ -178,7 +191,9 @@ impl PricesCommand {
             .await
             .unwrap_or([].to_vec());
         
-        let sort_by_method: SortByOption = self.sort_by.unwrap_or(SortByOption::Price);
+        let sort_by_method: SortByOption = self
+            .sort_by
+            .unwrap_or_else(|| ictx.preferences.sort_by.into());
         match sort_by_method {
             SortByOption::Price => {
                 country_prices.sort_by(|a, b| {
@@ -201,67 +216,48 @@ impl PricesCommand {
 
         // If service is invalid and request for prices fails
         if country_prices.is_empty() {
-            let sms_services = ictx.ctx.sms.clone().get_service_list().await.unwrap();
+            let sms_services = ictx.ctx.sms.clone().get_service_list().await.unwrap_or_default();
             if sms_services.is_empty() {
-                let request_error_embed = EmbedBuilder::new()
-                    .title("Error")
-                    .color(ictx.ctx.config.error_color)
-                    .description("An error occured when making your request.")
-                    .validate()?
-                    .build();
+                ictx.reply_template("service_list_failed", &[]).await?;
 
-                ictx.handle
-                    .reply(Reply::new().embed(request_error_embed).ephemeral())
-                    .await?;
-                
                 tracing::error!("Unable to obtain service list");
                 return Ok(())
             }
 
-            let similar_services = find_similar_services(service.as_str(), &sms_services);
+            let config = ictx.ctx.config();
+            let similar_services = if config.features.service_suggestions {
+                find_similar_services(
+                    service.as_str(),
+                    &sms_services,
+                    &ictx.ctx.blacklist(),
+                    &config.service_matching,
+                )
+            } else {
+                vec![]
+            };
 
             if similar_services.is_empty() {
-                let no_services_embed = EmbedBuilder::new()
-                    .title("Error")
-                    .color(ictx.ctx.config.error_color)
-                    .description(format!(
-                        "No services similar to **{}** could be found.",
-                        service
-                    ))
-                    .validate()?
-                    .build();
-
-                ictx.handle
-                    .reply(Reply::new().embed(no_services_embed).ephemeral())
-                    .await?;
-
-                return Ok(())
+                return ictx
+                    .reply_template("no_similar_services", &[("service", &service)])
+                    .await;
             }
 
-            let mut embed_desc =
-                "The service you provided was invalid. Here are some similar ones that you might be interested in:\n".to_string();
-
+            let similarity = ictx.label("similarity");
+            let mut services = String::new();
             for (i, s) in similar_services.iter().enumerate() {
-                embed_desc += format!(
-                    "**{}:** {} | `{}%` similarity\n",
+                services += format!(
+                    "**{}:** {} | `{}%` {}\n",
                     i + 1,
                     s.service_info.name,
-                    s.similarity_score
+                    s.similarity_score,
+                    similarity
                 )
                 .as_str();
             }
 
-            let invalid_service_error = EmbedBuilder::new()
-                .title("Error")
-                .color(ictx.ctx.config.error_color)
-                .description(embed_desc)
-                .validate()?
-                .build();
-            ictx.handle
-                .reply(Reply::new().embed(invalid_service_error).ephemeral())
-                .await?;
-
-            return Ok(());
+            return ictx
+                .reply_template("similar_services", &[("services", &services)])
+                .await;
         }
 
         match optional_country {
@@ -270,8 +266,7 @@ impl PricesCommand {
                     country = "gb".to_string();
                 }
 
-                let filtered_country: Option<CountryPriceInfo>;
-                filtered_country = if country.len() <= 3 {
+                let filtered_country: Option<CountryPriceInfo> = if country.len() <= 3 {
                     country_prices
                         .iter()
                         .find(|c| c.iso.to_lowercase() == country.to_lowercase())
@@ -290,43 +285,31 @@ impl PricesCommand {
                             find_similar_countries(country.as_str(), &supported_countries);
 
                         if similar_countries.is_empty() {
-                            let no_countries_embed = EmbedBuilder::new()
-                                .title("Error")
-                                .color(ictx.ctx.config.error_color)
-                                .description(format!(
-                                    "No countries similar to **{}** could be found",
-                                    country
-                                ))
-                                .validate()?
-                                .build();
-
-                            ictx.handle
-                                .reply(Reply::new().embed(no_countries_embed).ephemeral())
+                            ictx.reply_template("no_similar_countries", &[("country", &country)])
                                 .await?;
                         } else {
-                            let mut embed_desc = format!(
-                            "Out of `{}` countries supporting this product, `{}` matched your input of **{}**.\n\n",
-                            supported_countries.len(),
-                            similar_countries.len(),
-                            country
-                            );
-
+                            let similarity = ictx.label("similarity");
+                            let mut countries = String::new();
                             for (i, c) in similar_countries.iter().enumerate() {
-                                embed_desc += format!(
-                                    "**{}:** {} | `{}%` similarity\n",
+                                countries += format!(
+                                    "**{}:** {} | `{}%` {}\n",
                                     i + 1,
                                     c.country_info.name,
-                                    c.similarity_score
+                                    c.similarity_score,
+                                    similarity
                                 )
                                 .as_str();
                             }
 
-                            let similar_countries_embed = EmbedBuilder::new()
-                                .title("Success")
-                                .color(ictx.ctx.config.success_color)
-                                .description(embed_desc)
-                                .validate()?
-                                .build();
+                            let similar_countries_embed = ictx.render_template(
+                                "similar_countries",
+                                &[
+                                    ("total", &supported_countries.len()),
+                                    ("matched", &similar_countries.len()),
+                                    ("country", &country),
+                                    ("countries", &countries),
+                                ],
+                            )?;
 
                             ictx.handle
                                 .reply(Reply::new().embed(similar_countries_embed))
@@ -334,23 +317,29 @@ impl PricesCommand {
                         }
                     }
                     Some(country_price) => {
-                        let price_str = if country_price.price == country_price.low_price {
-                            format!(" is `${:.2}`", country_price.price * ictx.ctx.config.price_multiplier)
+                        let multiplier = ictx.ctx.config().pricing.multiplier;
+                        let price = ictx.price(country_price.price * multiplier);
+                        let price_embed = if country_price.price == country_price.low_price {
+                            ictx.template_embed(
+                                "country_price",
+                                &[
+                                    ("country", &country_price.name),
+                                    ("service", &service),
+                                    ("price", &price),
+                                ],
+                            )
                         } else {
-                            format!(" can range from `${:.2}` - `${:.2}`", 
-                            country_price.low_price * ictx.ctx.config.price_multiplier, 
-                            country_price.price * ictx.ctx.config.price_multiplier)
+                            ictx.template_embed(
+                                "country_price_range",
+                                &[
+                                    ("country", &country_price.name),
+                                    ("service", &service),
+                                    ("low_price", &ictx.price(country_price.low_price * multiplier)),
+                                    ("high_price", &price),
+                                ],
+                            )
                         };
-
-                        let price_embed = EmbedBuilder::new()
-                            .title("Success")
-                            .color(ictx.ctx.config.success_color)
-                            .description(format!(
-                                "The price for a number from **{}** for the service **{}**", 
-                                country_price.name, 
-                                service) + price_str.as_str()) 
-                            .validate()?
-                            .build();
+                        let price_embed = ictx.with_billing_note(price_embed).validate()?.build();
 
                         ictx.handle.reply(Reply::new().embed(price_embed)).await?;
                     }
@@ -358,15 +347,13 @@ impl PricesCommand {
             }
             None => {
                 
-                let mut price_embed = EmbedBuilder::new()
-                    .title("Success")
-                    .color(ictx.ctx.config.success_color)
-                    .description(
-                        format!("Here are the `{}` cheapest countries that are supported by the **{}** service.",
-                            if country_prices.len() >= 25 { 25 } else { country_prices.len() }, 
-                            service
-                        ) + " Country information is displayed in the following format:\n`{price} | {success_rate}`"
-                    );
+                let mut price_embed = ictx.template_embed(
+                    "country_prices",
+                    &[
+                        ("count", &country_prices.len().min(25)),
+                        ("service", &service),
+                    ],
+                );
 
                 for info in country_prices.iter().take(25) {
                     price_embed = price_embed.clone().field(
@@ -374,10 +361,11 @@ impl PricesCommand {
                                 "{}  :flag_{}:", 
                                 info.name, 
                                 info.iso.to_lowercase()), 
-                            format!("`${:.2}` | `{}%`", 
-                                info.low_price * ictx.ctx.config.price_multiplier, info.success_rate ))
+                            format!("`{}` | `{}%`", 
+                                ictx.price(info.low_price * ictx.ctx.config().pricing.multiplier), info.success_rate ))
                         .inline());
                 }
+                price_embed = ictx.with_billing_note(price_embed);
 
                 ictx.handle
                     .reply(
@@ -0,0 +1,259 @@
+use sparkle_convenience::{
+    error::IntoError, interaction::extract::InteractionDataExt, reply::Reply,
+};
+use std::collections::HashMap;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::channel::message::component::{TextInput, TextInputStyle};
+use twilight_util::builder::embed::EmbedFieldBuilder;
+
+use super::InteractionContext;
+use crate::{
+    currencies::BASE_CURRENCY,
+    preferences::{CodeDelivery, PriceSort},
+    templates::{fill, LOCALES},
+};
+
+/// The custom ID of the modal `/settings` opens
+pub const MODAL_ID: &str = "settings";
+
+/// The longest value a setting can have, long enough for any country name
+const MAX_LENGTH: u16 = 50;
+
+/// The most characters Discord allows in the title of a modal and the labels
+/// of its text inputs
+pub const MAX_MODAL_LABEL_LENGTH: usize = 45;
+
+/// The title of the settings modal and the labels of its text inputs, which
+/// translations of them are checked against `MAX_MODAL_LABEL_LENGTH` for
+pub const MODAL_LABELS: &[&str] = &[
+    "Settings",
+    "Default country for /getnumber",
+    "Sort /search prices by",
+    "Send codes found by /checksms as",
+    "Display currency",
+    "Language",
+];
+
+#[derive(CreateCommand, CommandModel, Debug)]
+#[command(name = "settings", desc = "change how the bot answers you")]
+pub struct SettingsCommand {}
+
+impl InteractionContext<'_> {
+    /// Open the settings modal, filled in with the current settings
+    pub async fn handle_settings_command(self) -> Result<(), anyhow::Error> {
+        let preferences = &self.preferences;
+        let text_inputs = vec![
+            self.setting_input(
+                "country",
+                "Default country for /getnumber",
+                "A country such as Germany, or empty for any",
+                preferences.country.clone(),
+            ),
+            self.setting_input(
+                "sort_by",
+                "Sort /search prices by",
+                "price or success rate",
+                Some(preferences.sort_by.name().to_string()),
+            ),
+            self.setting_input(
+                "code_delivery",
+                "Send codes found by /checksms as",
+                "reply or dm",
+                Some(preferences.code_delivery.name().to_string()),
+            ),
+            self.setting_input(
+                "currency",
+                "Display currency",
+                "A currency code such as EUR, or empty for only USD",
+                preferences.currency.clone(),
+            ),
+            self.setting_input(
+                "language",
+                "Language",
+                "A language such as de or pt-BR, or empty for Discord's",
+                preferences.language.clone(),
+            ),
+        ];
+
+        self.handle
+            .modal(MODAL_ID.to_string(), self.label("Settings"), text_inputs)
+            .await
+    }
+
+    fn setting_input(
+        &self,
+        custom_id: &str,
+        label: &str,
+        placeholder: &str,
+        value: Option<String>,
+    ) -> TextInput {
+        TextInput {
+            custom_id: custom_id.to_string(),
+            label: self.label(label),
+            max_length: Some(MAX_LENGTH),
+            min_length: None,
+            placeholder: Some(self.label(placeholder)),
+            required: Some(false),
+            style: TextInputStyle::Short,
+            value,
+        }
+    }
+
+    /// Save the settings from the modal, nothing is saved if any of them is
+    /// invalid
+    pub async fn handle_settings_modal(mut self) -> Result<(), anyhow::Error> {
+        let user_id = self.interaction.author_id().ok()?;
+        let values: HashMap<String, String> = self
+            .interaction
+            .data
+            .clone()
+            .ok()?
+            .modal()
+            .ok()?
+            .components
+            .into_iter()
+            .flat_map(|row| row.components)
+            .map(|input| {
+                let value = input.value.unwrap_or_default().trim().to_string();
+                (input.custom_id, value)
+            })
+            .collect();
+        let value = |custom_id: &str| values.get(custom_id).map_or("", String::as_str);
+
+        let mut preferences = self.preferences.clone();
+        let mut errors = vec![];
+
+        preferences.country = Some(value("country").to_string()).filter(|c| !c.is_empty());
+
+        match value("sort_by") {
+            "" => preferences.sort_by = PriceSort::default(),
+            sort_by => match PriceSort::parse(sort_by) {
+                Some(sort_by) => preferences.sort_by = sort_by,
+                None => errors.push(fill(
+                    &self.label(
+                        "`{value}` isn't a way to sort prices, use `price` or `success rate`",
+                    ),
+                    &[("value", &sort_by)],
+                )),
+            },
+        }
+
+        match value("code_delivery") {
+            "" => preferences.code_delivery = CodeDelivery::default(),
+            code_delivery => match CodeDelivery::parse(code_delivery) {
+                Some(code_delivery) => preferences.code_delivery = code_delivery,
+                None => errors.push(fill(
+                    &self.label("`{value}` isn't a way to send codes, use `reply` or `dm`"),
+                    &[("value", &code_delivery)],
+                )),
+            },
+        }
+
+        let rates = self.ctx.rates();
+        match value("currency") {
+            "" => preferences.currency = None,
+            code if code.eq_ignore_ascii_case(BASE_CURRENCY) => preferences.currency = None,
+            code => match rates.get(code) {
+                Some(currency) => preferences.currency = Some(currency.code.clone()),
+                None => {
+                    let currencies = [BASE_CURRENCY]
+                        .into_iter()
+                        .chain(rates.all().map(|currency| currency.code.as_str()))
+                        .map(|code| format!("`{}`", code))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    errors.push(fill(
+                        &self.label("`{value}` can't be shown, choose one of {currencies}"),
+                        &[("value", &code), ("currencies", &currencies)],
+                    ));
+                }
+            },
+        }
+
+        match value("language") {
+            "" => preferences.language = None,
+            language => match LOCALES
+                .iter()
+                .find(|locale| locale.eq_ignore_ascii_case(language))
+            {
+                Some(locale) => preferences.language = Some(locale.to_string()),
+                None => errors.push(fill(
+                    &self.label("`{value}` isn't a Discord language such as `de` or `pt-BR`"),
+                    &[("value", &language)],
+                )),
+            },
+        }
+
+        if !errors.is_empty() {
+            let errors = errors
+                .iter()
+                .map(|error| format!("- {}", error))
+                .collect::<Vec<_>>()
+                .join("\n");
+            return self
+                .reply_template("settings_invalid", &[("errors", &errors)])
+                .await;
+        }
+
+        // The reply is already in the language that was just picked
+        self.preferences = self
+            .ctx
+            .preferences_mut()
+            .update(user_id, |saved| *saved = preferences)?;
+
+        let settings_embed = self
+            .template_embed("settings_saved", &[])
+            .field(
+                EmbedFieldBuilder::new(
+                    self.label("Default country:"),
+                    match &self.preferences.country {
+                        Some(country) => country.clone(),
+                        None => self.label("Any"),
+                    },
+                )
+                .inline(),
+            )
+            .field(
+                EmbedFieldBuilder::new(
+                    self.label("Sort prices by:"),
+                    self.label(self.preferences.sort_by.name()),
+                )
+                .inline(),
+            )
+            .field(
+                EmbedFieldBuilder::new(
+                    self.label("Send codes as:"),
+                    self.label(self.preferences.code_delivery.name()),
+                )
+                .inline(),
+            )
+            .field(
+                EmbedFieldBuilder::new(
+                    self.label("Display currency:"),
+                    self.preferences
+                        .currency
+                        .as_deref()
+                        .unwrap_or(BASE_CURRENCY),
+                )
+                .inline(),
+            )
+            .field(
+                EmbedFieldBuilder::new(
+                    self.label("Language:"),
+                    match &self.preferences.language {
+                        Some(language) => language.clone(),
+                        None => self.label("Discord's"),
+                    },
+                )
+                .inline(),
+            )
+            .validate()?
+            .build();
+
+        self.handle
+            .reply(Reply::new().embed(settings_embed).ephemeral())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use sparkle_convenience::{error::IntoError, interaction::DeferVisibility, reply::Reply};
+use std::{collections::HashMap, fmt::Write};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_util::builder::embed::EmbedFieldBuilder;
+
+use super::InteractionContext;
+use crate::{api::Number, money};
+
+/// How many days and weeks back the spend is broken down
+const DAYS: i64 = 7;
+const WEEKS: i64 = 4;
+/// How many services and countries are listed
+const TOP: usize = 5;
+
+#[derive(CreateCommand, CommandModel, Debug)]
+#[command(name = "stats", desc = "see what you spent on numbers", dm_permission = false)]
+pub struct StatsCommand;
+
+/// What a user ordered, only numbers that received a code are charged
+#[derive(Debug, Default)]
+struct OrderStats {
+    orders: usize,
+    received: usize,
+    /// In cents
+    spent: i64,
+    /// In cents, oldest day first
+    per_day: Vec<(NaiveDate, i64)>,
+    /// In cents, by the first day of the week, oldest week first
+    per_week: Vec<(NaiveDate, i64)>,
+    /// In cents, highest spend first
+    per_service: Vec<(String, i64)>,
+    /// In cents, highest spend first
+    per_country: Vec<(String, i64)>,
+    /// In seconds, from the numbers that have both timestamps
+    average_time_to_code: Option<i64>,
+}
+
+impl OrderStats {
+    fn new(numbers: &[Number], now: DateTime<Utc>) -> Self {
+        let today = now.date_naive();
+        let this_week = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+
+        let mut per_day: Vec<(NaiveDate, i64)> = (0..DAYS)
+            .rev()
+            .map(|days| (today - Duration::days(days), 0))
+            .collect();
+        let mut per_week: Vec<(NaiveDate, i64)> = (0..WEEKS)
+            .rev()
+            .map(|weeks| (this_week - Duration::weeks(weeks), 0))
+            .collect();
+        let mut per_service = HashMap::new();
+        let mut per_country = HashMap::new();
+        let mut stats = Self {
+            orders: numbers.len(),
+            ..Self::default()
+        };
+        let (mut time_to_code_total, mut time_to_code_count) = (0, 0);
+
+        for number in numbers.iter().filter(|number| number.received) {
+            let price = i64::from(number.price);
+            stats.received += 1;
+            stats.spent += price;
+            *per_service.entry(number.service.clone()).or_insert(0) += price;
+            *per_country.entry(number.country.clone()).or_insert(0) += price;
+
+            if let Some(ordered_at) = number.ordered_at() {
+                let ordered_on = DateTime::from_timestamp(ordered_at, 0)
+                    .unwrap_or_default()
+                    .date_naive();
+                if let Some((_, spent)) = per_day.iter_mut().find(|(day, _)| *day == ordered_on) {
+                    *spent += price;
+                }
+                if let Some((_, spent)) = per_week
+                    .iter_mut()
+                    .find(|(week, _)| (*week..*week + Duration::weeks(1)).contains(&ordered_on))
+                {
+                    *spent += price;
+                }
+
+                if let Some(received_at) = number.received_at() {
+                    time_to_code_total += (received_at - ordered_at).max(0);
+                    time_to_code_count += 1;
+                }
+            }
+        }
+
+        stats.per_day = per_day;
+        stats.per_week = per_week;
+        stats.per_service = top(per_service);
+        stats.per_country = top(per_country);
+        stats.average_time_to_code =
+            (time_to_code_count > 0).then(|| time_to_code_total / time_to_code_count);
+
+        stats
+    }
+
+    /// The share of orders that received a code, in percent
+    fn success_rate(&self) -> Option<f64> {
+        (self.orders > 0).then(|| self.received as f64 / self.orders as f64 * 100.0)
+    }
+}
+
+impl InteractionContext<'_> {
+    pub async fn handle_stats_command(self) -> Result<(), anyhow::Error> {
+        let user = self.interaction.author().ok()?;
+
+        self.handle.defer(DeferVisibility::Ephemeral).await?;
+
+        let Ok(user_data) = self.ctx.api.get_user_data(user.id).await else {
+            return self
+                .reply_template("no_account", &[("user", &user.name)])
+                .await;
+        };
+
+        let stats = OrderStats::new(&user_data.numbers, Utc::now());
+
+        let mut per_day = String::new();
+        for (day, spent) in &stats.per_day {
+            writeln!(
+                per_day,
+                "{}: {}",
+                day.format("%a %d %b"),
+                usd(*spent, self.locale())
+            )?;
+        }
+        let week_of = self.label("Week of");
+        let mut per_week = String::new();
+        for (week, spent) in &stats.per_week {
+            writeln!(
+                per_week,
+                "{} {}: {}",
+                week_of,
+                week.format("%d %b"),
+                usd(*spent, self.locale())
+            )?;
+        }
+
+        let stats_embed = self
+            .template_embed("stats", &[("user_id", &user.id)])
+            .field(
+                EmbedFieldBuilder::new(self.label("Total Spent:"), usd(stats.spent, self.locale()))
+                    .inline(),
+            )
+            .field(
+                EmbedFieldBuilder::new(
+                    self.label("Orders:"),
+                    format!(
+                        "`{}` (`{}` {})",
+                        stats.orders,
+                        stats.received,
+                        self.label("received")
+                    ),
+                )
+                .inline(),
+            )
+            .field(
+                EmbedFieldBuilder::new(
+                    self.label("Success Rate:"),
+                    match stats.success_rate() {
+                        Some(success_rate) => format!("`{:.1}%`", success_rate),
+                        None => "`N/A`".to_string(),
+                    },
+                )
+                .inline(),
+            )
+            .field(
+                EmbedFieldBuilder::new(
+                    self.label("Average Time to Code:"),
+                    match stats.average_time_to_code {
+                        Some(seconds) => format!("`{}m {}s`", seconds / 60, seconds % 60),
+                        None => "`N/A`".to_string(),
+                    },
+                )
+                .inline(),
+            )
+            .field(EmbedFieldBuilder::new(self.label("Last 7 Days:"), per_day))
+            .field(EmbedFieldBuilder::new(
+                self.label("Last 4 Weeks:"),
+                per_week,
+            ))
+            .field(
+                EmbedFieldBuilder::new(
+                    self.label("Top Services:"),
+                    ranking(&stats.per_service, self.locale()),
+                )
+                .inline(),
+            )
+            .field(
+                EmbedFieldBuilder::new(
+                    self.label("Top Countries:"),
+                    ranking(&stats.per_country, self.locale()),
+                )
+                .inline(),
+            )
+            .validate()?
+            .build();
+
+        self.handle
+            .reply(Reply::new().embed(stats_embed).ephemeral())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// The highest spends, highest first
+fn top(spends: HashMap<String, i64>) -> Vec<(String, i64)> {
+    let mut spends: Vec<_> = spends.into_iter().collect();
+    spends.sort_by(|(a_name, a_spent), (b_name, b_spent)| {
+        b_spent.cmp(a_spent).then_with(|| a_name.cmp(b_name))
+    });
+    spends.truncate(TOP);
+    spends
+}
+
+fn ranking(spends: &[(String, i64)], locale: Option<&str>) -> String {
+    if spends.is_empty() {
+        return "`N/A`".to_string();
+    }
+
+    spends
+        .iter()
+        .enumerate()
+        .map(|(i, (name, spent))| format!("{}. {}: {}", i + 1, name, usd(*spent, locale)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn usd(cents: i64, locale: Option<&str>) -> String {
+    format!("`{}`", money::usd(cents as f64 / 100.0, locale))
+}
@@ -4,25 +4,15 @@ use sparkle_convenience::{
 use twilight_interactions::command::{CommandModel, CreateCommand};
 
 use twilight_model::guild::Permissions;
-use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, ImageSource};
+use twilight_util::builder::embed::{EmbedFieldBuilder, ImageSource};
 
-use crate::api::get_user_data;
-use serde_json::Value;
+use crate::api::{InvoiceStatus, Role};
+use crate::permissions::Capability;
 
 use super::InteractionContext;
 
 fn default_permissions() -> Permissions {
-    return Permissions::VIEW_AUDIT_LOG;
-}
-
-fn filter_invoices_for_completed(values: Vec<Value>) -> Vec<Value> {
-  values.iter()
-      .filter(|value| match value {
-        Value::Object(obj) => obj.get("status").and_then(|v| v.as_str()) == Some("COMPLETED"),
-          _ => false,
-      })
-      .cloned()
-      .collect()
+    Permissions::VIEW_AUDIT_LOG
 }
 
 #[derive(CreateCommand, CommandModel, Debug)]
@@ -43,76 +33,77 @@ impl InteractionContext<'_> {
             self.interaction.data.clone().ok()?.command().ok()?.into(),
         )?;
 
-        let user: twilight_model::user::User;
-
-        match options.user {
-            Some(u) => {
-                user = u;
-            }
-            None => {
-                let u = self.interaction.author().ok()?;
-                user = u.clone();
-            }
+        let user = match options.user {
+            Some(u) => u,
+            None => self.interaction.author().ok()?.clone(),
         };
 
-        let user_data = get_user_data(user.id).await;
+        if Some(user.id) != self.interaction.author_id()
+            && self.authorize(Capability::ViewUserData).await?.is_none()
+        {
+            return Ok(());
+        }
+
+        let user_data = self.ctx.api.get_user_data(user.id).await;
 
         match user_data {
             Err(_) => {
-                let error_embed = EmbedBuilder::new()
-                    .title("Error")
-                    .color(self.ctx.config.error_color)
-                    .description(format!("No account could be found for **@{}**", self.interaction.author().unwrap().name))
-                    .validate()?
-                    .build();
-
-                self.handle
-                    .reply(Reply::new().embed(error_embed).ephemeral())
+                self.reply_template("no_account", &[("user", &self.interaction.author().unwrap().name)])
                     .await?;
             }
             Ok(data) => {
-                let info_embed = EmbedBuilder::new()
-                    .title("Success")
-                    .color(self.ctx.config.success_color)
-                    .description(format!(
-                        "Here is the requested information for <@{}>",
-                        user.id
-                    ))
-                    .thumbnail(ImageSource::url(data.image)?)
+                let completed_invoices: Vec<_> = data
+                    .invoices
+                    .iter()
+                    .filter(|invoice| invoice.status == InvoiceStatus::Completed)
+                    .collect();
+                let last_deposit = completed_invoices
+                    .iter()
+                    .filter_map(|invoice| invoice.created_at())
+                    .max();
+
+                let mut info_embed = self.template_embed("user_data", &[("user_id", &user.id)]);
+                // Accounts without a profile picture have no image URL
+                if let Ok(image) = ImageSource::url(data.image) {
+                    info_embed = info_embed.thumbnail(image);
+                }
+                let info_embed = info_embed
                     .field(EmbedFieldBuilder::new(
-                        "Discord User Info:",
+                        self.label("Discord User Info:"),
                         format!("`{}` | **@{}**", user.id, user.name),
                     ))
                     .field(EmbedFieldBuilder::new(
-                        "User Balance:",
-                        format!("`${:.2} USD`", (data.balance as f32) / 100.00),
+                        self.label("User Balance:"),
+                        format!("`{}`", self.usd(f64::from(data.balance) / 100.0)),
                     ))
                     .field(EmbedFieldBuilder::new(
-                        "Last Deposit:",
-                        if filter_invoices_for_completed(data.invoices.clone()).len() == 0 {
-                          "`N/A`".to_string()
-                        } else {
-                          format!(
-                            "<t:{}:f>",
-                            chrono::DateTime::parse_from_rfc3339(&data.updated_at)
-                                .expect("Failed to parse datetime")
-                                .with_timezone(&chrono::Utc)
-                                .timestamp()
-                          )
+                        self.label("Last Deposit:"),
+                        match last_deposit {
+                            Some(last_deposit) => format!("<t:{}:f>", last_deposit),
+                            None => "`N/A`".to_string(),
                         },
                     ))
                     .field(EmbedFieldBuilder::new(
-                        "Number of Deposits",
-                        format!("`{}` deposits on record", filter_invoices_for_completed(data.invoices.clone()).len()),
+                        self.label("Number of Deposits"),
+                        format!(
+                            "`{}` {}",
+                            completed_invoices.len(),
+                            self.label("deposits on record")
+                        ),
                     ))
                     .field(EmbedFieldBuilder::new(
-                        "Role",
-                        if data.role == 2 {
-                            "<:moderator:1123391823885381762> **Admin**"
-                        } else if data.role == 1 {
-                            "<:bug:1123148520665391134> **Support**"
-                        } else {
-                          "<:members:1123391833997852692> **User**"
+                        self.label("Role"),
+                        match data.role {
+                            Role::Admin => format!(
+                                "<:moderator:1123391823885381762> **{}**",
+                                self.label("Admin")
+                            ),
+                            Role::Support => {
+                                format!("<:bug:1123148520665391134> **{}**", self.label("Support"))
+                            }
+                            Role::User => {
+                                format!("<:members:1123391833997852692> **{}**", self.label("User"))
+                            }
                         },
                     ))
                     .validate()?
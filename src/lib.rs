@@ -0,0 +1,199 @@
+use adjustment::{PendingAdjustments, RefundedOrders};
+use api::ApiClient;
+use bans::BanList;
+use blacklist::Blacklist;
+use config::{Config, ConfigError};
+use currencies::Rates;
+use maintenance::Maintenance;
+use monitor::BalanceMonitor;
+use pending_deposits::PendingDeposits;
+use preferences::Preferences;
+use ratelimit::{ActiveNumbers, RateLimiter};
+use futures::StreamExt;
+use sms::{get_country_prices::CountryCodes, get_service_list::ServiceCache, SmsClient};
+use sparkle_convenience::Bot;
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex, RwLock},
+};
+use twilight_gateway::{error::ReceiveMessageErrorType, stream::ShardEventStream, EventTypeFlags};
+use twilight_http as _;
+use twilight_model::{
+    application::interaction::Interaction,
+    gateway::{event::Event, Intents},
+};
+
+pub mod adjustment;
+pub mod api;
+mod audit;
+pub mod bans;
+pub mod blacklist;
+pub mod cli;
+mod decode;
+pub mod config;
+pub mod currencies;
+pub mod interaction;
+mod logic;
+mod maintenance;
+pub mod monitor;
+pub mod money;
+pub mod pending_deposits;
+pub mod permissions;
+mod policy;
+pub mod preferences;
+pub mod ratelimit;
+#[cfg(feature = "mock-backend")]
+pub mod mock_backend;
+pub mod sms;
+mod store;
+pub mod templates;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("unknown interaction: {0:#?}")]
+    UnknownInteraction(Interaction),
+}
+
+#[derive(Debug)]
+pub struct Context {
+    bot: Bot,
+    config: RwLock<Arc<Config>>,
+    sms: SmsClient,
+    api: ApiClient,
+    blacklist: RwLock<Blacklist>,
+    bans: RwLock<BanList>,
+    maintenance: RwLock<Maintenance>,
+    services: ServiceCache,
+    country_codes: CountryCodes,
+    rate_limiter: RateLimiter,
+    active_numbers: ActiveNumbers,
+    pending_adjustments: PendingAdjustments,
+    refunded_orders: RefundedOrders,
+    balance_monitor: BalanceMonitor,
+    pending_deposits: Mutex<PendingDeposits>,
+    preferences: RwLock<Preferences>,
+    rates: RwLock<Arc<Rates>>,
+}
+
+impl Context {
+    async fn handle_event(&self, event: Event) {
+        if let Event::InteractionCreate(interaction) = event {
+            self.handle_interaction(interaction.0).await;
+        }
+    }
+}
+
+#[cfg(unix)]
+fn spawn_reload_on_sighup(ctx: Arc<Context>) -> Result<(), anyhow::Error> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            if let Err(err) = ctx.reload_config() {
+                tracing::error!("Failed to reload config: {}", err);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+impl Context {
+    /// Load the data files and create the API clients, the bot is only used
+    /// once interactions are handled
+    pub fn new(bot: Bot, config: Config) -> Result<Self, anyhow::Error> {
+        let blacklist = Blacklist::load(config.storage.data_dir.clone())?;
+        let bans = BanList::load(config.storage.data_dir.clone())?;
+        let maintenance = Maintenance::load(config.storage.data_dir.clone())?;
+        let refunded_orders = RefundedOrders::load(config.storage.data_dir.clone())?;
+        let pending_deposits = PendingDeposits::load(config.storage.data_dir.clone())?;
+        let preferences = Preferences::load(config.storage.data_dir.clone())?;
+        let rates = match &config.currencies.file {
+            Some(file) => Rates::load(file).map_err(ConfigError::Invalid)?,
+            None => Rates::default(),
+        };
+
+        let sms = SmsClient::new(config.provider(), &config.fake_provider, config.timeouts.http);
+        if config.provider().is_fake() {
+            tracing::warn!("Using the fake SMS provider, orders are not real");
+        }
+        let api = ApiClient::new(
+            config.api.base_url.clone(),
+            config.secrets.admin_token.clone(),
+            config.timeouts.http,
+        );
+
+        Ok(Self {
+            bot,
+            sms,
+            api,
+            config: RwLock::new(Arc::new(config)),
+            blacklist: RwLock::new(blacklist),
+            bans: RwLock::new(bans),
+            maintenance: RwLock::new(maintenance),
+            services: ServiceCache::default(),
+            country_codes: CountryCodes::default(),
+            rate_limiter: RateLimiter::default(),
+            active_numbers: ActiveNumbers::default(),
+            pending_adjustments: PendingAdjustments::default(),
+            refunded_orders,
+            balance_monitor: BalanceMonitor::default(),
+            pending_deposits: Mutex::new(pending_deposits),
+            preferences: RwLock::new(preferences),
+            rates: RwLock::new(Arc::new(rates)),
+        })
+    }
+}
+
+/// Connect to Discord and handle interactions until the gateway connection
+/// fails
+pub async fn run(config: Config, prune_commands: bool) -> Result<(), anyhow::Error> {
+    let (bot, mut shards) = Bot::new(
+        config.secrets.discord_token.clone(),
+        Intents::empty(),
+        EventTypeFlags::INTERACTION_CREATE,
+    )
+    .await?;
+
+    tracing::info!("Connected as {}", bot.user.name);
+
+    let ctx = Arc::new(Context::new(bot, config)?);
+
+    #[cfg(unix)]
+    spawn_reload_on_sighup(Arc::clone(&ctx))?;
+    Arc::clone(&ctx).spawn_balance_monitor();
+    Arc::clone(&ctx).spawn_deposit_poller();
+    Arc::clone(&ctx).spawn_rates_reloader();
+
+    ctx.create_commands(prune_commands).await.unwrap_or_else(|err| {
+        tracing::error!("Failed to create commands:\n{}", err.backtrace());
+    });
+
+    let mut events = ShardEventStream::new(shards.iter_mut());
+    while let Some((_, event_res)) = events.next().await {
+        let ctx_event_ref = Arc::clone(&ctx);
+        match event_res {
+            Ok(event) => {
+                tokio::spawn(async move {
+                    ctx_event_ref.handle_event(event).await;
+                });
+            }
+            Err(err)
+                if !matches!(
+                    err.kind(),
+                    ReceiveMessageErrorType::Deserializing { .. } | ReceiveMessageErrorType::Io
+                ) =>
+            {
+                ctx_event_ref.bot.log(&err).await;
+
+                if err.is_fatal() {
+                    break;
+                }
+            }
+            Err(_) => {}
+        };
+    }
+
+    Ok(())
+}
@@ -1,209 +1,177 @@
 use fuzzywuzzy::fuzz;
+use std::cmp::Reverse;
 
+use crate::blacklist::Blacklist;
+use crate::config::ServiceMatchingConfig;
 use crate::sms::{get_country_prices::CountryPriceInfo, get_service_list::ServiceResponse};
 
-pub fn is_service_blacklisted(service: &str) -> bool {
-    let blacklisted_services = vec![
-        "5ka.ru",
-        "AdvCash",
-        "Afterpay",
-        "Airtm",
-        "Alipay",
-        "beCHARGE",
-        "Blackcatcard",
-        "BluePay",
-        "CashApp",
-        "CentroBill",
-        "Clearpay",
-        "CoinsBaron",
-        "Coinbase",
-        "CoinSwitch",
-        "CoinSpot",
-        "Coinstash",
-        "CornerCard",
-        "cPay",
-        "Easy Pay",
-        "Entropay",
-        "ePayments",
-        "GCash",
-        "Holvi",
-        "iCard",
-        "iPayYou",
-        "Joompay",
-        "Leupay",
-        "LocalBitcoins",
-        "Mezu",
-        "MuchBetter",
-        "MyBoost",
-        "Neteller",
-        "OKCoin",
-        "Papara",
-        "PayGo",
-        "Payoneer",
-        "Paypal",
-        "Paysafe",
-        "PaySay",
-        "PaySend",
-        "Paysera",
-        "Paytm",
-        "QIWIWallet",
-        "SimplexCC",
-        "Skrill",
-        "Steemit",
-        "Stripe",
-        "TransferWise",
-        "Venmo",
-        "Verse",
-        "Vimpay",
-        "Walmart money card",
-        "Webmoney",
-        "xcoins",
-        "ZapZap",
-        "zebpay",
-        "ZipCo",
-        "ZipPay",
-        "Abra",
-        "Akulaku",
-        "ANZ",
-        "Banq24",
-        "banxa",
-        "BeemIt",
-        "Billcom",
-        "Bunq",
-        "CapitalOne",
-        "Cardyard",
-        "CashAA",
-        "CashZine",
-        "CodaPayments",
-        "Cogni Bank",
-        "CreditKarma",
-        "Dukascopy",
-        "ecoPayz",
-        "eToro",
-        "EuroPYM",
-        "ExpertOption",
-        "FBS",
-        "FreshForex",
-        "FTX",
-        "Go2Bank",
-        "GoFundMe",
-        "GreenDot",
-        "instaforex",
-        "InstaGC",
-        "Instarem",
-        "IQOption",
-        "Jerry",
-        "JuanCash",
-        "KBZpay",
-        "KVBPrime",
-        "LydiaApp",
-        "Monese",
-        "Moneylion",
-        "MoneyPak",
-        "MoneyRawr",
-        "Monzo",
-        "Naver",
-        "NetBank",
-        "Netease",
-        "NiftyLoans",
-        "PayMaya",
-        "Paymium",
-        "PayQin",
-        "Phyre",
-        "RazerPay",
-        "Remitly",
-        "Revolut",
-        "Robinhood",
-        "SafeCurrency",
-        "sharemoney",
-        "SwissBorg",
-        "SwitchHere",
-        "tala",
-        "Tenx",
-        "The Change",
-        "Tikki",
-        "ToTalk",
-        "TradingView",
-        "TransferHome",
-        "Trustcom",
-        "UBank",
-        "Varo",
-        "Vodi",
-        "Voyager",
-        "WestStein",
-        "Wing",
-        "Wirex",
-        "wittix",
-        "Womply",
-        "X-Bank",
-        "Xoom",
-        "Yodlee",
-        "YouTrip",
-        "Zelle",
-        "Zest",
-        "Indacoin",
-        "Coinomi",
-        "Coinjar",
-    ];
-
-    for s in blacklisted_services {
-        if service.eq_ignore_ascii_case(s) {
-            return true;
-        }
-    }
-
-    if service.to_lowercase().contains("coin") {
-        return true;
-    } else if service.to_lowercase().contains("pay") {
-        return true;
-    } else if service.to_lowercase().contains("cash") {
-        return true;
-    } else if service.to_lowercase().contains("sell") {
-        return true;
-    }
-
-    false
-}
+/// Abbreviations and common misspellings of services, by what users type for
+/// them
+///
+/// Other brands of the same company aren't aliases, the provider may sell
+/// them as services of their own
+///
+/// `service_matching.aliases` adds to these and replaces the ones it repeats
+const BUILTIN_ALIASES: &[(&str, &str)] = &[
+    ("tg", "Telegram"),
+    ("tele", "Telegram"),
+    ("telegarm", "Telegram"),
+    ("wa", "WhatsApp"),
+    ("whatsap", "WhatsApp"),
+    ("watsapp", "WhatsApp"),
+    ("dc", "Discord"),
+    ("discrod", "Discord"),
+    ("fb", "Facebook"),
+    ("facebok", "Facebook"),
+    ("ig", "Instagram"),
+    ("insta", "Instagram"),
+    ("instagramm", "Instagram"),
+    ("x", "Twitter"),
+    ("twiter", "Twitter"),
+    ("tw", "Twitter"),
+    ("tt", "TikTok"),
+    ("snap", "Snapchat"),
+    ("ms", "Microsoft"),
+    ("amzn", "Amazon"),
+];
 
 pub struct SimilarServiceInfo {
     pub service_info: ServiceResponse,
     pub similarity_score: u8,
 }
 
+/// The services that match what the user typed best, most similar first and
+/// the more popular one of equally similar services first
+///
+/// An alias of a service matches it like its name does, unless a service has
+/// the alias as its name
 pub fn find_similar_services(
     desired_service: &str,
-    total_services: &Vec<ServiceResponse>,
+    total_services: &[ServiceResponse],
+    blacklist: &Blacklist,
+    matching: &ServiceMatchingConfig,
 ) -> Vec<SimilarServiceInfo> {
-    let mut similar_services: Vec<SimilarServiceInfo> = vec![];
-    for service in total_services {
-        if is_service_blacklisted(&service.name) {
-            continue;
-        }
-        let similarity_score = fuzz::ratio(
-            &desired_service.to_lowercase(),
-            &service.name.to_lowercase(),
-        );
+    let desired_service = normalize(desired_service);
+    if desired_service.is_empty() {
+        return vec![];
+    }
+    let alias =
+        alias_of(&desired_service, total_services, matching).map(|service| normalize(&service));
 
-        if similarity_score == 100 {
-            similar_services = vec![SimilarServiceInfo {
-                service_info: service.clone(),
-                similarity_score,
-            }];
-            return similar_services;
-        } else if similarity_score >= 55 {
-            similar_services.append(&mut vec![SimilarServiceInfo {
-                service_info: service.clone(),
-                similarity_score,
-            }]);
-        } else if service.name.contains(&desired_service) {
-            similar_services.append(&mut vec![SimilarServiceInfo {
+    let mut similar_services: Vec<SimilarServiceInfo> = total_services
+        .iter()
+        .filter(|service| !blacklist.is_blacklisted(&service.name))
+        .filter_map(|service| {
+            let name = normalize(&service.name);
+            let similarity_score = if alias.as_ref() == Some(&name) {
+                100
+            } else {
+                match_score(&desired_service, &name)
+            };
+
+            (similarity_score >= matching.min_score).then(|| SimilarServiceInfo {
                 service_info: service.clone(),
                 similarity_score,
-            }])
-        }
+            })
+        })
+        .collect();
+
+    similar_services.sort_by_key(|similar| {
+        (
+            Reverse(similar.similarity_score),
+            Reverse(similar.service_info.favourite),
+            similar.service_info.name.to_lowercase(),
+        )
+    });
+    similar_services.truncate(matching.max_results);
+
+    similar_services
+}
+
+/// The service the name is an alias of, so that it can be ordered under the
+/// name the provider knows it by
+pub fn resolve_alias(
+    name: &str,
+    services: &[ServiceResponse],
+    matching: &ServiceMatchingConfig,
+) -> Option<String> {
+    alias_of(&normalize(name), services, matching)
+}
+
+/// The service the input is an alias of, the configured aliases first, or
+/// `None` if one of the services is named like the input
+fn alias_of(
+    input: &str,
+    services: &[ServiceResponse],
+    matching: &ServiceMatchingConfig,
+) -> Option<String> {
+    if services
+        .iter()
+        .any(|service| normalize(&service.name) == input)
+    {
+        return None;
     }
 
-    return similar_services;
+    let configured = matching
+        .aliases
+        .iter()
+        .find(|(alias, _)| normalize(alias) == input)
+        .map(|(_, service)| service.clone());
+
+    configured.or_else(|| {
+        BUILTIN_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == input)
+            .map(|(_, service)| service.to_string())
+    })
+}
+
+/// How well the input matches the name out of 100, the best of comparing them
+/// whole, by their words and by the part of the name most like the input
+///
+/// Both have to be normalized
+fn match_score(input: &str, name: &str) -> u8 {
+    if input.is_empty() || name.is_empty() {
+        return 0;
+    }
+
+    let ratio = f32::from(fuzz::ratio(input, name));
+    // Word order and extra words, such as `google voice` for `Google`, count
+    // for little
+    let token_set = f32::from(fuzz::token_set_ratio(input, name, false, false)) * 0.95;
+    // Parts are only compared when the lengths differ, and count for less the
+    // more they do since a short input is part of many long names
+    let lengths = input.len().max(name.len()) as f32 / input.len().min(name.len()) as f32;
+    let partial_weight = if lengths < 1.5 {
+        0.0
+    } else if lengths < 8.0 {
+        0.9
+    } else {
+        0.6
+    };
+    let partial = f32::from(fuzz::partial_ratio(input, name)) * partial_weight;
+
+    ratio.max(token_set).max(partial).round() as u8
+}
+
+/// Lowercase ASCII letters and digits with single spaces between words, so
+/// `WhatsApp!` and `whatsapp` compare equal
+///
+/// The fuzzy matching compares bytes, which other characters have several of
+pub fn normalize(text: &str) -> String {
+    let text: String = text
+        .chars()
+        .filter(char::is_ascii)
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                ' '
+            }
+        })
+        .collect();
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
 pub struct SimilarCountryInfo {
@@ -213,28 +181,26 @@ pub struct SimilarCountryInfo {
 
 pub fn find_similar_countries(
     desired_country: &str,
-    total_countries: &Vec<CountryPriceInfo>,
+    total_countries: &[CountryPriceInfo],
 ) -> Vec<SimilarCountryInfo> {
     let mut similar_countries: Vec<SimilarCountryInfo> = vec![];
-    let mut similarity_score: u8;
     for country in total_countries {
-        if desired_country.len() <= 3 {
-            similarity_score =
-                fuzz::ratio(&desired_country.to_lowercase(), &country.iso.to_lowercase());
+        let similarity_score = if desired_country.len() <= 3 {
+            fuzz::ratio(&desired_country.to_lowercase(), &country.iso.to_lowercase())
         } else {
-            similarity_score = fuzz::ratio(
+            fuzz::ratio(
                 &desired_country.to_lowercase(),
                 &country.name.to_lowercase(),
-            );
-        }
+            )
+        };
 
         if similarity_score >= 55 {
-            similar_countries.append(&mut vec![SimilarCountryInfo {
+            similar_countries.push(SimilarCountryInfo {
                 country_info: country.clone(),
                 similarity_score,
-            }]);
+            });
         }
     }
 
-    return similar_countries;
+    similar_countries
 }
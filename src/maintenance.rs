@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::{PoisonError, RwLockReadGuard, RwLockWriteGuard},
+};
+use twilight_model::channel::message::Embed;
+
+use crate::{
+    store::{self, StoreError},
+    Context,
+};
+
+const FILE_NAME: &str = "maintenance.json";
+
+/// The contents of the maintenance data file
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+struct MaintenanceFile {
+    /// Every command but `/admin` is unavailable
+    enabled: bool,
+    /// Shown to users while maintenance mode is on
+    message: Option<String>,
+    /// Names of the commands that are unavailable
+    disabled_commands: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Maintenance {
+    path: PathBuf,
+    file: MaintenanceFile,
+}
+
+impl Maintenance {
+    pub fn load(data_dir: PathBuf) -> Result<Self, StoreError> {
+        let path = data_dir.join(FILE_NAME);
+        let file = store::load_json(&path)?.unwrap_or_default();
+
+        Ok(Self { path, file })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.file.enabled
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.file.message.as_deref()
+    }
+
+    pub fn disabled_commands(&self) -> &[String] {
+        &self.file.disabled_commands
+    }
+
+    pub fn is_command_disabled(&self, command: &str) -> bool {
+        self.file
+            .disabled_commands
+            .iter()
+            .any(|disabled| disabled == command)
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool, message: Option<String>) -> Result<(), StoreError> {
+        let previous = self.file.clone();
+
+        self.file.enabled = enabled;
+        self.file.message = message;
+        self.save_or_rollback(previous)
+    }
+
+    /// Returns whether the command wasn't disabled already
+    pub fn disable_command(&mut self, command: &str) -> Result<bool, StoreError> {
+        if self.is_command_disabled(command) {
+            return Ok(false);
+        }
+        let previous = self.file.clone();
+
+        self.file.disabled_commands.push(command.to_string());
+        self.save_or_rollback(previous)?;
+        Ok(true)
+    }
+
+    /// Returns whether the command was disabled
+    pub fn enable_command(&mut self, command: &str) -> Result<bool, StoreError> {
+        if !self.is_command_disabled(command) {
+            return Ok(false);
+        }
+        let previous = self.file.clone();
+
+        self.file.disabled_commands.retain(|disabled| disabled != command);
+        self.save_or_rollback(previous)?;
+        Ok(true)
+    }
+
+    /// Save the state, restoring the previous one in memory if that fails so
+    /// it doesn't differ from the file
+    fn save_or_rollback(&mut self, previous: MaintenanceFile) -> Result<(), StoreError> {
+        store::save_json(&self.path, &self.file).inspect_err(|_| self.file = previous)
+    }
+}
+
+impl Context {
+    pub fn maintenance(&self) -> RwLockReadGuard<'_, Maintenance> {
+        self.maintenance.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn maintenance_mut(&self) -> RwLockWriteGuard<'_, Maintenance> {
+        self.maintenance.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The embed to refuse the command with if it is unavailable, `/admin` is
+    /// always available so that it can be turned back on
+    pub fn unavailable_command_embed(
+        &self,
+        locale: Option<&str>,
+        command: &str,
+    ) -> Result<Option<Embed>, anyhow::Error> {
+        if command == "admin" {
+            return Ok(None);
+        }
+
+        let maintenance = self.maintenance();
+        let embed = if maintenance.is_enabled() {
+            match maintenance.message() {
+                Some(message) => self.render_template(
+                    locale,
+                    "maintenance_with_message",
+                    &[("message", &message)],
+                )?,
+                None => self.render_template(locale, "maintenance", &[])?,
+            }
+        } else if maintenance.is_command_disabled(command) {
+            self.render_template(locale, "command_disabled", &[("command", &command)])?
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(embed))
+    }
+}
@@ -0,0 +1,330 @@
+//! A stand-in for the website's API with an in-memory user table, to run the
+//! bot and its tests without the real backend
+//!
+//! It serves the endpoints `ApiClient` uses, answering with the same
+//! `{ success, message, resource }` envelope.
+
+use hyper::{
+    body,
+    header::AUTHORIZATION,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+use twilight_model::id::{marker::UserMarker, Id};
+
+use crate::api::{Invoice, InvoiceStatus, Number, Role, User};
+
+#[derive(Debug, Default)]
+struct Users {
+    users: Vec<User>,
+    next_invoice: u64,
+}
+
+#[derive(Debug)]
+pub struct MockBackend {
+    admin_token: String,
+    users: Mutex<Users>,
+}
+
+/// A user to seed the mock backend with, as read by the `mock_backend`
+/// binary
+#[derive(Deserialize, Debug)]
+pub struct SeedUser {
+    pub discord_id: Id<UserMarker>,
+    /// In cents
+    #[serde(default)]
+    pub balance: i32,
+    #[serde(default = "default_role")]
+    pub role: Role,
+}
+
+fn default_role() -> Role {
+    Role::User
+}
+
+#[derive(Deserialize)]
+struct PostNumber {
+    number: String,
+    service: String,
+    country: String,
+    price: i32,
+    #[serde(default)]
+    base_cost: Option<i32>,
+    order_id: String,
+    user_id: u32,
+}
+
+#[derive(Deserialize)]
+struct AdjustBalance {
+    amount: i32,
+}
+
+#[derive(Deserialize)]
+struct CreateInvoice {
+    amount: f64,
+}
+
+impl MockBackend {
+    /// Requests have to use `admin_token` like they do with the real backend
+    pub fn new(admin_token: impl Into<String>) -> Self {
+        Self {
+            admin_token: admin_token.into(),
+            users: Mutex::default(),
+        }
+    }
+
+    /// Create an account for the Discord user, returning its id
+    pub fn add_user(&self, discord_id: Id<UserMarker>, role: Role, balance: i32) -> u32 {
+        let mut users = self.users();
+        let id = users.users.len() as u32 + 1;
+        let now = chrono::Utc::now().to_rfc3339();
+        users.users.push(User {
+            balance,
+            created_at: now.clone(),
+            updated_at: now,
+            discord_id: discord_id.to_string(),
+            email: format!("user{}@example.com", id),
+            id,
+            image: String::new(),
+            name: format!("user{}", id),
+            role,
+            invoices: vec![],
+            numbers: vec![],
+        });
+
+        id
+    }
+
+    /// The account of the Discord user as the backend has it now
+    pub fn user(&self, discord_id: Id<UserMarker>) -> Option<User> {
+        self.users()
+            .users
+            .iter()
+            .find(|user| user.discord_id == discord_id.to_string())
+            .cloned()
+    }
+
+    /// Mark the invoice paid and add it to the balance, like the payment
+    /// processor's callback does
+    pub fn complete_invoice(&self, invoice_id: &str) -> bool {
+        let mut users = self.users();
+        for user in &mut users.users {
+            if let Some(invoice) = user
+                .invoices
+                .iter_mut()
+                .find(|invoice| invoice.id == invoice_id && invoice.status == InvoiceStatus::Pending)
+            {
+                invoice.status = InvoiceStatus::Completed;
+                invoice.url = None;
+                user.balance += (invoice.amount * 100.0).round() as i32;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Serve requests on `addr` in the background, returning the address
+    /// bound to, which is useful with port 0
+    pub fn spawn(self: Arc<Self>, addr: SocketAddr) -> Result<SocketAddr, hyper::Error> {
+        let server = Server::try_bind(&addr)?.serve(make_service_fn(move |_| {
+            let backend = Arc::clone(&self);
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let backend = Arc::clone(&backend);
+                    async move { Ok::<_, Infallible>(backend.handle(request).await) }
+                }))
+            }
+        }));
+        let addr = server.local_addr();
+
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                tracing::error!("Mock backend stopped: {}", err);
+            }
+        });
+
+        Ok(addr)
+    }
+
+    async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        let authorized = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .is_some_and(|token| token == self.admin_token);
+        if !authorized {
+            return failure(StatusCode::UNAUTHORIZED, "Unauthorized");
+        }
+
+        let method = request.method().clone();
+        let path = request.uri().path().to_string();
+        let query = request.uri().query().unwrap_or_default().to_string();
+        let body = match body::to_bytes(request.into_body()).await {
+            Ok(body) => body,
+            Err(err) => return failure(StatusCode::BAD_REQUEST, &err.to_string()),
+        };
+
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        match (method, segments.as_slice()) {
+            (Method::GET, ["user", "discord", discord_id]) => self.get_user(discord_id),
+            (Method::POST, ["user", "number"]) => match serde_json::from_slice(&body) {
+                Ok(number) => self.post_number(number),
+                Err(err) => failure(StatusCode::BAD_REQUEST, &err.to_string()),
+            },
+            (Method::PUT, ["user", "number", number, "received"]) => {
+                let discord_id = query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix("discord_id="))
+                    .unwrap_or_default();
+                self.mark_received(number, discord_id)
+            }
+            (Method::POST, ["user", "number", order_id, "refund"]) => self.refund(order_id),
+            (Method::POST, ["user", id, "balance"]) => match serde_json::from_slice(&body) {
+                Ok(AdjustBalance { amount }) => self.adjust_balance(id, amount),
+                Err(err) => failure(StatusCode::BAD_REQUEST, &err.to_string()),
+            },
+            (Method::POST, ["user", id, "invoice"]) => match serde_json::from_slice(&body) {
+                Ok(CreateInvoice { amount }) => self.create_invoice(id, amount),
+                Err(err) => failure(StatusCode::BAD_REQUEST, &err.to_string()),
+            },
+            _ => failure(StatusCode::NOT_FOUND, "Not found"),
+        }
+    }
+
+    fn get_user(&self, discord_id: &str) -> Response<Body> {
+        let users = self.users();
+        match users.users.iter().find(|user| user.discord_id == discord_id) {
+            Some(user) => success(json!(user)),
+            None => failure(StatusCode::NOT_FOUND, "User not found"),
+        }
+    }
+
+    fn post_number(&self, number: PostNumber) -> Response<Body> {
+        let mut users = self.users();
+        let Some(user) = users.users.iter_mut().find(|user| user.id == number.user_id) else {
+            return failure(StatusCode::NOT_FOUND, "User not found");
+        };
+
+        user.numbers.push(Number {
+            number: number.number,
+            service: number.service,
+            country: number.country,
+            price: number.price,
+            base_cost: number.base_cost,
+            order_id: number.order_id,
+            received: false,
+            created_at: Some(chrono::Utc::now().to_rfc3339()),
+            received_at: None,
+        });
+
+        success(Value::Null)
+    }
+
+    /// Numbers are only charged once they receive a code
+    fn mark_received(&self, number: &str, discord_id: &str) -> Response<Body> {
+        let mut users = self.users();
+        let Some(user) = users.users.iter_mut().find(|user| user.discord_id == discord_id) else {
+            return failure(StatusCode::NOT_FOUND, "User not found");
+        };
+        let Some(user_number) = user
+            .numbers
+            .iter_mut()
+            .rev()
+            .find(|user_number| user_number.number == number && !user_number.received)
+        else {
+            return failure(StatusCode::NOT_FOUND, "Number not found");
+        };
+
+        user_number.received = true;
+        user_number.received_at = Some(chrono::Utc::now().to_rfc3339());
+        user.balance -= user_number.price;
+
+        success(Value::Null)
+    }
+
+    fn refund(&self, order_id: &str) -> Response<Body> {
+        let mut users = self.users();
+        for user in &mut users.users {
+            if let Some(number) = user
+                .numbers
+                .iter()
+                .find(|number| number.order_id == order_id)
+            {
+                if !number.received {
+                    return failure(StatusCode::BAD_REQUEST, "The order wasn't charged");
+                }
+                user.balance += number.price;
+                return success(Value::Null);
+            }
+        }
+
+        failure(StatusCode::NOT_FOUND, "Order not found")
+    }
+
+    fn adjust_balance(&self, id: &str, amount: i32) -> Response<Body> {
+        let mut users = self.users();
+        let Some(user) = users.users.iter_mut().find(|user| user.id.to_string() == id) else {
+            return failure(StatusCode::NOT_FOUND, "User not found");
+        };
+        if user.balance + amount < 0 {
+            return failure(StatusCode::BAD_REQUEST, "Insufficient balance");
+        }
+
+        user.balance += amount;
+        success(Value::Null)
+    }
+
+    fn create_invoice(&self, id: &str, amount: f64) -> Response<Body> {
+        let mut users = self.users();
+        users.next_invoice += 1;
+        let invoice_id = format!("mock-invoice-{}", users.next_invoice);
+        let Some(user) = users.users.iter_mut().find(|user| user.id.to_string() == id) else {
+            return failure(StatusCode::NOT_FOUND, "User not found");
+        };
+
+        let invoice = Invoice {
+            url: Some(format!("https://example.com/pay/{}", invoice_id)),
+            id: invoice_id,
+            amount,
+            status: InvoiceStatus::Pending,
+            method: None,
+            created_at: Some(chrono::Utc::now().to_rfc3339()),
+        };
+        user.invoices.push(invoice.clone());
+
+        success(json!(invoice))
+    }
+
+    fn users(&self) -> MutexGuard<'_, Users> {
+        self.users.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn success(resource: Value) -> Response<Body> {
+    respond(
+        StatusCode::OK,
+        json!({ "success": true, "message": "OK", "resource": resource }),
+    )
+}
+
+fn failure(status: StatusCode, message: &str) -> Response<Body> {
+    respond(
+        status,
+        json!({ "success": false, "message": message, "resource": null }),
+    )
+}
+
+fn respond(status: StatusCode, body: Value) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+}
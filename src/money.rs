@@ -0,0 +1,84 @@
+//! Amounts of money written the way the locale of the user writes them
+
+use crate::currencies::Currency;
+
+/// How a locale writes an amount of money
+struct Format {
+    decimal: char,
+    group: char,
+    /// Whether the currency symbol comes before the amount, otherwise only the
+    /// currency code after it is shown
+    symbol_first: bool,
+}
+
+impl Format {
+    fn of(locale: Option<&str>) -> Self {
+        let (decimal, group, symbol_first) = match locale.unwrap_or_default() {
+            "pt-BR" | "nl" | "id" | "tr" => (',', '.', true),
+            "de" | "es-ES" | "it" | "da" | "hr" | "ro" | "el" | "vi" => (',', '.', false),
+            "fr" | "fi" | "sv-SE" | "no" | "pl" | "cs" | "hu" | "lt" | "ru" | "uk" | "bg" => {
+                (',', '\u{a0}', false)
+            }
+            _ => ('.', ',', true),
+        };
+
+        Self {
+            decimal,
+            group,
+            symbol_first,
+        }
+    }
+}
+
+/// The amount in USD, such as `$1,234.50 USD` in English or `1.234,50 USD` in
+/// German
+pub fn usd(amount: impl Into<f64>, locale: Option<&str>) -> String {
+    format(amount.into(), "USD", Some("$"), 2, locale)
+}
+
+/// The amount in the currency, such as `€12.00 EUR` in English or
+/// `12,00 EUR` in German
+pub fn amount(amount: f64, currency: &Currency, locale: Option<&str>) -> String {
+    format(
+        amount,
+        &currency.code,
+        currency.symbol.as_deref(),
+        currency.decimals,
+        locale,
+    )
+}
+
+fn format(
+    amount: f64,
+    code: &str,
+    symbol: Option<&str>,
+    decimals: u8,
+    locale: Option<&str>,
+) -> String {
+    let format = Format::of(locale);
+    let scale = 10_i64.pow(u32::from(decimals));
+    let units = (amount * scale as f64).round() as i64;
+    let sign = if units < 0 { "-" } else { "" };
+
+    let whole = (units.abs() / scale).to_string();
+    let mut number = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i).is_multiple_of(3) {
+            number.push(format.group);
+        }
+        number.push(digit);
+    }
+    if decimals > 0 {
+        number.push(format.decimal);
+        number.push_str(&format!(
+            "{:0width$}",
+            units.abs() % scale,
+            width = usize::from(decimals)
+        ));
+    }
+
+    match symbol {
+        Some(symbol) if format.symbol_first => format!("{}{}{} {}", sign, symbol, number, code),
+        _ => format!("{}{} {}", sign, number, code),
+    }
+}
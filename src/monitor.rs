@@ -0,0 +1,242 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+use twilight_model::channel::message::Embed;
+use twilight_util::builder::embed::EmbedFieldBuilder;
+
+use crate::{
+    money,
+    templates::Args,
+    Context,
+};
+
+/// How far back spending is looked at to estimate how long the balance lasts
+const SPEND_WINDOW_SECS: i64 = 6 * 60 * 60;
+
+#[derive(Debug, Default)]
+pub struct BalanceMonitor {
+    state: Mutex<MonitorState>,
+}
+
+#[derive(Debug, Default)]
+struct MonitorState {
+    /// Unix timestamps and balances in USD, oldest first
+    samples: VecDeque<(i64, f32)>,
+    /// The lowest threshold that was already warned about
+    warned_at: Option<f32>,
+    paused: bool,
+}
+
+/// What changed with a new balance, to be reported once the lock is released
+#[derive(Debug, Default, PartialEq)]
+pub struct Changes {
+    /// The lowest warning threshold the balance newly dropped below
+    pub crossed: Option<f32>,
+    /// Whether ordering was just paused or resumed
+    pub paused: Option<bool>,
+    pub hours_left: Option<f32>,
+}
+
+impl BalanceMonitor {
+    /// Whether ordering is paused because the balance is below the floor
+    pub fn is_paused(&self) -> bool {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .paused
+    }
+
+    /// Forget the balances and the pause, so that turning the monitor off
+    /// doesn't keep ordering paused and turning it back on starts over
+    pub fn reset(&self) {
+        *self.state.lock().unwrap_or_else(PoisonError::into_inner) = MonitorState::default();
+    }
+
+    /// How many hours the balance lasts at the recent rate of spending, `None`
+    /// if nothing was spent recently
+    pub fn hours_left(&self) -> Option<f32> {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .hours_left()
+    }
+
+    /// Add the balance at the unix timestamp `now`, returning the alerts and
+    /// pause it calls for
+    pub fn record(&self, now: i64, balance: f32, warn_at: &[f32], pause_below: f32) -> Changes {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        state.samples.push_back((now, balance));
+        while state
+            .samples
+            .front()
+            .is_some_and(|(at, _)| now - at > SPEND_WINDOW_SECS)
+        {
+            state.samples.pop_front();
+        }
+
+        let mut changes = Changes {
+            hours_left: state.hours_left(),
+            ..Changes::default()
+        };
+
+        let lowest_crossed = warn_at
+            .iter()
+            .copied()
+            .filter(|&threshold| balance < threshold)
+            .reduce(f32::min);
+        match (lowest_crossed, state.warned_at) {
+            (Some(crossed), Some(warned_at)) if crossed >= warned_at => {}
+            (Some(crossed), _) => changes.crossed = Some(crossed),
+            (None, _) => {}
+        }
+        // Going back above a threshold, such as after a top up, warns about it again
+        // the next time
+        state.warned_at = lowest_crossed;
+
+        let paused = pause_below > 0.0 && balance < pause_below;
+        if paused != state.paused {
+            state.paused = paused;
+            changes.paused = Some(paused);
+        }
+
+        changes
+    }
+}
+
+impl MonitorState {
+    fn hours_left(&self) -> Option<f32> {
+        let (first_at, _) = *self.samples.front()?;
+        let (last_at, balance) = *self.samples.back()?;
+        if last_at <= first_at {
+            return None;
+        }
+
+        // Only count what was spent, top ups would hide it
+        let spent: f32 = self
+            .samples
+            .iter()
+            .zip(self.samples.iter().skip(1))
+            .map(|((_, before), (_, after))| (before - after).max(0.0))
+            .sum();
+        if spent <= 0.0 {
+            return None;
+        }
+
+        let spent_per_hour = spent / ((last_at - first_at) as f32 / 3600.0);
+        Some(balance / spent_per_hour)
+    }
+}
+
+impl Context {
+    /// Poll the provider balance and warn about it in the log channel
+    pub fn spawn_balance_monitor(self: Arc<Self>) {
+        tokio::spawn(async move {
+            loop {
+                let config = self.config();
+                if config.balance_monitor.enabled {
+                    self.check_balance().await;
+                } else {
+                    self.balance_monitor.reset();
+                }
+
+                let interval = config.balance_monitor.interval_secs.max(1);
+                tokio::time::sleep(Duration::from_secs(interval)).await;
+            }
+        });
+    }
+
+    /// Check the provider balance once and post the alerts it calls for,
+    /// a balance that can't be checked is tried again on the next tick
+    pub async fn check_balance(&self) {
+        let balance = match self.sms.clone().get_api_balance().await {
+            Ok(balance) => balance,
+            Err(err) => {
+                tracing::error!("Unable to check the provider balance: {:?}", err);
+                return;
+            }
+        };
+
+        let config = self.config();
+        let changes = self.balance_monitor.record(
+            chrono::Utc::now().timestamp(),
+            balance,
+            &config.balance_monitor.warn_at,
+            config.balance_monitor.pause_below,
+        );
+
+        let mut alerts = vec![];
+        if let Some(threshold) = changes.crossed {
+            let threshold = money::usd(f64::from(threshold), None);
+            alerts.push(self.balance_alert(
+                "balance_low_log",
+                &[("threshold", &threshold)],
+                balance,
+                changes.hours_left,
+            ));
+        }
+        match changes.paused {
+            Some(true) => {
+                let pause_below = money::usd(f64::from(config.balance_monitor.pause_below), None);
+                alerts.push(self.balance_alert(
+                    "orders_paused_log",
+                    &[("pause_below", &pause_below)],
+                    balance,
+                    changes.hours_left,
+                ));
+            }
+            Some(false) => alerts.push(self.balance_alert(
+                "orders_resumed_log",
+                &[],
+                balance,
+                changes.hours_left,
+            )),
+            None => {}
+        }
+
+        for alert in alerts {
+            match alert {
+                Ok(embed) => {
+                    tracing::warn!(
+                        "{}: {}",
+                        embed.title.as_deref().unwrap_or_default(),
+                        embed.description.as_deref().unwrap_or_default()
+                    );
+                    self.send_log_pinging(config.balance_monitor.alert_role, embed)
+                        .await;
+                }
+                Err(err) => tracing::error!("Invalid balance alert embed: {}", err),
+            }
+        }
+    }
+
+    /// The alert of the template with the balance and how long it lasts, the
+    /// log channel is shared so it stays in English
+    fn balance_alert(
+        &self,
+        name: &str,
+        args: Args<'_>,
+        balance: f32,
+        hours_left: Option<f32>,
+    ) -> Result<Embed, anyhow::Error> {
+        let hours_left = match hours_left {
+            Some(hours) => format!("About `{:.1}` hours at the recent rate", hours),
+            None => "Unknown, nothing was spent recently".to_string(),
+        };
+
+        Ok(self
+            .template_embed(None, name, args)
+            .field(
+                EmbedFieldBuilder::new(
+                    "Balance:",
+                    format!("`{}`", money::usd(f64::from(balance), None)),
+                )
+                .inline(),
+            )
+            .field(EmbedFieldBuilder::new("Time Left:", hours_left).inline())
+            .validate()?
+            .build())
+    }
+}
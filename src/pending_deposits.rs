@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::{Arc, MutexGuard, PoisonError},
+    time::Duration,
+};
+use twilight_model::{
+    channel::message::Embed,
+    id::{marker::UserMarker, Id},
+};
+use twilight_util::builder::embed::EmbedFieldBuilder;
+
+use crate::{
+    api::InvoiceStatus,
+    money,
+    store::{self, StoreError},
+    Context,
+};
+
+const FILE_NAME: &str = "pending_deposits.json";
+/// Discord only allows editing an interaction's response for 15 minutes
+const INTERACTION_TOKEN_LIFETIME_SECS: i64 = 14 * 60;
+
+/// A deposit started with `/deposit` that hasn't been paid yet
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PendingDeposit {
+    pub invoice_id: String,
+    pub user_id: Id<UserMarker>,
+    /// In USD
+    pub amount: f64,
+    /// Unix timestamp
+    pub created_at: i64,
+    /// Used to update the `/deposit` reply while it can still be edited
+    pub interaction_token: String,
+    /// The locale of the user when they started the deposit, the
+    /// notification is sent in it
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+/// The contents of the pending deposits data file
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct PendingDepositsFile {
+    deposits: Vec<PendingDeposit>,
+}
+
+/// Pending deposits are kept on disk so a restart doesn't lose the
+/// notifications
+#[derive(Debug)]
+pub struct PendingDeposits {
+    path: PathBuf,
+    file: PendingDepositsFile,
+}
+
+impl PendingDeposits {
+    pub fn load(data_dir: PathBuf) -> Result<Self, StoreError> {
+        let path = data_dir.join(FILE_NAME);
+        let file = store::load_json(&path)?.unwrap_or_default();
+
+        Ok(Self { path, file })
+    }
+
+    pub fn all(&self) -> &[PendingDeposit] {
+        &self.file.deposits
+    }
+
+    pub fn insert(&mut self, deposit: PendingDeposit) -> Result<(), StoreError> {
+        self.file.deposits.push(deposit);
+        self.save().inspect_err(|_| {
+            self.file.deposits.pop();
+        })
+    }
+
+    /// Stop checking the invoice, it stays in memory only if saving fails so
+    /// the user isn't notified twice
+    pub fn remove(&mut self, invoice_id: &str) {
+        self.file
+            .deposits
+            .retain(|deposit| deposit.invoice_id != invoice_id);
+        if let Err(err) = self.save() {
+            tracing::error!("Failed to save pending deposits: {}", err);
+        }
+    }
+
+    fn save(&self) -> Result<(), StoreError> {
+        store::save_json(&self.path, &self.file)
+    }
+}
+
+impl Context {
+    pub fn pending_deposits(&self) -> MutexGuard<'_, PendingDeposits> {
+        self.pending_deposits
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Check pending deposits for payment and tell the users about the ones
+    /// that completed
+    pub fn spawn_deposit_poller(self: Arc<Self>) {
+        tokio::spawn(async move {
+            loop {
+                self.poll_deposits().await;
+
+                let interval = self.config().deposits.poll_interval_secs.max(1);
+                tokio::time::sleep(Duration::from_secs(interval)).await;
+            }
+        });
+    }
+
+    /// Check every pending deposit once, deposits that can't be checked are
+    /// tried again on the next poll
+    pub async fn poll_deposits(&self) {
+        let deposits = self.pending_deposits().all().to_vec();
+        let now = chrono::Utc::now().timestamp();
+        let expire_after_secs = i64::from(self.config().deposits.expire_after_hours) * 60 * 60;
+
+        for deposit in deposits {
+            let user = match self.api.get_user_data(deposit.user_id).await {
+                Ok(user) => user,
+                Err(err) => {
+                    tracing::error!("Unable to check deposit {}: {:?}", deposit.invoice_id, err);
+                    continue;
+                }
+            };
+
+            let status = user
+                .invoices
+                .iter()
+                .find(|invoice| invoice.id == deposit.invoice_id)
+                .map(|invoice| invoice.status);
+            let embed = match status {
+                Some(InvoiceStatus::Completed) => self.deposit_completed_embed(&deposit, user.balance),
+                Some(InvoiceStatus::Expired | InvoiceStatus::Cancelled) => {
+                    self.deposit_failed_embed(&deposit, "deposit_cancelled")
+                }
+                _ if now - deposit.created_at > expire_after_secs => {
+                    self.deposit_failed_embed(&deposit, "deposit_unpaid")
+                }
+                _ => continue,
+            };
+
+            self.pending_deposits().remove(&deposit.invoice_id);
+            match embed {
+                Ok(embed) => self.notify_deposit(&deposit, now, embed).await,
+                Err(err) => tracing::error!("Invalid deposit embed: {}", err),
+            }
+        }
+    }
+
+    /// Update the `/deposit` reply if it can still be edited, otherwise send
+    /// the embed to the user's DMs
+    async fn notify_deposit(&self, deposit: &PendingDeposit, now: i64, embed: Embed) {
+        let embeds = [embed];
+
+        if now - deposit.created_at < INTERACTION_TOKEN_LIFETIME_SECS {
+            let client = self.bot.interaction_client();
+            let update = client
+                .update_response(&deposit.interaction_token)
+                .embeds(Some(&embeds))
+                .and_then(|update| update.components(Some(&[])));
+            match update {
+                Ok(update) => match update.await {
+                    Ok(_) => return,
+                    Err(err) => tracing::info!("Unable to update /deposit reply: {}", err),
+                },
+                Err(err) => tracing::error!("Invalid /deposit reply update: {}", err),
+            }
+        }
+
+        let channel = match self.bot.http.create_private_channel(deposit.user_id).await {
+            Ok(response) => response.model().await,
+            Err(err) => {
+                tracing::info!("Unable to DM {} about their deposit: {}", deposit.user_id, err);
+                return;
+            }
+        };
+        let message = match channel {
+            Ok(channel) => self.bot.http.create_message(channel.id).embeds(&embeds),
+            Err(err) => {
+                tracing::error!("Invalid DM channel: {}", err);
+                return;
+            }
+        };
+        match message {
+            Ok(message) => {
+                if let Err(err) = message.await {
+                    tracing::info!("Unable to DM {} about their deposit: {}", deposit.user_id, err);
+                }
+            }
+            Err(err) => tracing::error!("Invalid deposit message: {}", err),
+        }
+    }
+
+    fn deposit_completed_embed(
+        &self,
+        deposit: &PendingDeposit,
+        balance: i32,
+    ) -> Result<Embed, anyhow::Error> {
+        let locale = deposit.locale.as_deref();
+        let balance_label = self.config().templates.label(locale, "Balance:");
+
+        Ok(self
+            .template_embed(
+                locale,
+                "deposit_received",
+                &[("amount", &money::usd(deposit.amount, locale))],
+            )
+            .field(
+                EmbedFieldBuilder::new(
+                    balance_label,
+                    format!("`{}`", money::usd(f64::from(balance) / 100.0, locale)),
+                )
+                .inline(),
+            )
+            .validate()?
+            .build())
+    }
+
+    /// The embed of the template for why the deposit wasn't received
+    fn deposit_failed_embed(
+        &self,
+        deposit: &PendingDeposit,
+        template: &str,
+    ) -> Result<Embed, anyhow::Error> {
+        let locale = deposit.locale.as_deref();
+        self.render_template(
+            locale,
+            template,
+            &[("amount", &money::usd(deposit.amount, locale))],
+        )
+    }
+}
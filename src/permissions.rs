@@ -0,0 +1,50 @@
+use std::fmt;
+
+use crate::api::Role;
+
+/// A privileged action, granted by the user's role on the website rather than
+/// their permissions in the guild, which guild admins can hand out freely
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Capability {
+    /// Look up another user's account with `/userdata`
+    ViewUserData,
+    /// See the bot's balance with the SMS provider with `/adminbal`
+    ViewBotBalance,
+    /// Ban, suspend and unban users
+    ManageBans,
+    /// Credit, debit and refund users, up to the role's cap
+    AdjustBalances,
+    /// Edit the service blacklist
+    ManageBlacklist,
+    /// Reload the config
+    ManageBot,
+}
+
+impl Capability {
+    /// The lowest role that has the capability
+    const fn min_role(self) -> Role {
+        match self {
+            Self::ViewUserData | Self::ManageBans | Self::AdjustBalances => Role::Support,
+            Self::ViewBotBalance | Self::ManageBlacklist | Self::ManageBot => Role::Admin,
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::ViewUserData => "view user data",
+            Self::ViewBotBalance => "view the bot balance",
+            Self::ManageBans => "manage bans",
+            Self::AdjustBalances => "adjust balances",
+            Self::ManageBlacklist => "manage the blacklist",
+            Self::ManageBot => "manage the bot",
+        })
+    }
+}
+
+impl Role {
+    pub fn can(self, capability: Capability) -> bool {
+        self >= capability.min_role()
+    }
+}
@@ -0,0 +1,76 @@
+use serde::Serialize;
+use std::fmt;
+use twilight_model::{channel::message::Embed, user::User};
+
+use crate::{audit::AuditEvent, Context};
+
+/// What a user tried to do with a service
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceAction {
+    Order,
+    PriceLookup,
+    Search,
+}
+
+impl fmt::Display for ServiceAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Order => "order",
+            Self::PriceLookup => "price lookup",
+            Self::Search => "search",
+        })
+    }
+}
+
+impl Context {
+    /// Whether a service may be offered to users at all, used where there is
+    /// nobody to refuse, such as suggestions and autocomplete
+    pub fn is_service_allowed(&self, service: &str) -> bool {
+        !self.blacklist().is_blacklisted(service)
+    }
+
+    /// Check a service a user asked for before it is ordered or priced
+    ///
+    /// Returns the embed to refuse the user with in their locale if the
+    /// service is blocked, and records the attempt in the audit log
+    pub async fn enforce_service_policy(
+        &self,
+        user: Option<&User>,
+        locale: Option<&str>,
+        service: &str,
+        action: ServiceAction,
+    ) -> Result<Option<Embed>, anyhow::Error> {
+        let Some(rule) = self.blacklist().check(service).cloned() else {
+            return Ok(None);
+        };
+
+        tracing::info!(
+            "Refused {} of blocked service {} matching {} rule `{}`",
+            action,
+            service,
+            rule.kind,
+            rule.pattern
+        );
+        self.audit(
+            user,
+            AuditEvent::BlockedServiceAttempted {
+                service: service.to_string(),
+                action,
+                pattern: rule.pattern,
+                category: rule.category,
+            },
+        )
+        .await;
+
+        Ok(Some(self.blocked_service_embed(locale, service)?))
+    }
+
+    pub fn blocked_service_embed(
+        &self,
+        locale: Option<&str>,
+        service: &str,
+    ) -> Result<Embed, anyhow::Error> {
+        self.render_template(locale, "service_blocked", &[("service", &service)])
+    }
+}
@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::{PoisonError, RwLockReadGuard, RwLockWriteGuard},
+};
+use twilight_model::id::{marker::UserMarker, Id};
+
+use crate::{
+    store::{self, StoreError},
+    Context,
+};
+
+const FILE_NAME: &str = "preferences.json";
+
+/// The order `/search prices` lists countries in when the command doesn't
+/// say
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSort {
+    #[default]
+    Price,
+    SuccessRate,
+}
+
+impl PriceSort {
+    /// The sort the user typed, such as `success rate`
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().replace(' ', "_").as_str() {
+            "price" => Some(Self::Price),
+            "success_rate" => Some(Self::SuccessRate),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Price => "price",
+            Self::SuccessRate => "success rate",
+        }
+    }
+}
+
+/// Where `/checksms` sends the codes it finds
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CodeDelivery {
+    /// In the reply to the command
+    #[default]
+    Reply,
+    /// In the user's DMs, in the reply if they don't accept DMs from the bot
+    Dm,
+}
+
+impl CodeDelivery {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "reply" => Some(Self::Reply),
+            "dm" | "dms" => Some(Self::Dm),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Reply => "reply",
+            Self::Dm => "dm",
+        }
+    }
+}
+
+/// What a user changed about how the bot answers them
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct UserPreferences {
+    /// The country `/getnumber` orders from when the command doesn't name one
+    #[serde(default)]
+    pub country: Option<String>,
+    #[serde(default)]
+    pub sort_by: PriceSort,
+    #[serde(default)]
+    pub code_delivery: CodeDelivery,
+    /// The code of the currency prices are also shown in, `None` for only USD
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// The Discord locale replies are in, `None` for the one of the user's
+    /// Discord client
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct UserEntry {
+    user_id: Id<UserMarker>,
+    #[serde(flatten)]
+    preferences: UserPreferences,
+}
+
+/// The contents of the preferences data file
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct PreferencesFile {
+    users: Vec<UserEntry>,
+}
+
+#[derive(Debug)]
+pub struct Preferences {
+    path: PathBuf,
+    file: PreferencesFile,
+}
+
+impl Preferences {
+    pub fn load(data_dir: PathBuf) -> Result<Self, StoreError> {
+        let path = data_dir.join(FILE_NAME);
+        let file = store::load_json(&path)?.unwrap_or_default();
+
+        Ok(Self { path, file })
+    }
+
+    /// The user's preferences, the defaults if they didn't change any
+    pub fn get(&self, user_id: Id<UserMarker>) -> UserPreferences {
+        self.file
+            .users
+            .iter()
+            .find(|entry| entry.user_id == user_id)
+            .map(|entry| entry.preferences.clone())
+            .unwrap_or_default()
+    }
+
+    /// Change the user's preferences, users left with only the defaults are
+    /// removed from the file
+    pub fn update(
+        &mut self,
+        user_id: Id<UserMarker>,
+        change: impl FnOnce(&mut UserPreferences),
+    ) -> Result<UserPreferences, StoreError> {
+        let previous_users = self.file.users.clone();
+
+        let mut preferences = self.get(user_id);
+        change(&mut preferences);
+        self.file.users.retain(|entry| entry.user_id != user_id);
+        if preferences != UserPreferences::default() {
+            self.file.users.push(UserEntry {
+                user_id,
+                preferences: preferences.clone(),
+            });
+        }
+        self.save_or_rollback(previous_users)?;
+
+        Ok(preferences)
+    }
+
+    /// Save the preferences, restoring the previous ones in memory if that
+    /// fails so they don't differ from the file
+    fn save_or_rollback(&mut self, previous_users: Vec<UserEntry>) -> Result<(), StoreError> {
+        store::save_json(&self.path, &self.file).inspect_err(|_| self.file.users = previous_users)
+    }
+}
+
+impl Context {
+    pub fn preferences(&self) -> RwLockReadGuard<'_, Preferences> {
+        self.preferences
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn preferences_mut(&self) -> RwLockWriteGuard<'_, Preferences> {
+        self.preferences
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+use twilight_model::id::{
+    marker::{GuildMarker, UserMarker},
+    Id,
+};
+
+/// Buckets are only dropped once there are this many, to not scan the map on
+/// every request
+const PRUNE_THRESHOLD: usize = 1024;
+/// The longest limit period, a bucket that wasn't used for this long is full
+const MAX_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LimitedAction {
+    Order,
+    CheckSms,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BucketKey {
+    User(LimitedAction, Id<UserMarker>),
+    Guild(LimitedAction, Id<GuildMarker>),
+}
+
+/// `amount` uses per `period`, refilled gradually
+#[derive(Clone, Copy, Debug)]
+pub struct Limit {
+    pub amount: u32,
+    pub period: Duration,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn full(limit: Limit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.amount),
+            updated_at: now,
+        }
+    }
+
+    /// Refill the bucket, the limit is passed in so that config reloads apply
+    /// to existing buckets
+    fn refill(&mut self, limit: Limit, now: Instant) {
+        let capacity = f64::from(limit.amount);
+        let per_second = capacity / limit.period.as_secs_f64();
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * per_second).min(capacity);
+        self.updated_at = now;
+    }
+
+    /// How long until a token is available, `None` if one is available now
+    fn wait_time(&self, limit: Limit) -> Option<Duration> {
+        if self.tokens >= 1.0 {
+            return None;
+        }
+
+        let per_second = f64::from(limit.amount) / limit.period.as_secs_f64();
+        Some(Duration::from_secs_f64((1.0 - self.tokens) / per_second))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<BucketKey, TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// Take a token from each bucket if all of them have one, otherwise take
+    /// none and return the bucket that is empty and how long until it refills
+    ///
+    /// Limits with an amount of 0 are ignored
+    pub fn try_acquire(&self, limits: &[(BucketKey, Limit)]) -> Result<(), (BucketKey, Duration)> {
+        self.try_acquire_at(limits, Instant::now())
+    }
+
+    /// [`Self::try_acquire`] as if it's `now`
+    pub fn try_acquire_at(
+        &self,
+        limits: &[(BucketKey, Limit)],
+        now: Instant,
+    ) -> Result<(), (BucketKey, Duration)> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let limits: Vec<_> = limits
+            .iter()
+            .filter(|(_, limit)| limit.amount > 0 && !limit.period.is_zero())
+            .collect();
+
+        for (key, limit) in &limits {
+            let bucket = buckets
+                .entry(*key)
+                .or_insert_with(|| TokenBucket::full(*limit, now));
+            bucket.refill(*limit, now);
+            if let Some(wait_time) = bucket.wait_time(*limit) {
+                return Err((*key, wait_time));
+            }
+        }
+
+        for (key, _) in &limits {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+
+        if buckets.len() > PRUNE_THRESHOLD {
+            // Full buckets behave the same as missing ones
+            buckets.retain(|_, bucket| now.duration_since(bucket.updated_at) < MAX_PERIOD);
+        }
+
+        Ok(())
+    }
+
+    /// Give a token back to each bucket, for uses that turned out not to count
+    pub fn refund(&self, limits: &[(BucketKey, Limit)]) {
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        for (key, limit) in limits {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.tokens = (bucket.tokens + 1.0).min(f64::from(limit.amount));
+            }
+        }
+    }
+}
+
+/// Numbers that were ordered through the bot and are still waiting for a
+/// code, by user
+#[derive(Debug, Default)]
+pub struct ActiveNumbers {
+    numbers: Mutex<HashMap<Id<UserMarker>, Vec<ActiveNumber>>>,
+}
+
+#[derive(Debug, Clone)]
+struct ActiveNumber {
+    order_id: String,
+    /// Unix timestamp
+    expires_at: i64,
+}
+
+impl ActiveNumbers {
+    pub fn insert(&self, user: Id<UserMarker>, order_id: String, expires_at: i64) {
+        let mut numbers = self.numbers.lock().unwrap_or_else(PoisonError::into_inner);
+        numbers.entry(user).or_default().push(ActiveNumber {
+            order_id,
+            expires_at,
+        });
+    }
+
+    pub fn remove(&self, user: Id<UserMarker>, order_id: &str) {
+        let mut numbers = self.numbers.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(user_numbers) = numbers.get_mut(&user) {
+            user_numbers.retain(|number| number.order_id != order_id);
+        }
+    }
+
+    /// The user's unexpired numbers and when the first of them expires
+    pub fn active(&self, user: Id<UserMarker>) -> (usize, Option<i64>) {
+        let now = chrono::Utc::now().timestamp();
+        let mut numbers = self.numbers.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let Some(user_numbers) = numbers.get_mut(&user) else {
+            return (0, None);
+        };
+        user_numbers.retain(|number| number.expires_at > now);
+        if user_numbers.is_empty() {
+            numbers.remove(&user);
+            return (0, None);
+        }
+
+        (
+            user_numbers.len(),
+            user_numbers.iter().map(|number| number.expires_at).min(),
+        )
+    }
+}
@@ -1,33 +1,56 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
-use super::{deserialize_to_float, SmsClient, API_URL};
+use super::SmsClient;
+use crate::decode;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SmsOrderInfo {
-    #[serde(rename = "cc")]
+    #[serde(rename = "cc", deserialize_with = "decode::string")]
     pub area_code: String,
-    #[serde(deserialize_with = "deserialize_to_float")]
+    #[serde(deserialize_with = "decode::number")]
     pub cost: f32,
     pub country: String,
+    #[serde(deserialize_with = "decode::number", default)]
     pub expires_in: i16,
+    #[serde(deserialize_with = "decode::number")]
     pub expiration: i64,
+    #[serde(default)]
     pub message: String,
+    #[serde(deserialize_with = "decode::number")]
     pub number: i64,
+    #[serde(deserialize_with = "decode::string")]
     pub order_id: String,
+    #[serde(deserialize_with = "decode::string")]
     pub phonenumber: String,
+    #[serde(deserialize_with = "decode::number", default)]
     pub pool: i8,
     pub service: String,
+    #[serde(deserialize_with = "decode::number", default)]
     pub success: i8,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SMSOrderError {
+    #[serde(deserialize_with = "decode::number", default)]
     pub success: u8,
+    #[serde(default)]
     pub message: String,
     #[serde(rename = "type")]
     pub error_type: String,
 }
 
+impl SMSOrderError {
+    /// The error for a response that couldn't be received or read
+    fn unreadable(err: impl fmt::Display) -> Self {
+        Self {
+            success: 0,
+            message: err.to_string(),
+            error_type: "INVALID_RESPONSE".to_string(),
+        }
+    }
+}
+
 impl SmsClient {
     pub async fn create_sms_order(
         self,
@@ -35,27 +58,26 @@ impl SmsClient {
         country: &str,
     ) -> Result<SmsOrderInfo, SMSOrderError> {
         let request = self
-            .client
-            .post(format!("{}/purchase/sms", API_URL))
-            .header(
-                reqwest::header::AUTHORIZATION,
-                format!("Bearer {}", self.api_key),
+            .post(
+                "/purchase/sms",
+                true,
+                &[
+                    ("service", service),
+                    ("country", country),
+                    ("pricing_option", "1"),
+                ],
             )
-            .form(&[
-                ("service", service),
-                ("country", country),
-                ("pricing_option", "1"),
-            ])
-            .send()
             .await
-            .unwrap();
+            .map_err(SMSOrderError::unreadable)?;
 
-        if request.status() == 200 {
-            let order_info = request.json::<SmsOrderInfo>().await.unwrap();
-            Ok(order_info)
+        if request.status == 200 {
+            request
+                .json::<SmsOrderInfo>()
+                .map_err(SMSOrderError::unreadable)
         } else {
-            let error = request.json::<SMSOrderError>().await.unwrap();
-            Err(error)
+            Err(request
+                .json::<SMSOrderError>()
+                .unwrap_or_else(SMSOrderError::unreadable))
         }
     }
 }
@@ -0,0 +1,360 @@
+//! An in-process stand-in for the SMS provider, answering the same requests
+//! with the same payloads so the bot can run without network access to the
+//! provider or a paid API key
+
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use super::SmsResponse;
+use crate::config::{FakeOperation, FakeProviderConfig};
+
+const SERVICES: [&str; 8] = [
+    "Discord",
+    "Telegram",
+    "WhatsApp",
+    "Google",
+    "Instagram",
+    "Twitter",
+    "Facebook",
+    "Uber",
+];
+
+struct FakeCountry {
+    name: &'static str,
+    iso: &'static str,
+    calling_code: &'static str,
+    /// In USD
+    price: f32,
+    success_rate: i32,
+}
+
+const COUNTRIES: [FakeCountry; 5] = [
+    FakeCountry {
+        name: "United States",
+        iso: "us",
+        calling_code: "1",
+        price: 0.50,
+        success_rate: 92,
+    },
+    FakeCountry {
+        name: "United Kingdom",
+        iso: "gb",
+        calling_code: "44",
+        price: 0.60,
+        success_rate: 88,
+    },
+    FakeCountry {
+        name: "Germany",
+        iso: "de",
+        calling_code: "49",
+        price: 0.70,
+        success_rate: 85,
+    },
+    FakeCountry {
+        name: "Canada",
+        iso: "ca",
+        calling_code: "1",
+        price: 0.55,
+        success_rate: 90,
+    },
+    FakeCountry {
+        name: "Netherlands",
+        iso: "nl",
+        calling_code: "31",
+        price: 0.65,
+        success_rate: 80,
+    },
+];
+
+#[derive(Debug)]
+struct FakeOrder {
+    service: String,
+    /// In USD
+    cost: f32,
+    /// Unix timestamp
+    ordered_at: i64,
+    /// Unix timestamp
+    expiration: i64,
+    /// Whether the code was received and the balance charged
+    delivered: bool,
+}
+
+#[derive(Debug)]
+struct FakeState {
+    /// In USD
+    balance: f32,
+    orders: HashMap<String, FakeOrder>,
+    next_order: u64,
+}
+
+#[derive(Debug)]
+pub struct FakeProvider {
+    config: FakeProviderConfig,
+    state: Mutex<FakeState>,
+}
+
+impl FakeProvider {
+    pub fn new(config: FakeProviderConfig) -> Self {
+        let state = FakeState {
+            balance: config.balance,
+            orders: HashMap::new(),
+            next_order: 1,
+        };
+
+        Self {
+            config,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Answer a request the way the provider would
+    pub(super) async fn respond(&self, path: &str, form: &[(&str, &str)]) -> SmsResponse {
+        if self.config.latency_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(self.config.latency_ms)).await;
+        }
+
+        let field = |name: &str| {
+            form.iter()
+                .find(|(key, _)| *key == name)
+                .map_or("", |(_, value)| *value)
+        };
+        let operation = match path {
+            "/purchase/sms" => FakeOperation::Purchase,
+            "/sms/check" => FakeOperation::CheckSms,
+            "/request/success_rate" => FakeOperation::CountryPrices,
+            "/service/retrieve_all" => FakeOperation::ServiceList,
+            "/request/balance" => FakeOperation::Balance,
+            _ => return response(404, &request_error("path", "Not found", "Unknown endpoint")),
+        };
+
+        if self.config.malformed.contains(&operation) {
+            return SmsResponse {
+                status: 200,
+                body: br#"{"success": 1, "data": "#.to_vec(),
+            };
+        }
+        if self.config.failing.contains(&operation) {
+            return response(400, &json!({ "success": 0 }));
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        match operation {
+            FakeOperation::Purchase => self.purchase(field("service"), field("country"), now),
+            FakeOperation::CheckSms => self.check_sms(field("orderid"), now),
+            FakeOperation::CountryPrices => self.country_prices(field("service")),
+            FakeOperation::ServiceList => response(
+                200,
+                &Value::Array(
+                    SERVICES
+                        .iter()
+                        .enumerate()
+                        .map(|(i, name)| json!({ "ID": i + 1, "name": name, "favourite": 0 }))
+                        .collect(),
+                ),
+            ),
+            FakeOperation::Balance => {
+                let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+                response(200, &json!({ "balance": format!("{:.2}", state.balance) }))
+            }
+        }
+    }
+
+    fn purchase(&self, service: &str, country: &str, now: i64) -> SmsResponse {
+        let Some(service) = find_service(service) else {
+            return response(400, &order_error("INVALID_SERVICE", "The service is not supported"));
+        };
+        if self.lists(&self.config.out_of_stock, service) {
+            return response(400, &order_error("OUT_OF_STOCK", "No numbers are in stock"));
+        }
+
+        let available: Vec<&FakeCountry> = COUNTRIES
+            .iter()
+            .filter(|fake_country| !self.lists(&self.config.unavailable_countries, fake_country.name))
+            .collect();
+        let fake_country = if country.trim().is_empty() {
+            available
+                .iter()
+                .min_by(|a, b| a.price.total_cmp(&b.price))
+                .copied()
+        } else {
+            available.iter().copied().find(|fake_country| {
+                fake_country.name.eq_ignore_ascii_case(country.trim())
+                    || fake_country.iso.eq_ignore_ascii_case(country.trim())
+            })
+        };
+        let Some(fake_country) = fake_country else {
+            return response(
+                400,
+                &order_error(
+                    "COUNTRY_NOT_AVAILABLE_FOR_SERVICE",
+                    "The country is not available for this service",
+                ),
+            );
+        };
+
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let order_id = format!("fake-{}", state.next_order);
+        let number = format!("{}555{:07}", fake_country.calling_code, state.next_order);
+        state.next_order += 1;
+
+        let lifetime = self.config.order_lifetime_secs as i64;
+        state.orders.insert(
+            order_id.clone(),
+            FakeOrder {
+                service: service.to_string(),
+                cost: fake_country.price,
+                ordered_at: now,
+                expiration: now + lifetime,
+                delivered: false,
+            },
+        );
+
+        response(
+            200,
+            &json!({
+                "cc": fake_country.calling_code,
+                "cost": format!("{:.2}", fake_country.price),
+                "country": fake_country.name,
+                "expires_in": lifetime / 60,
+                "expiration": now + lifetime,
+                "message": "Number purchased",
+                "number": number[fake_country.calling_code.len()..].parse::<i64>().unwrap_or_default(),
+                "order_id": order_id,
+                "phonenumber": number,
+                "pool": 1,
+                "service": service,
+                "success": 1,
+            }),
+        )
+    }
+
+    fn check_sms(&self, order_id: &str, now: i64) -> SmsResponse {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let FakeState {
+            balance, orders, ..
+        } = &mut *state;
+        let Some(order) = orders.get_mut(order_id) else {
+            return response(
+                400,
+                &request_error("orderid", "Invalid order", "No order exists with this id"),
+            );
+        };
+
+        let arrives_at = order.ordered_at + self.config.sms_delay_secs as i64;
+        let deliverable = !self.lists(&self.config.never_delivered, &order.service)
+            && now >= arrives_at
+            && arrives_at < order.expiration;
+        if deliverable && !order.delivered {
+            order.delivered = true;
+            *balance -= order.cost;
+        }
+
+        if order.delivered {
+            let code = fake_code(order_id);
+            return response(
+                200,
+                &json!({
+                    "status": 3,
+                    "message": "SMS received",
+                    "resend": 0,
+                    "expiration": order.expiration,
+                    "time_left": (order.expiration - now).max(0),
+                    "sms": code,
+                    "full_sms": format!("Your {} verification code is {}", order.service, code),
+                }),
+            );
+        }
+
+        if now >= order.expiration {
+            return response(
+                200,
+                &json!({
+                    "status": 5,
+                    "message": "Order expired",
+                    "expiration": order.expiration,
+                    "time_left": 0,
+                }),
+            );
+        }
+
+        response(
+            200,
+            &json!({
+                "status": 1,
+                "message": "Waiting for SMS",
+                "resend": 0,
+                "expiration": order.expiration,
+                "time_left": order.expiration - now,
+            }),
+        )
+    }
+
+    fn country_prices(&self, service: &str) -> SmsResponse {
+        let Some(service) = find_service(service) else {
+            // The provider answers unknown services with something that isn't
+            // a list of countries
+            return response(200, &json!({ "success": 0, "message": "Invalid service" }));
+        };
+        let out_of_stock = self.lists(&self.config.out_of_stock, service);
+
+        let countries = COUNTRIES
+            .iter()
+            .enumerate()
+            .filter(|(_, fake_country)| !self.lists(&self.config.unavailable_countries, fake_country.name))
+            .map(|(i, fake_country)| {
+                json!({
+                    "country_id": i + 1,
+                    "name": fake_country.name,
+                    "short_name": fake_country.iso.to_uppercase(),
+                    "price": format!("{:.2}", fake_country.price),
+                    "low_price": format!("{:.2}", fake_country.price * 0.8),
+                    "success_rate": if out_of_stock { 0 } else { fake_country.success_rate },
+                })
+            })
+            .collect();
+
+        response(200, &Value::Array(countries))
+    }
+
+    /// Whether the name is in the list, ignoring case
+    fn lists(&self, list: &[String], name: &str) -> bool {
+        list.iter().any(|listed| listed.eq_ignore_ascii_case(name))
+    }
+}
+
+fn find_service(service: &str) -> Option<&'static str> {
+    SERVICES
+        .iter()
+        .copied()
+        .find(|name| name.eq_ignore_ascii_case(service.trim()))
+}
+
+/// A six digit code that stays the same for an order
+fn fake_code(order_id: &str) -> String {
+    let hash = order_id
+        .bytes()
+        .fold(7_u32, |hash, byte| hash.wrapping_mul(31).wrapping_add(u32::from(byte)));
+    format!("{:06}", hash % 1_000_000)
+}
+
+fn order_error(error_type: &str, message: &str) -> Value {
+    json!({ "success": 0, "message": message, "type": error_type })
+}
+
+fn request_error(param: &str, message: &str, description: &str) -> Value {
+    json!({
+        "success": 0,
+        "errors": [{ "message": message, "param": param, "description": description }],
+    })
+}
+
+fn response(status: u16, body: &Value) -> SmsResponse {
+    SmsResponse {
+        status,
+        body: body.to_string().into_bytes(),
+    }
+}
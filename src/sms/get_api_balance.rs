@@ -1,32 +1,30 @@
 use serde::{Deserialize, Serialize};
 
-use super::{SMSResponseError, SmsClient, API_URL};
+use super::{SMSResponseError, SmsClient};
+use crate::decode;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BalanceResponseType {
-    pub balance: String,
+    #[serde(deserialize_with = "decode::number")]
+    pub balance: f32,
 }
 
 impl SmsClient {
     pub async fn get_api_balance(self) -> Result<f32, SMSResponseError> {
         let request = self
-            .client
-            .post(format!("{}/request/balance", API_URL))
-            .header(
-                reqwest::header::AUTHORIZATION,
-                format!("Bearer {}", self.api_key),
-            )
-            .send()
+            .post("/request/balance", true, &[])
             .await
-            .unwrap();
+            .map_err(|err| SMSResponseError::unreadable("balance", err))?;
 
-        if request.status() == 200 {
-            let balance_info = request.json::<BalanceResponseType>().await.unwrap();
-            let balance = balance_info.balance.parse::<f32>().unwrap();
-            return Ok(balance);
+        if request.status == 200 {
+            let balance_info = request
+                .json::<BalanceResponseType>()
+                .map_err(|err| SMSResponseError::unreadable("balance", err))?;
+            Ok(balance_info.balance)
         } else {
-            let error_info = request.json::<SMSResponseError>().await.unwrap();
-            return Err(error_info);
+            Err(request
+                .json::<SMSResponseError>()
+                .unwrap_or_else(|err| SMSResponseError::unreadable("balance", err)))
         }
     }
 }
@@ -1,35 +1,91 @@
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Mutex, PoisonError},
+};
 
-use super::{deserialize_to_float, ApiErrorInfo, SMSResponseError, SmsClient, API_URL};
+use super::{ApiErrorInfo, SMSResponseError, SmsClient};
+use crate::decode;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CountryPriceInfo {
+    #[serde(deserialize_with = "decode::number", default)]
     pub country_id: i32,
     pub name: String,
     #[serde(rename = "short_name")]
     pub iso: String,
-    #[serde(deserialize_with = "deserialize_to_float")]
+    #[serde(deserialize_with = "decode::number")]
     pub price: f32,
-    #[serde(deserialize_with = "deserialize_to_float")]
+    #[serde(deserialize_with = "decode::number")]
     pub low_price: f32,
+    #[serde(deserialize_with = "decode::number", default)]
     pub success_rate: i32,
 }
 
+/// ISO codes of the countries seen in country prices by lowercase name, for
+/// showing flags where only the country name is known
+#[derive(Debug, Default)]
+pub struct CountryCodes {
+    codes: Mutex<HashMap<String, String>>,
+    /// Countries looked up without finding their code, so they aren't looked
+    /// up again
+    missing: Mutex<HashSet<String>>,
+}
+
+impl CountryCodes {
+    pub fn get(&self, country: &str) -> Option<String> {
+        self.codes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&country.to_lowercase())
+            .cloned()
+    }
+
+    pub fn extend(&self, countries: &[CountryPriceInfo]) {
+        self.codes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend(
+                countries
+                    .iter()
+                    .map(|country| (country.name.to_lowercase(), country.iso.to_lowercase())),
+            );
+    }
+
+    /// Whether the country's code is known or was looked up without finding
+    /// it
+    pub fn looked_up(&self, country: &str) -> bool {
+        self.get(country).is_some()
+            || self
+                .missing
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .contains(&country.to_lowercase())
+    }
+
+    /// Remember that the country was looked up, if its code wasn't found
+    pub fn mark_looked_up(&self, country: &str) {
+        if self.get(country).is_none() {
+            self.missing
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(country.to_lowercase());
+        }
+    }
+}
+
 impl SmsClient {
     pub async fn get_country_prices(
         self,
         service: &str,
     ) -> Result<Vec<CountryPriceInfo>, SMSResponseError> {
         let request = self
-            .client
-            .post(format!("{}/request/success_rate", API_URL))
-            .form(&[("service", service)])
-            .send()
+            .post("/request/success_rate", false, &[("service", service)])
             .await
-            .unwrap();
+            .map_err(|err| SMSResponseError::unreadable("service", err))?;
 
-        if request.status() == 200 {
-            let service_info = request.json::<Vec<CountryPriceInfo>>().await;
+        if request.status == 200 {
+            let service_info = request.json::<Vec<CountryPriceInfo>>();
             // let service_info = request.json::<Value>().await;
             match service_info {
                 Ok(services) => Ok(services),
@@ -43,8 +99,9 @@ impl SmsClient {
                 }),
             }
         } else {
-            let error_info = request.json::<SMSResponseError>().await.unwrap();
-            return Err(error_info);
+            Err(request
+                .json::<SMSResponseError>()
+                .unwrap_or_else(|err| SMSResponseError::unreadable("service", err)))
         }
     }
 }
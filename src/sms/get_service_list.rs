@@ -1,30 +1,74 @@
 use serde::{Deserialize, Serialize};
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
 
-use super::{SMSResponseError, SmsClient, API_URL};
+use super::{SMSResponseError, SmsClient};
+use crate::{decode, Context};
+
+const SERVICE_LIST_MAX_AGE: Duration = Duration::from_secs(10 * 60);
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ServiceResponse {
-    #[serde(rename = "ID")]
+    #[serde(rename = "ID", deserialize_with = "decode::number")]
     pub id: i16,
     pub name: String,
+    #[serde(deserialize_with = "decode::number", default)]
     pub favourite: i8,
 }
 
+/// The service list rarely changes, so autocomplete and alias resolution
+/// reuse it for a while instead of requesting it on every keystroke
+#[derive(Debug, Default)]
+pub struct ServiceCache {
+    services: Mutex<Option<(Instant, Arc<Vec<ServiceResponse>>)>>,
+}
+
+impl ServiceCache {
+    pub fn get(&self, max_age: Duration) -> Option<Arc<Vec<ServiceResponse>>> {
+        let services = self.services.lock().unwrap_or_else(PoisonError::into_inner);
+        services
+            .as_ref()
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < max_age)
+            .map(|(_, services)| Arc::clone(services))
+    }
+
+    pub fn set(&self, services: Vec<ServiceResponse>) -> Arc<Vec<ServiceResponse>> {
+        let services = Arc::new(services);
+        *self.services.lock().unwrap_or_else(PoisonError::into_inner) =
+            Some((Instant::now(), Arc::clone(&services)));
+        services
+    }
+}
+
 impl SmsClient {
     pub async fn get_service_list(self) -> Result<Vec<ServiceResponse>, SMSResponseError> {
         let request = self
-            .client
-            .post(format!("{}/service/retrieve_all", API_URL))
-            .send()
+            .post("/service/retrieve_all", false, &[])
             .await
-            .unwrap();
+            .map_err(|err| SMSResponseError::unreadable("service", err))?;
 
-        if request.status() == 200 {
-            let service_info = request.json::<Vec<ServiceResponse>>().await.unwrap();
-            Ok(service_info)
+        if request.status == 200 {
+            request
+                .json::<Vec<ServiceResponse>>()
+                .map_err(|err| SMSResponseError::unreadable("service", err))
         } else {
-            let error_info = request.json::<SMSResponseError>().await.unwrap();
-            Err(error_info)
+            Err(request
+                .json::<SMSResponseError>()
+                .unwrap_or_else(|err| SMSResponseError::unreadable("service", err)))
+        }
+    }
+}
+
+impl Context {
+    /// The provider's services, requested again once the cached list is too old
+    pub(crate) async fn service_list(&self) -> Result<Arc<Vec<ServiceResponse>>, SMSResponseError> {
+        if let Some(services) = self.services.get(SERVICE_LIST_MAX_AGE) {
+            return Ok(services);
         }
+
+        let services = self.sms.clone().get_service_list().await?;
+        Ok(self.services.set(services))
     }
 }
@@ -1,41 +1,49 @@
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 
-use super::{SMSResponseError, SmsClient, API_URL};
+use super::{ApiErrorInfo, SMSResponseError, SmsClient};
+use crate::decode;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CheckSMSResponse {
+    #[serde(deserialize_with = "decode::number")]
     pub status: i8,
+    #[serde(default)]
     pub message: Option<String>,
+    #[serde(deserialize_with = "decode::optional_number", default)]
     pub resend: Option<i8>,
+    #[serde(deserialize_with = "decode::number")]
     pub expiration: i64,
+    #[serde(deserialize_with = "decode::optional_number", default)]
     pub time_left: Option<i32>,
+    #[serde(default)]
     pub sms: Option<String>,
+    #[serde(default)]
     pub full_sms: Option<String>,
 }
 
 impl SmsClient {
     pub async fn get_sms_code(self, order_id: &str) -> Result<CheckSMSResponse, SMSResponseError> {
         let request = self
-            .client
-            .post(format!("{}/sms/check", API_URL))
-            .header(
-                reqwest::header::AUTHORIZATION,
-                format!("Bearer {}", self.api_key),
-            )
-            .form(&[("orderid", order_id)])
-            .send()
+            .post("/sms/check", true, &[("orderid", order_id)])
             .await
-            .unwrap();
+            .map_err(|err| SMSResponseError::unreadable("orderid", err))?;
 
-        if request.status() == 200 {
-            let sms_info = request.json::<CheckSMSResponse>().await.unwrap();
-            Ok(sms_info)
+        if request.status == 200 {
+            match request.json::<CheckSMSResponse>() {
+                Ok(sms_info) => Ok(sms_info),
+                Err(err) => Err(SMSResponseError {
+                    success: 0,
+                    errors: vec![ApiErrorInfo {
+                        param: "orderid".to_string(),
+                        message: err.to_string(),
+                        description: "The response to the sms check could not be read.".to_string(),
+                    }],
+                }),
+            }
         } else {
-            let info = request.json::<Value>().await.unwrap();
-
-            let error_info = serde_json::from_value::<SMSResponseError>(info).unwrap();
-            Err(error_info)
+            Err(request
+                .json::<SMSResponseError>()
+                .unwrap_or_else(|err| SMSResponseError::unreadable("orderid", err)))
         }
     }
 }
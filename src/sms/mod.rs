@@ -1,48 +1,131 @@
-use serde::{Deserialize, Deserializer, Serialize};
-use std::str::FromStr;
-use std::time::Duration;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{fmt, sync::Arc, time::Duration};
+
+use crate::{
+    config::{FakeProviderConfig, ProviderConfig},
+    decode,
+};
+use fake::FakeProvider;
 
 pub mod create_sms_order;
+pub mod fake;
 pub mod get_api_balance;
 pub mod get_country_prices;
 pub mod get_service_list;
 pub mod get_sms_code;
 
-const API_URL: &str = "....";
+#[derive(Debug, Clone)]
+pub struct SmsClient {
+    transport: Transport,
+}
 
+/// Where the requests go, the fake provider answers them in process
 #[derive(Debug, Clone)]
+enum Transport {
+    Http {
+        client: reqwest::Client,
+        base_url: String,
+        api_key: String,
+    },
+    Fake(Arc<FakeProvider>),
+}
 
-pub struct SmsClient {
-    client: reqwest::Client,
-    api_key: String,
+/// The status and body of a provider response
+#[derive(Debug)]
+struct SmsResponse {
+    status: u16,
+    body: Vec<u8>,
+}
+
+impl SmsResponse {
+    fn json<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(&self.body)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ApiErrorInfo {
     pub message: String,
+    #[serde(default)]
     pub param: String,
+    #[serde(default)]
     pub description: String,
 }
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SMSResponseError {
+    #[serde(deserialize_with = "decode::number", default)]
     pub success: i8,
+    #[serde(default)]
     pub errors: Vec<ApiErrorInfo>,
 }
 
+impl SMSResponseError {
+    /// The error for a response that couldn't be received or read, `param`
+    /// is the request field it was for
+    fn unreadable(param: &str, err: impl fmt::Display) -> Self {
+        Self {
+            success: 0,
+            errors: vec![ApiErrorInfo {
+                param: param.to_string(),
+                message: err.to_string(),
+                description: "The response of the provider could not be read.".to_string(),
+            }],
+        }
+    }
+}
+
 impl SmsClient {
-    pub fn new(api_key: String) -> Self {
+    pub fn new(provider: &ProviderConfig, fake: &FakeProviderConfig, timeout: Duration) -> Self {
+        if provider.is_fake() {
+            return Self {
+                transport: Transport::Fake(Arc::new(FakeProvider::new(fake.clone()))),
+            };
+        }
+
         let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(30))
+            .timeout(timeout)
             .build()
             .unwrap();
-        return Self { client, api_key };
+        Self {
+            transport: Transport::Http {
+                client,
+                base_url: provider.base_url.clone(),
+                api_key: provider.api_key.clone(),
+            },
+        }
     }
-}
 
-fn deserialize_to_float<'de, D>(deserializer: D) -> Result<f32, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s: String = Deserialize::deserialize(deserializer)?;
-    f32::from_str(&s).map_err(serde::de::Error::custom)
+    /// Post the form to the provider, with the API key if `authorized`
+    async fn post(
+        &self,
+        path: &str,
+        authorized: bool,
+        form: &[(&str, &str)],
+    ) -> Result<SmsResponse, reqwest::Error> {
+        match &self.transport {
+            Transport::Http {
+                client,
+                base_url,
+                api_key,
+            } => {
+                let mut request = client.post(format!("{}{}", base_url, path));
+                if authorized {
+                    request = request.header(
+                        reqwest::header::AUTHORIZATION,
+                        format!("Bearer {}", api_key),
+                    );
+                }
+                if !form.is_empty() {
+                    request = request.form(form);
+                }
+
+                let response = request.send().await?;
+                let status = response.status().as_u16();
+                let body = response.bytes().await?.to_vec();
+
+                Ok(SmsResponse { status, body })
+            }
+            Transport::Fake(fake) => Ok(fake.respond(path, form).await),
+        }
+    }
 }
@@ -0,0 +1,78 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[error("failed to access {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+    #[error("{path} is not valid: {source}")]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+}
+
+/// Read a JSON data file, returning `None` if it doesn't exist yet
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, StoreError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(source) => {
+            return Err(StoreError::Io {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+    };
+
+    serde_json::from_str(&contents)
+        .map(Some)
+        .map_err(|source| StoreError::Json {
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+/// Write a JSON data file, replacing it only once the new contents are fully
+/// written so a crash can't leave it half written
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), StoreError> {
+    let io_err = |source| StoreError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    let contents = serde_json::to_string_pretty(value).map_err(|source| StoreError::Json {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(io_err)?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, contents).map_err(io_err)?;
+    fs::rename(&tmp_path, path).map_err(io_err)
+}
+
+/// Append a line to a file, creating it and its directory if needed
+pub fn append_line(path: &Path, line: &str) -> Result<(), StoreError> {
+    use io::Write;
+
+    let io_err = |source| StoreError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(io_err)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(io_err)?;
+    writeln!(file, "{}", line).map_err(io_err)
+}
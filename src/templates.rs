@@ -0,0 +1,597 @@
+//! The text of the embeds the bot replies with, kept out of the handlers so
+//! it can be changed and translated without recompiling
+
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+use twilight_model::{channel::message::Embed, util::Timestamp};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFooterBuilder};
+
+use crate::{
+    interaction::{MAX_MODAL_LABEL_LENGTH, MODAL_LABELS},
+    Context,
+};
+
+/// The built-in templates, which overrides are checked against
+const BUILTIN: &str = include_str!("templates.toml");
+
+/// The labels the handlers use, which translated labels are checked against
+const BUILTIN_LABELS: &str = include_str!("labels.txt");
+
+/// The locales Discord clients can be set to, which the locale files are
+/// named after
+pub const LOCALES: &[&str] = &[
+    "id", "da", "de", "en-GB", "en-US", "es-ES", "es-419", "fr", "hr", "it", "lt", "hu", "nl",
+    "no", "pl", "pt-BR", "ro", "fi", "sv-SE", "vi", "tr", "cs", "el", "bg", "ru", "uk", "hi", "th",
+    "zh-CN", "ja", "zh-TW", "ko",
+];
+
+/// Text by Discord locale, the way Discord takes command translations
+pub type Localizations = HashMap<String, String>;
+
+/// The values of the placeholders by name
+pub type Args<'a> = &'a [(&'a str, &'a (dyn Display + Sync))];
+
+/// Which of the configured colors an embed has
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Tone {
+    Success,
+    Error,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Template {
+    pub tone: Tone,
+    pub title: String,
+    pub description: String,
+}
+
+/// A template in the override file, the fields left out are kept from the
+/// built-in one
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct TemplateOverride {
+    tone: Option<Tone>,
+    title: Option<String>,
+    description: Option<String>,
+}
+
+/// The text of a locale file, anything it leaves out is shown in English
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Translation {
+    templates: HashMap<String, TranslatedTemplate>,
+    /// The names of embed fields, by their English name
+    labels: HashMap<String, String>,
+    /// By the space separated path of the command, subcommand, option or
+    /// choice, such as `search prices country`
+    commands: HashMap<String, TranslatedCommand>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+struct TranslatedTemplate {
+    title: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+struct TranslatedCommand {
+    name: Option<String>,
+    description: Option<String>,
+}
+
+/// The templates by name, their translations and what is added to every
+/// embed
+#[derive(Debug, Clone)]
+pub struct Templates {
+    templates: HashMap<String, Template>,
+    /// By Discord locale, such as `de` or `pt-BR`
+    locales: BTreeMap<String, Translation>,
+    pub footer: Option<String>,
+    pub timestamp: bool,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Self {
+            templates: toml::from_str(BUILTIN).expect("built-in templates are valid"),
+            locales: BTreeMap::new(),
+            footer: None,
+            timestamp: false,
+        }
+    }
+}
+
+impl Templates {
+    /// The built-in templates with the ones in `file` replacing them
+    ///
+    /// Returns every problem found with the file, such as templates that
+    /// don't exist or placeholders they don't have
+    pub fn load(file: &Path) -> Result<Self, Vec<String>> {
+        let contents = fs::read_to_string(file).map_err(|err| {
+            vec![format!(
+                "templates.file `{}` can't be read: {}",
+                file.display(),
+                err
+            )]
+        })?;
+        let overrides: HashMap<String, TemplateOverride> =
+            toml::from_str(&contents).map_err(|err| {
+                vec![format!(
+                    "templates.file `{}` is invalid: {}",
+                    file.display(),
+                    err
+                )]
+            })?;
+
+        let mut templates = Self::default();
+        let mut errors = vec![];
+        let mut names: Vec<_> = overrides.keys().cloned().collect();
+        names.sort();
+        for name in names {
+            let template_override = &overrides[&name];
+            let Some(template) = templates.templates.get_mut(&name) else {
+                errors.push(format!("templates.file: there is no template `{}`", name));
+                continue;
+            };
+
+            for (field, text, builtin) in [
+                ("title", &template_override.title, &template.title),
+                (
+                    "description",
+                    &template_override.description,
+                    &template.description,
+                ),
+            ] {
+                let Some(text) = text else {
+                    continue;
+                };
+                for placeholder in unknown_placeholders(text, builtin) {
+                    errors.push(format!(
+                        "templates.file: the {} of `{}` uses `{{{}}}`, which it doesn't have",
+                        field, name, placeholder
+                    ));
+                }
+            }
+
+            if let Some(tone) = template_override.tone {
+                template.tone = tone;
+            }
+            if let Some(title) = &template_override.title {
+                template.title.clone_from(title);
+            }
+            if let Some(description) = &template_override.description {
+                template.description.clone_from(description);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(templates)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Load the locale files in `dir`, each named after the Discord locale it
+    /// translates such as `de.toml`
+    ///
+    /// Returns every problem found with the files, such as templates, labels
+    /// or commands that don't exist
+    pub fn load_locales(&mut self, dir: &Path) -> Result<(), Vec<String>> {
+        let entries = fs::read_dir(dir).map_err(|err| {
+            vec![format!(
+                "templates.locales `{}` can't be read: {}",
+                dir.display(),
+                err
+            )]
+        })?;
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "toml")
+            })
+            .collect();
+        files.sort();
+
+        let commands = crate::interaction::command_paths();
+        let mut errors = vec![];
+        for file in files {
+            let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+            let Some(locale) = file
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .filter(|stem| LOCALES.contains(stem))
+            else {
+                errors.push(format!(
+                    "templates.locales: `{}` isn't named after a Discord locale such as `de.toml`",
+                    file_name
+                ));
+                continue;
+            };
+
+            let translation = fs::read_to_string(&file)
+                .map_err(|err| err.to_string())
+                .and_then(|contents| {
+                    toml::from_str::<Translation>(&contents).map_err(|err| err.to_string())
+                });
+            let translation = match translation {
+                Ok(translation) => translation,
+                Err(err) => {
+                    errors.push(format!(
+                        "templates.locales: `{}` is invalid: {}",
+                        file_name, err
+                    ));
+                    continue;
+                }
+            };
+
+            errors.extend(self.check_translation(&file_name, &translation, &commands));
+            self.locales.insert(locale.to_string(), translation);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// The problems with the templates, labels and commands the locale file
+    /// translates
+    fn check_translation(
+        &self,
+        file_name: &str,
+        translation: &Translation,
+        commands: &[(String, bool)],
+    ) -> Vec<String> {
+        let mut errors = vec![];
+
+        let mut names: Vec<&String> = translation.templates.keys().collect();
+        names.sort();
+        for name in names {
+            let Some(template) = self.templates.get(name) else {
+                errors.push(format!(
+                    "templates.locales: `{}` has no template `{}`",
+                    file_name, name
+                ));
+                continue;
+            };
+
+            let translated = &translation.templates[name];
+            for (field, text, builtin) in [
+                ("title", &translated.title, &template.title),
+                (
+                    "description",
+                    &translated.description,
+                    &template.description,
+                ),
+            ] {
+                let Some(text) = text else {
+                    continue;
+                };
+                for placeholder in unknown_placeholders(text, builtin) {
+                    errors.push(format!(
+                        "templates.locales: the {} of `{}` in `{}` uses `{{{}}}`, which it doesn't have",
+                        field, name, file_name, placeholder
+                    ));
+                }
+            }
+        }
+
+        let builtin_labels = labels();
+        let mut labels: Vec<&String> = translation.labels.keys().collect();
+        labels.sort();
+        for label in labels {
+            if !builtin_labels.contains(&label.as_str()) {
+                errors.push(format!(
+                    "templates.locales: `{}` has no label `{}`",
+                    file_name, label
+                ));
+                continue;
+            }
+
+            let translated = &translation.labels[label];
+            for placeholder in unknown_placeholders(translated, label) {
+                errors.push(format!(
+                    "templates.locales: the label `{}` in `{}` uses `{{{}}}`, which it doesn't have",
+                    label, file_name, placeholder
+                ));
+            }
+            if MODAL_LABELS.contains(&label.as_str())
+                && translated.chars().count() > MAX_MODAL_LABEL_LENGTH
+            {
+                errors.push(format!(
+                    "templates.locales: the label `{}` in `{}` is shown in a modal, which allows at most {} characters",
+                    label, file_name, MAX_MODAL_LABEL_LENGTH
+                ));
+            }
+        }
+
+        let mut paths: Vec<&String> = translation.commands.keys().collect();
+        paths.sort();
+        for path in paths {
+            let Some((_, choice)) = commands.iter().find(|(command, _)| command == path) else {
+                errors.push(format!(
+                    "templates.locales: `{}` has no command `{}`",
+                    file_name, path
+                ));
+                continue;
+            };
+
+            let translated = &translation.commands[path];
+            if let Some(name) = &translated.name {
+                let valid = if *choice {
+                    (1..=100).contains(&name.chars().count())
+                } else {
+                    (1..=32).contains(&name.chars().count())
+                        && name.to_lowercase() == *name
+                        && name
+                            .chars()
+                            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+                };
+                if !valid {
+                    errors.push(format!(
+                        "templates.locales: the name of `{}` in `{}` isn't a valid {}",
+                        path,
+                        file_name,
+                        if *choice {
+                            "choice name of at most 100 characters"
+                        } else {
+                            "command name, which is lowercase without spaces and at most 32 characters"
+                        }
+                    ));
+                }
+            }
+            if let Some(description) = &translated.description {
+                if *choice {
+                    errors.push(format!(
+                        "templates.locales: `{}` in `{}` is a choice, which has no description",
+                        path, file_name
+                    ));
+                } else if !(1..=100).contains(&description.chars().count()) {
+                    errors.push(format!(
+                        "templates.locales: the description of `{}` in `{}` must be 1 to 100 characters",
+                        path, file_name
+                    ));
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// The locale file for the locale, or one in the same language if there
+    /// is none for it, such as `es-ES` for `es-419`
+    fn translation(&self, locale: Option<&str>) -> Option<&Translation> {
+        let locale = locale?;
+        self.locales.get(locale).or_else(|| {
+            let language = locale.split('-').next()?;
+            self.locales
+                .iter()
+                .find(|(other, _)| other.split('-').next() == Some(language))
+                .map(|(_, translation)| translation)
+        })
+    }
+
+    /// The template in the locale, falling back to English for the text it
+    /// doesn't translate, and to one showing the name if the template doesn't
+    /// exist, which is a bug
+    pub fn get(&self, locale: Option<&str>, name: &str) -> Template {
+        let mut template = self.templates.get(name).cloned().unwrap_or_else(|| {
+            tracing::error!("There is no template `{}`", name);
+            Template {
+                tone: Tone::Error,
+                title: "Error".to_string(),
+                description: name.to_string(),
+            }
+        });
+
+        if let Some(translated) = self
+            .translation(locale)
+            .and_then(|translation| translation.templates.get(name))
+        {
+            if let Some(title) = &translated.title {
+                template.title.clone_from(title);
+            }
+            if let Some(description) = &translated.description {
+                template.description.clone_from(description);
+            }
+        }
+
+        template
+    }
+
+    /// The name of an embed field in the locale, or the English one if it
+    /// isn't translated
+    pub fn label(&self, locale: Option<&str>, label: &str) -> String {
+        self.translation(locale)
+            .and_then(|translation| translation.labels.get(label))
+            .map_or_else(|| label.to_string(), Clone::clone)
+    }
+
+    /// The translated names and descriptions of the command, subcommand,
+    /// option or choice by locale, `None` if no locale translates it
+    pub fn command_localizations(
+        &self,
+        path: &str,
+    ) -> (Option<Localizations>, Option<Localizations>) {
+        let (mut names, mut descriptions) = (HashMap::new(), HashMap::new());
+        for (locale, translation) in &self.locales {
+            let Some(translated) = translation.commands.get(path) else {
+                continue;
+            };
+            if let Some(name) = &translated.name {
+                names.insert(locale.clone(), name.clone());
+            }
+            if let Some(description) = &translated.description {
+                descriptions.insert(locale.clone(), description.clone());
+            }
+        }
+
+        (
+            Some(names).filter(|names| !names.is_empty()),
+            Some(descriptions).filter(|descriptions| !descriptions.is_empty()),
+        )
+    }
+
+    /// The locales with a locale file, sorted
+    pub fn locales(&self) -> Vec<&str> {
+        self.locales.keys().map(String::as_str).collect()
+    }
+
+    /// The names of the templates, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.templates.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// The English text of the labels the handlers use, in no particular order
+pub fn labels() -> Vec<&'static str> {
+    BUILTIN_LABELS
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
+
+/// Replace the placeholders in the text with the values of `args`, the ones
+/// without a value are left as they are
+pub fn fill(text: &str, args: Args<'_>) -> String {
+    parts(text)
+        .into_iter()
+        .map(|part| match part {
+            Part::Text(text) => text.to_string(),
+            Part::Placeholder(name) => match args.iter().find(|(arg, _)| *arg == name) {
+                Some((_, value)) => value.to_string(),
+                None => format!("{{{}}}", name),
+            },
+        })
+        .collect()
+}
+
+/// The names of the placeholders in the text
+fn placeholders(text: &str) -> Vec<&str> {
+    parts(text)
+        .into_iter()
+        .filter_map(|part| match part {
+            Part::Placeholder(name) => Some(name),
+            Part::Text(_) => None,
+        })
+        .collect()
+}
+
+/// The placeholders in the text that the built-in text doesn't have, so
+/// nothing would fill them in
+fn unknown_placeholders<'a>(text: &'a str, builtin: &str) -> Vec<&'a str> {
+    let allowed = placeholders(builtin);
+    placeholders(text)
+        .into_iter()
+        .filter(|placeholder| !allowed.contains(placeholder))
+        .collect()
+}
+
+enum Part<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Split the text into literal text and placeholders, `{{` and `}}` are
+/// literal braces
+fn parts(text: &str) -> Vec<Part<'_>> {
+    let mut parts = vec![];
+    let mut rest = text;
+    while let Some(i) = rest.find(['{', '}']) {
+        parts.push(Part::Text(&rest[..i]));
+        rest = &rest[i..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            parts.push(Part::Text(&rest[..1]));
+            rest = &rest[2..];
+        } else if let Some((name, after)) = rest
+            .strip_prefix('{')
+            .and_then(|placeholder| placeholder.split_once('}'))
+        {
+            parts.push(Part::Placeholder(name));
+            rest = after;
+        } else {
+            parts.push(Part::Text(&rest[..1]));
+            rest = &rest[1..];
+        }
+    }
+    parts.push(Part::Text(rest));
+
+    parts
+}
+
+impl Context {
+    /// An embed in the color of the tone, with the footer and timestamp every
+    /// embed has
+    pub fn embed(&self, tone: Tone, title: impl Into<String>) -> EmbedBuilder {
+        let config = self.config();
+        let mut embed = EmbedBuilder::new().title(title).color(match tone {
+            Tone::Success => config.colors.success,
+            Tone::Error => config.colors.error,
+        });
+
+        if let Some(footer) = &config.templates.footer {
+            embed = embed.footer(EmbedFooterBuilder::new(footer));
+        }
+        if config.templates.timestamp {
+            if let Ok(now) = Timestamp::from_secs(chrono::Utc::now().timestamp()) {
+                embed = embed.timestamp(now);
+            }
+        }
+
+        embed
+    }
+
+    /// The embed of the template in the locale with its placeholders filled
+    /// in, fields can still be added to it
+    pub fn template_embed(&self, locale: Option<&str>, name: &str, args: Args<'_>) -> EmbedBuilder {
+        let template = self.config().templates.get(locale, name);
+
+        self.embed(template.tone, fill(&template.title, args))
+            .description(fill(&template.description, args))
+    }
+
+    /// The embed of the template in the locale without any fields
+    pub fn render_template(
+        &self,
+        locale: Option<&str>,
+        name: &str,
+        args: Args<'_>,
+    ) -> Result<Embed, anyhow::Error> {
+        Ok(self.template_embed(locale, name, args).validate()?.build())
+    }
+
+    /// A generic error embed, for text that doesn't have a template such as
+    /// errors from the backend
+    pub fn error_embed(
+        &self,
+        locale: Option<&str>,
+        description: impl Into<String>,
+    ) -> Result<Embed, anyhow::Error> {
+        let description = description.into();
+        self.render_template(locale, "error", &[("message", &description)])
+    }
+
+    /// A generic success embed, for text that doesn't have a template
+    pub fn success_embed(
+        &self,
+        locale: Option<&str>,
+        description: impl Into<String>,
+    ) -> Result<Embed, anyhow::Error> {
+        let description = description.into();
+        self.render_template(locale, "success", &[("message", &description)])
+    }
+}
@@ -0,0 +1,236 @@
+mod common;
+
+use altera_client::{adjustment::RefundedOrders, api::Role, interaction::respond::Recording};
+use common::{Harness, USER_ID};
+use serde_json::{json, Value};
+use std::{env, fs};
+use twilight_model::{channel::message::Component, id::Id};
+
+/// The user whose balance is adjusted, the default user is the moderator
+const TARGET: u64 = 2000;
+
+fn harness(balance: i32) -> Harness {
+    with_target(Harness::new(), balance)
+}
+
+/// Make the default user an admin and create the account of the target
+fn with_target(harness: Harness, balance: i32) -> Harness {
+    harness.register_as(Role::Admin, 0);
+    harness
+        .backend
+        .add_user(Id::new(TARGET), Role::User, balance);
+    harness
+}
+
+fn balance(harness: &Harness) -> i32 {
+    harness.backend.user(Id::new(TARGET)).unwrap().balance
+}
+
+async fn propose(harness: &Harness, subcommand: &str, value: Value) -> Recording {
+    let (name, kind) = match subcommand {
+        "refund" => ("order", 3),
+        _ => ("amount", 10),
+    };
+    let options = json!([{
+        "name": subcommand,
+        "type": 1,
+        "options": [
+            { "name": "user", "type": 6, "value": TARGET.to_string() },
+            { "name": name, "type": kind, "value": value },
+            { "name": "reason", "type": 3, "value": "testing" },
+        ],
+    }]);
+
+    harness
+        .command_with_users("admin", options, &[TARGET])
+        .await
+}
+
+/// The custom ID of the button with the action on the confirmation message
+fn button(recording: &Recording, action: &str) -> String {
+    recording
+        .replies()
+        .into_iter()
+        .flat_map(|reply| reply.components)
+        .flat_map(|row| match row {
+            Component::ActionRow(row) => row.components,
+            _ => vec![],
+        })
+        .find_map(|component| {
+            match component {
+                Component::Button(button) => button.custom_id,
+                _ => None,
+            }
+            .filter(|custom_id| custom_id.contains(&format!(":{}:", action)))
+        })
+        .unwrap()
+}
+
+async fn click(harness: &Harness, user_id: u64, custom_id: &str) -> Option<String> {
+    let recording = harness
+        .run(common::component(user_id, custom_id, &[]))
+        .await;
+
+    recording.embeds().pop().and_then(|embed| embed.description)
+}
+
+fn error(recording: &Recording) -> Option<String> {
+    recording.embeds().pop().and_then(|embed| embed.description)
+}
+
+#[tokio::test]
+async fn credit_is_made_once_confirmed() {
+    let harness = harness(500);
+
+    let proposal = propose(&harness, "credit", json!(5.0)).await;
+    assert_eq!(balance(&harness), 500);
+
+    click(&harness, USER_ID, &button(&proposal, "confirm")).await;
+    assert_eq!(balance(&harness), 1000);
+
+    // The confirmation is used up
+    let expired = click(&harness, USER_ID, &button(&proposal, "confirm")).await;
+    assert_eq!(
+        expired.as_deref(),
+        Some("This adjustment has expired, run the command again.")
+    );
+    assert_eq!(balance(&harness), 1000);
+}
+
+#[tokio::test]
+async fn only_the_moderator_can_cancel() {
+    let harness = harness(500);
+    let proposal = propose(&harness, "debit", json!(2.0)).await;
+
+    let refused = click(&harness, 3000, &button(&proposal, "cancel")).await;
+    assert_eq!(
+        refused.as_deref(),
+        Some("Only the moderator who ran the command can confirm it.")
+    );
+
+    // The adjustment is still pending for the moderator
+    click(&harness, USER_ID, &button(&proposal, "confirm")).await;
+    assert_eq!(balance(&harness), 300);
+}
+
+#[tokio::test]
+async fn debit_beyond_the_balance_is_refused() {
+    let harness = harness(100);
+
+    let recording = propose(&harness, "debit", json!(2.0)).await;
+    assert_eq!(
+        error(&recording).as_deref(),
+        Some("**@user2000** only has $1.00 USD, debit at most that much.")
+    );
+}
+
+#[tokio::test]
+async fn balance_overflow_is_refused() {
+    let harness = harness(i32::MAX - 100);
+
+    let recording = propose(&harness, "credit", json!(5.0)).await;
+    assert_eq!(
+        error(&recording).as_deref(),
+        Some("**@user2000**'s balance can't be adjusted by that much.")
+    );
+}
+
+#[tokio::test]
+async fn amount_above_the_maximum_is_refused() {
+    let harness = harness(0);
+
+    // Discord doesn't send amounts above the maximum either
+    let recording = propose(&harness, "credit", json!(10_000.01)).await;
+    assert!(recording.replies().is_empty());
+}
+
+#[tokio::test]
+async fn orders_are_refunded_once() {
+    let harness = with_target(
+        Harness::with_config(|config| config.fake_provider.sms_delay_secs = 0),
+        500,
+    );
+    let getnumber = json!([
+        { "name": "service", "type": 3, "value": "Discord" },
+        { "name": "country", "type": 3, "value": "Germany" },
+    ]);
+    harness
+        .run(common::command(TARGET, "getnumber", getnumber))
+        .await;
+    harness
+        .run(common::command(TARGET, "checksms", json!([])))
+        .await;
+    let charged = balance(&harness);
+    assert!(charged < 500);
+
+    let first = propose(&harness, "refund", json!("fake-1")).await;
+    let second = propose(&harness, "refund", json!("fake-1")).await;
+
+    click(&harness, USER_ID, &button(&first, "confirm")).await;
+    assert_eq!(balance(&harness), 500);
+
+    let refused = click(&harness, USER_ID, &button(&second, "confirm")).await;
+    assert_eq!(
+        refused.as_deref(),
+        Some("Order `fake-1` was already refunded.")
+    );
+    assert_eq!(balance(&harness), 500);
+
+    let recording = propose(&harness, "refund", json!("fake-1")).await;
+    assert_eq!(
+        error(&recording).as_deref(),
+        Some("Order `fake-1` was already refunded.")
+    );
+}
+
+#[test]
+fn refunds_are_reserved_and_saved() {
+    let dir = env::temp_dir().join(format!(
+        "altera-client-refunds-{}-reserved",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+
+    let orders = RefundedOrders::load(dir.clone()).unwrap();
+    assert!(orders.reserve("a"));
+    // A second confirmation can't refund it while the first one is made
+    assert!(!orders.reserve("a"));
+    assert!(orders.contains("a"));
+
+    // A failed refund can be retried
+    orders.release("a");
+    assert!(!orders.contains("a"));
+    assert!(orders.reserve("a"));
+    orders.finish("a").unwrap();
+    assert!(!orders.reserve("a"));
+
+    let reloaded = RefundedOrders::load(dir.clone()).unwrap();
+    assert!(reloaded.contains("a"));
+    assert!(!reloaded.contains("b"));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn refunds_are_taken_from_the_audit_log() {
+    let dir = env::temp_dir().join(format!(
+        "altera-client-refunds-{}-audit",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("audit.log"),
+        [
+            r#"{"timestamp":0,"event":"balance_adjusted","kind":"refund","order_id":"a"}"#,
+            r#"{"timestamp":0,"event":"balance_adjusted","kind":"credit","order_id":null}"#,
+            "not json",
+        ]
+        .join("\n"),
+    )
+    .unwrap();
+
+    let orders = RefundedOrders::load(dir.clone()).unwrap();
+    assert!(orders.contains("a"));
+    assert!(dir.join("refunded_orders.json").exists());
+    let _ = fs::remove_dir_all(dir);
+}
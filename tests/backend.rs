@@ -0,0 +1,181 @@
+use altera_client::{
+    api::{ApiClient, ApiError, InvoiceStatus, OrderPrice, Role},
+    config::{FakeProviderConfig, ProviderConfig, FAKE_PROVIDER_URL},
+    mock_backend::MockBackend,
+    sms::SmsClient,
+};
+use std::{sync::Arc, time::Duration};
+use twilight_model::id::Id;
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Start a mock backend on a free port, returning it and its base URL
+fn start_backend() -> (Arc<MockBackend>, String) {
+    let backend = Arc::new(MockBackend::new(ADMIN_TOKEN));
+    let addr = Arc::clone(&backend)
+        .spawn(([127, 0, 0, 1], 0).into())
+        .unwrap();
+
+    (backend, format!("http://{}", addr))
+}
+
+fn api(base_url: &str) -> ApiClient {
+    ApiClient::new(base_url.to_string(), ADMIN_TOKEN.to_string(), TIMEOUT)
+}
+
+/// The message the backend refused the request with
+fn rejection(err: ApiError) -> String {
+    match err {
+        ApiError::Rejected(response) => response.message,
+        err => panic!("expected the backend to refuse, got {:?}", err),
+    }
+}
+
+fn fake_sms(fake: FakeProviderConfig) -> SmsClient {
+    let provider = ProviderConfig {
+        name: "fake".to_string(),
+        base_url: FAKE_PROVIDER_URL.to_string(),
+        api_key: String::new(),
+        enabled: true,
+    };
+
+    SmsClient::new(&provider, &fake, TIMEOUT)
+}
+
+#[tokio::test]
+async fn balance() {
+    let (backend, base_url) = start_backend();
+    let api = api(&base_url);
+    backend.add_user(Id::new(1), Role::User, 1234);
+
+    let user = api.get_user_data(Id::new(1)).await.unwrap();
+    assert_eq!(user.balance, 1234);
+    assert_eq!(user.role, Role::User);
+
+    let missing = api.get_user_data(Id::new(2)).await.unwrap_err();
+    assert_eq!(rejection(missing), "User not found");
+}
+
+#[tokio::test]
+async fn wrong_admin_token_is_rejected() {
+    let (backend, base_url) = start_backend();
+    backend.add_user(Id::new(1), Role::User, 1234);
+    let api = ApiClient::new(base_url, "wrong".to_string(), TIMEOUT);
+
+    let err = api.get_user_data(Id::new(1)).await.unwrap_err();
+    assert_eq!(rejection(err), "Unauthorized");
+}
+
+#[tokio::test]
+async fn purchase_is_not_charged_until_received() {
+    let (backend, base_url) = start_backend();
+    let api = api(&base_url);
+    let account_id = backend.add_user(Id::new(1), Role::User, 500);
+    let sms = fake_sms(FakeProviderConfig::default());
+
+    let order = sms.clone().create_sms_order("discord", "").await.unwrap();
+    api.post_user_number(
+        &order.number.to_string(),
+        &order.service,
+        &order.country,
+        OrderPrice {
+            price: (order.cost * 100.0) as i32,
+            base_cost: (order.cost * 100.0) as i32,
+        },
+        &order.order_id,
+        account_id,
+    )
+    .await
+    .unwrap();
+
+    let user = backend.user(Id::new(1)).unwrap();
+    assert_eq!(user.balance, 500);
+    assert_eq!(user.numbers.len(), 1);
+    assert_eq!(user.numbers[0].order_id, order.order_id);
+    assert!(!user.numbers[0].received);
+    assert!(user.numbers[0].ordered_at().is_some());
+
+    let sms_code = sms.get_sms_code(&order.order_id).await.unwrap();
+    assert_eq!(sms_code.status, 1);
+    assert!(sms_code.sms.is_none());
+}
+
+#[tokio::test]
+async fn receipt_charges_the_number() {
+    let (backend, base_url) = start_backend();
+    let api = api(&base_url);
+    let account_id = backend.add_user(Id::new(1), Role::User, 500);
+    let sms = fake_sms(FakeProviderConfig {
+        sms_delay_secs: 0,
+        ..FakeProviderConfig::default()
+    });
+
+    let order = sms.clone().create_sms_order("discord", "Germany").await.unwrap();
+    let base_cost = (order.cost * 100.0).round() as i32;
+    let price = base_cost + 20;
+    api.post_user_number(
+        &order.number.to_string(),
+        &order.service,
+        &order.country,
+        OrderPrice { price, base_cost },
+        &order.order_id,
+        account_id,
+    )
+    .await
+    .unwrap();
+
+    let sms_code = sms.get_sms_code(&order.order_id).await.unwrap();
+    assert_eq!(sms_code.status, 3);
+    assert!(sms_code.full_sms.unwrap().contains(&sms_code.sms.unwrap()));
+
+    api.mark_number_received(order.number.to_string(), "1".to_string())
+        .await
+        .unwrap();
+
+    let user = api.get_user_data(Id::new(1)).await.unwrap();
+    assert_eq!(user.balance, 500 - price);
+    assert!(user.numbers[0].received);
+    assert_eq!(user.numbers[0].base_cost, Some(base_cost));
+    assert!(user.numbers[0].received_at().is_some());
+
+    // A number is only charged once
+    let err = api
+        .mark_number_received(order.number.to_string(), "1".to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(rejection(err), "Number not found");
+    assert_eq!(backend.user(Id::new(1)).unwrap().balance, 500 - price);
+}
+
+#[tokio::test]
+async fn deposit_completes() {
+    let (backend, base_url) = start_backend();
+    let api = api(&base_url);
+    let account_id = backend.add_user(Id::new(1), Role::User, 0);
+
+    let invoice = api.create_invoice(account_id, 12.5).await.unwrap();
+    assert_eq!(invoice.status, InvoiceStatus::Pending);
+    assert!(invoice.url.is_some());
+
+    assert!(backend.complete_invoice(&invoice.id));
+
+    let user = api.get_user_data(Id::new(1)).await.unwrap();
+    assert_eq!(user.balance, 1250);
+    assert_eq!(user.invoices[0].status, InvoiceStatus::Completed);
+}
+
+#[tokio::test]
+async fn backend_unreachable() {
+    // Nothing listens on the port once the listener is dropped
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let api = api(&format!("http://{}", addr));
+
+    let err = api.get_user_data(Id::new(1)).await.unwrap_err();
+    assert!(matches!(err, ApiError::Request(_)), "{:?}", err);
+    let err = api.create_invoice(1, 10.0).await.unwrap_err();
+    assert!(matches!(err, ApiError::Request(_)), "{:?}", err);
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use altera_client::{
+    api::Role,
+    bans::{parse_duration, Ban, BanList},
+};
+use common::{Harness, USER_ID};
+use serde_json::{json, Value};
+use std::{env, fs};
+use twilight_model::id::Id;
+
+/// The user who is banned, the default user is the moderator
+const TARGET: u64 = 2000;
+
+const HOUR: i64 = 60 * 60;
+
+fn ban(user_id: u64, expires_at: Option<i64>) -> Ban {
+    Ban {
+        user_id: Id::new(user_id),
+        reason: "testing".to_string(),
+        moderator_id: Id::new(USER_ID),
+        moderator_name: "moderator".to_string(),
+        created_at: 0,
+        expires_at,
+    }
+}
+
+fn ban_command() -> Value {
+    json!([{
+        "name": "ban",
+        "type": 1,
+        "options": [
+            { "name": "user", "type": 6, "value": TARGET.to_string() },
+            { "name": "reason", "type": 3, "value": "testing" },
+        ],
+    }])
+}
+
+#[test]
+fn durations() {
+    assert_eq!(parse_duration("30m"), Some(30 * 60));
+    assert_eq!(parse_duration("12H"), Some(12 * HOUR));
+    assert_eq!(parse_duration(" 1d12h "), Some(36 * HOUR));
+    assert_eq!(parse_duration("2w"), Some(14 * 24 * HOUR));
+    assert_eq!(parse_duration("1h1h"), Some(2 * HOUR));
+
+    // Zero
+    assert_eq!(parse_duration("0m"), None);
+    assert_eq!(parse_duration("0d0h"), None);
+    // Overflow
+    assert_eq!(parse_duration("99999999999999999999m"), None);
+    assert_eq!(parse_duration("9223372036854775807w"), None);
+    assert_eq!(parse_duration("15250284452471w15250284452471w"), None);
+    // Garbage
+    assert_eq!(parse_duration(""), None);
+    assert_eq!(parse_duration("12"), None);
+    assert_eq!(parse_duration("h"), None);
+    assert_eq!(parse_duration("1x"), None);
+    assert_eq!(parse_duration("-1h"), None);
+    assert_eq!(parse_duration("1.5h"), None);
+    assert_eq!(parse_duration("1d 12h"), None);
+}
+
+#[test]
+fn suspensions_expire() {
+    let dir = env::temp_dir().join(format!("altera-client-bans-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let now = chrono::Utc::now().timestamp();
+
+    let mut bans = BanList::load(dir.clone()).unwrap();
+    bans.ban(ban(1, Some(now + HOUR))).unwrap();
+    bans.ban(ban(2, Some(now - 1))).unwrap();
+    bans.ban(ban(3, None)).unwrap();
+
+    assert!(bans.active(Id::new(1)).is_some());
+    assert!(bans.active(Id::new(2)).is_none());
+    assert!(bans.active(Id::new(3)).is_some());
+    // An expired suspension isn't lifted again
+    assert!(bans.unban(Id::new(2)).unwrap().is_none());
+
+    // A new ban replaces the previous one
+    let previous = bans.ban(ban(1, None)).unwrap().unwrap();
+    assert_eq!(previous.expires_at, Some(now + HOUR));
+
+    let bans = BanList::load(dir.clone()).unwrap();
+    assert_eq!(bans.active(Id::new(1)).unwrap().expires_at, None);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn suspended_users_can_use_the_bot_once_it_ends() {
+    let harness = Harness::new();
+    harness.register(500);
+    let now = chrono::Utc::now().timestamp();
+
+    harness
+        .ctx
+        .bans_mut()
+        .ban(ban(USER_ID, Some(now + HOUR)))
+        .unwrap();
+    let embeds = harness.command("balance", json!([])).await.embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Suspended"));
+
+    harness
+        .ctx
+        .bans_mut()
+        .ban(ban(USER_ID, Some(now - 1)))
+        .unwrap();
+    let embeds = harness.command("balance", json!([])).await.embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Success"));
+}
+
+#[tokio::test]
+async fn only_lower_roles_can_be_banned() {
+    for (moderator, target, allowed) in [
+        (Role::Support, Role::User, true),
+        (Role::Support, Role::Support, false),
+        (Role::Support, Role::Admin, false),
+        (Role::Admin, Role::Support, true),
+        (Role::Admin, Role::Admin, false),
+    ] {
+        let harness = Harness::new();
+        harness.register_as(moderator, 0);
+        harness.backend.add_user(Id::new(TARGET), target, 0);
+
+        let embeds = harness
+            .command_with_users("admin", ban_command(), &[TARGET])
+            .await
+            .embeds();
+        assert_eq!(
+            harness.ctx.bans().active(Id::new(TARGET)).is_some(),
+            allowed,
+            "{} banning {}: {:?}",
+            moderator,
+            target,
+            embeds[0].description
+        );
+        if !allowed {
+            assert_eq!(
+                embeds[0].description.as_deref(),
+                Some(
+                    format!(
+                        "**@user{}** is {}, you can only ban users with a lower role.",
+                        TARGET, target
+                    )
+                    .as_str()
+                )
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn users_without_an_account_can_be_banned() {
+    let harness = Harness::new();
+    harness.register_as(Role::Support, 0);
+
+    harness
+        .command_with_users("admin", ban_command(), &[TARGET])
+        .await;
+    assert!(harness.ctx.bans().active(Id::new(TARGET)).is_some());
+}
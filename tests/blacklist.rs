@@ -0,0 +1,152 @@
+mod common;
+
+use altera_client::{
+    api::Role,
+    blacklist::{Blacklist, BlacklistError, BlacklistRule, Category, RuleKind},
+};
+use common::Harness;
+use serde_json::json;
+use std::{env, fs, path::PathBuf};
+
+/// An empty data directory no other test uses
+fn data_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!(
+        "altera-client-blacklist-{}-{}",
+        std::process::id(),
+        name
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn rule(pattern: &str, kind: RuleKind) -> BlacklistRule {
+    BlacklistRule {
+        pattern: pattern.to_string(),
+        kind,
+        category: Category::Other,
+    }
+}
+
+#[test]
+fn default_rules() {
+    let dir = data_dir("defaults");
+    let mut blacklist = Blacklist::load(dir.clone()).unwrap();
+
+    assert!(blacklist.is_blacklisted("Paypal"));
+    assert!(blacklist.is_blacklisted("  coinbase "));
+    // What the bot blocked before the blacklist was configurable is still
+    // blocked, until it's allowlisted
+    for pattern in ["coin", "pay", "cash", "sell"] {
+        assert!(blacklist
+            .rules()
+            .iter()
+            .any(|rule| rule.pattern == pattern && rule.kind == RuleKind::Substring));
+    }
+    assert_eq!(blacklist.check("Bitcoin Core").unwrap().pattern, "coin");
+    assert!(blacklist.is_blacklisted("Google Pay"));
+    blacklist.allow("Google Pay").unwrap();
+    assert!(!blacklist.is_blacklisted("Google Pay"));
+
+    // The defaults are saved so they can be edited
+    assert!(dir.join("blacklist.json").exists());
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn rule_kinds() {
+    let dir = data_dir("kinds");
+    let mut blacklist = Blacklist::load(dir.clone()).unwrap();
+
+    blacklist.add_rule(rule("Acme", RuleKind::Exact)).unwrap();
+    blacklist
+        .add_rule(rule("bank", RuleKind::Substring))
+        .unwrap();
+    blacklist
+        .add_rule(rule(r"^crypto\d+$", RuleKind::Regex))
+        .unwrap();
+
+    assert!(blacklist.is_blacklisted("ACME"));
+    assert!(!blacklist.is_blacklisted("Acme Corp"));
+    assert!(blacklist.is_blacklisted("Online Banking"));
+    assert!(blacklist.is_blacklisted("BANKING"));
+    assert!(blacklist.is_blacklisted("Crypto42"));
+    assert!(!blacklist.is_blacklisted("Crypto"));
+    // The rule that blocks the service is the one reported
+    assert_eq!(blacklist.check("mybank").unwrap().pattern, "bank");
+
+    assert!(matches!(
+        blacklist.add_rule(rule("acme", RuleKind::Exact)),
+        Err(BlacklistError::Duplicate { .. })
+    ));
+    assert!(matches!(
+        blacklist.add_rule(rule("(", RuleKind::Regex)),
+        Err(BlacklistError::Regex { .. })
+    ));
+    // A literal `(` is fine as a substring
+    blacklist.add_rule(rule("(", RuleKind::Substring)).unwrap();
+
+    assert_eq!(blacklist.remove_rule("BANK").unwrap().len(), 1);
+    assert!(!blacklist.is_blacklisted("Online Banking"));
+
+    // Changes are saved
+    let reloaded = Blacklist::load(dir.clone()).unwrap();
+    assert!(reloaded.is_blacklisted("acme"));
+    assert!(!reloaded.is_blacklisted("Online Banking"));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn allowlist_overrides_rules() {
+    let dir = data_dir("allowlist");
+    let mut blacklist = Blacklist::load(dir.clone()).unwrap();
+
+    // Blocked by the default `pay` substring rule
+    assert!(blacklist.allow("Google Pay").unwrap());
+    assert!(!blacklist.allow("google pay").unwrap());
+    assert!(blacklist.check("GOOGLE PAY").is_none());
+    assert!(blacklist.is_blacklisted("Apple Pay"));
+
+    assert!(blacklist.disallow("GOOGLE PAY").unwrap());
+    assert!(!blacklist.disallow("Google Pay").unwrap());
+    assert!(blacklist.is_blacklisted("Google Pay"));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn admin_commands() {
+    let harness = Harness::new();
+    harness.register_as(Role::Admin, 0);
+    let blacklist = |options| json!([{ "name": "blacklist", "type": 1, "options": [options] }]);
+
+    let add = blacklist(json!({
+        "name": "add",
+        "type": 1,
+        "options": [
+            { "name": "pattern", "type": 3, "value": "Acme" },
+            { "name": "kind", "type": 3, "value": "substring" },
+            { "name": "category", "type": 3, "value": "other" },
+        ],
+    }));
+    let embeds = harness.command("admin", add.clone()).await.embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("Services matching the substring pattern `Acme` are now blocked as **other**.")
+    );
+    let embeds = harness.command("admin", add).await.embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("A substring rule for `Acme` already exists.")
+    );
+
+    let check = blacklist(json!({
+        "name": "check",
+        "type": 1,
+        "options": [{ "name": "service", "type": 3, "value": "Acme Mail" }],
+    }));
+    let embeds = harness.command("admin", check).await.embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("**Acme Mail** is blocked as **other** by the substring rule `Acme`.")
+    );
+}
@@ -0,0 +1,184 @@
+mod common;
+
+use altera_client::{
+    api::Role,
+    cli::{self, CliError, Command},
+};
+use common::{Harness, USER_ID};
+use serde_json::json;
+use twilight_model::id::Id;
+
+fn parse(args: &[&str]) -> Result<Command, CliError> {
+    Command::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+async fn run(harness: &Harness, command: Command) -> Result<String, anyhow::Error> {
+    let mut out = vec![];
+    cli::run(command, (*harness.ctx.config()).clone(), &mut out).await?;
+
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn parse_commands() {
+    assert_eq!(
+        parse(&[]),
+        Ok(Command::Serve {
+            prune_commands: false
+        })
+    );
+    assert_eq!(
+        parse(&["--prune-commands"]),
+        Ok(Command::Serve {
+            prune_commands: true
+        })
+    );
+    assert_eq!(
+        parse(&["register-commands", "--prune-commands"]),
+        Ok(Command::RegisterCommands {
+            prune_commands: true
+        })
+    );
+    assert_eq!(
+        parse(&["lookup-user", "1000", "--json"]),
+        Ok(Command::LookupUser {
+            discord_id: Id::new(1000),
+            json: true
+        })
+    );
+    assert_eq!(
+        parse(&["export-orders", "1000", "1001"]),
+        Ok(Command::ExportOrders {
+            discord_ids: vec![Id::new(1000), Id::new(1001)]
+        })
+    );
+    assert_eq!(parse(&["--help"]), Ok(Command::Help));
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(
+        parse(&["frobnicate"]),
+        Err(CliError::UnknownCommand("frobnicate".to_string()))
+    );
+    assert_eq!(
+        parse(&["lookup-user"]),
+        Err(CliError::MissingArgument(
+            "lookup-user",
+            "a Discord user id"
+        ))
+    );
+    assert_eq!(
+        parse(&["lookup-user", "someone"]),
+        Err(CliError::InvalidUserId("someone".to_string()))
+    );
+    assert_eq!(
+        parse(&["balance", "extra"]),
+        Err(CliError::UnexpectedArgument("extra".to_string()))
+    );
+    assert!(parse(&["export-orders"]).is_err());
+}
+
+#[tokio::test]
+async fn balance() {
+    let harness = Harness::new();
+
+    assert_eq!(run(&harness, Command::Balance).await.unwrap(), "$100.00\n");
+}
+
+#[tokio::test]
+async fn lookup_user() {
+    let harness = Harness::new();
+    harness.register_as(Role::Support, 1234);
+
+    let out = run(
+        &harness,
+        Command::LookupUser {
+            discord_id: Id::new(USER_ID),
+            json: false,
+        },
+    )
+    .await
+    .unwrap();
+    assert!(out.contains("Role:       support\n"));
+    assert!(out.contains("Balance:    $12.34 USD\n"));
+
+    let out = run(
+        &harness,
+        Command::LookupUser {
+            discord_id: Id::new(USER_ID),
+            json: true,
+        },
+    )
+    .await
+    .unwrap();
+    let user: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(user["Balance"], 1234);
+
+    let err = run(
+        &harness,
+        Command::LookupUser {
+            discord_id: Id::new(1),
+            json: false,
+        },
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.to_string(), "User not found");
+}
+
+#[tokio::test]
+async fn order_status_of_unknown_order() {
+    let harness = Harness::new();
+
+    let err = run(
+        &harness,
+        Command::OrderStatus {
+            order_id: "fake-1".to_string(),
+            json: false,
+        },
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Invalid order");
+}
+
+#[tokio::test]
+async fn export_orders() {
+    let harness = Harness::new();
+    harness.register(500);
+    harness
+        .command(
+            "getnumber",
+            json!([
+                { "name": "service", "type": 3, "value": "Discord" },
+                { "name": "country", "type": 3, "value": "Germany" },
+            ]),
+        )
+        .await;
+
+    let out = run(
+        &harness,
+        Command::ExportOrders {
+            discord_ids: vec![Id::new(USER_ID)],
+        },
+    )
+    .await
+    .unwrap();
+    let rows: Vec<&str> = out.lines().collect();
+    assert_eq!(rows.len(), 2);
+    assert!(rows[0].starts_with("discord_id,order_id,"));
+    assert!(rows[1].starts_with(&format!(
+        "{},fake-1,5550000001,Discord,Germany,0.70,false,",
+        USER_ID
+    )));
+}
+
+#[tokio::test]
+async fn check_config() {
+    let harness = Harness::new();
+
+    let out = run(&harness, Command::CheckConfig).await.unwrap();
+    assert!(out.starts_with("The config is valid\n"));
+    assert!(out.contains("Provider:   fake (fake://)\n"));
+}
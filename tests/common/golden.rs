@@ -0,0 +1,106 @@
+//! Compares the replies a handler sent against the checked-in files in
+//! `tests/golden`
+//!
+//! Run the tests with `UPDATE_GOLDEN=1` to write the files from the current
+//! replies instead, then review the diff
+
+use altera_client::interaction::respond::Recording;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use std::{env, fs, path::PathBuf};
+
+/// Assert that the replies match `tests/golden/{name}.json`
+pub fn assert_golden(name: &str, recording: &Recording) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.json", name));
+    let actual = serde_json::to_string_pretty(&render(recording)).unwrap() + "\n";
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "Unable to read {}: {}, run with UPDATE_GOLDEN=1 to create it",
+            path.display(),
+            err
+        )
+    });
+    assert!(
+        expected == actual,
+        "The replies don't match {}, run with UPDATE_GOLDEN=1 if the change is intended\n\
+         expected:\n{}\nactual:\n{}",
+        path.display(),
+        expected,
+        actual
+    );
+}
+
+/// The replies in their canonical form: keys sorted, colors in hex and the
+/// parts that change between runs replaced with placeholders
+pub fn render(recording: &Recording) -> Value {
+    Value::Array(
+        recording
+            .replies()
+            .into_iter()
+            .map(|reply| {
+                let embeds = reply
+                    .embeds
+                    .iter()
+                    .map(|embed| {
+                        let mut embed = serde_json::to_value(embed).unwrap();
+                        if let Some(color) = embed.get("color").and_then(Value::as_u64) {
+                            embed["color"] = json!(format!("#{:06X}", color));
+                        }
+                        embed
+                    })
+                    .collect::<Vec<_>>();
+
+                canonical(json!({
+                    "content": reply.content,
+                    "ephemeral": reply.flags.contains(
+                        twilight_model::channel::message::MessageFlags::EPHEMERAL
+                    ),
+                    "embeds": embeds,
+                }))
+            })
+            .collect(),
+    )
+}
+
+fn canonical(value: Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            let mut entries: Vec<(String, Value)> = object.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, canonical(value)))
+                    .collect::<Map<_, _>>(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(canonical).collect()),
+        Value::String(string) => Value::String(redact(&string)),
+        value => value,
+    }
+}
+
+/// Replace the timestamps, dates and durations, which depend on when and how
+/// fast the test runs
+fn redact(string: &str) -> String {
+    let timestamp = Regex::new(r"<t:\d+:([a-zA-Z])>").unwrap();
+    let duration = Regex::new(r"`\d+m \d+s`").unwrap();
+    let day = Regex::new(r"\b(Mon|Tue|Wed|Thu|Fri|Sat|Sun) \d{2} [A-Z][a-z]{2}\b").unwrap();
+    let week = Regex::new(r"\bWeek of \d{2} [A-Z][a-z]{2}\b").unwrap();
+
+    let string = timestamp.replace_all(string, "<t:TIMESTAMP:$1>");
+    let string = duration.replace_all(&string, "`DURATION`");
+    let string = day.replace_all(&string, "DAY");
+    week.replace_all(&string, "Week of DAY").into_owned()
+}
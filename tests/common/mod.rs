@@ -0,0 +1,306 @@
+//! Runs interaction handlers against the mock backend and the fake provider,
+//! recording their responses instead of sending them to Discord
+
+#![allow(dead_code)]
+
+pub mod golden;
+
+use altera_client::{
+    api::{ApiClient, Role},
+    config::{Config, FakeProviderConfig},
+    interaction::respond::{Recording, Responder},
+    mock_backend::MockBackend,
+    Context,
+};
+use serde_json::{json, Value};
+use sparkle_convenience::Bot;
+use std::{
+    env, fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use twilight_model::{application::interaction::Interaction, id::Id};
+
+pub const ADMIN_TOKEN: &str = "test-admin-token";
+/// The Discord user the interactions are from unless a test picks another
+pub const USER_ID: u64 = 1000;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+pub struct Harness {
+    pub backend: Arc<MockBackend>,
+    /// A client for the mock backend, to set up accounts the way the bot would
+    pub api: ApiClient,
+    pub ctx: Context,
+    data_dir: PathBuf,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        Self::with_fake_provider(FakeProviderConfig::default())
+    }
+
+    pub fn with_fake_provider(fake_provider: FakeProviderConfig) -> Self {
+        Self::with_config(|config| config.fake_provider = fake_provider)
+    }
+
+    /// Change the config before the bot starts, it starts from one that only
+    /// has what's required to run
+    pub fn with_config(edit: impl FnOnce(&mut Config)) -> Self {
+        let backend = Arc::new(MockBackend::new(ADMIN_TOKEN));
+        let addr = Arc::clone(&backend)
+            .spawn(([127, 0, 0, 1], 0).into())
+            .unwrap();
+
+        let data_dir = env::temp_dir().join(format!(
+            "altera-client-test-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut config = Config::from_toml(&format!(
+            r##"
+            [discord]
+            token = "test"
+            guilds = [2]
+            log_channel = 1
+
+            [api]
+            base_url = "http://{addr}"
+            admin_token = "{ADMIN_TOKEN}"
+
+            [[providers]]
+            name = "fake"
+            base_url = "fake://"
+
+            [storage]
+            data_dir = "{data_dir}"
+
+            [features]
+            log_channel_messages = false
+            "##,
+            data_dir = data_dir.display(),
+        ))
+        .unwrap();
+        edit(&mut config);
+
+        let ctx = Context::new(bot(), config).unwrap();
+        let api = ApiClient::new(
+            format!("http://{}", addr),
+            ADMIN_TOKEN.to_string(),
+            Duration::from_secs(5),
+        );
+
+        Self {
+            backend,
+            api,
+            ctx,
+            data_dir,
+        }
+    }
+
+    /// Create an account for the default user
+    pub fn register(&self, balance: i32) -> u32 {
+        self.register_as(Role::User, balance)
+    }
+
+    /// Create an account with the role for the default user
+    pub fn register_as(&self, role: Role, balance: i32) -> u32 {
+        self.backend.add_user(Id::new(USER_ID), role, balance)
+    }
+
+    /// Handle the interaction, returning what it responded with
+    pub async fn run(&self, interaction: Interaction) -> Recording {
+        let recording = Recording::default();
+        self.ctx
+            .respond_to_interaction(interaction, Responder::Recording(&recording))
+            .await
+            .unwrap();
+
+        recording
+    }
+
+    /// Run the slash command as the default user, `options` are in the format
+    /// Discord sends them in
+    pub async fn command(&self, name: &str, options: Value) -> Recording {
+        self.run(command(USER_ID, name, options)).await
+    }
+
+    /// Run the slash command as the default user, with the users its user
+    /// options mention
+    pub async fn command_with_users(&self, name: &str, options: Value, users: &[u64]) -> Recording {
+        self.run(command_with_users(USER_ID, name, options, users))
+            .await
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.data_dir);
+    }
+}
+
+/// A bot that is never connected, sending anything with it fails right away
+/// since requests go to a closed port
+#[allow(deprecated)]
+fn bot() -> Bot {
+    Bot {
+        http: twilight_http::Client::builder()
+            .token("test".to_string())
+            .proxy("127.0.0.1:1".to_string(), true)
+            .build(),
+        application: serde_json::from_value(json!({
+            "id": "1",
+            "name": "test",
+            "description": "",
+            "bot_public": false,
+            "bot_require_code_grant": false,
+            "icon": null,
+            "summary": "",
+            "verify_key": "",
+        }))
+        .unwrap(),
+        user: serde_json::from_value(json!({
+            "id": "1",
+            "username": "test",
+            "discriminator": "0000",
+            "avatar": null,
+            "bot": true,
+            "mfa_enabled": false,
+        }))
+        .unwrap(),
+        logging_format: sparkle_convenience::log::DisplayFormat::Display,
+        logging_print_enabled: false,
+        logging_webhook: None,
+        logging_file_path: None,
+    }
+}
+
+/// A slash command interaction from the user in a guild
+pub fn command(user_id: u64, name: &str, options: Value) -> Interaction {
+    interaction(
+        user_id,
+        2,
+        json!({ "id": "1", "name": name, "type": 1, "options": options }),
+    )
+}
+
+/// A slash command interaction from the user in a guild, with the users its
+/// user options mention
+pub fn command_with_users(user_id: u64, name: &str, options: Value, users: &[u64]) -> Interaction {
+    let users: serde_json::Map<String, Value> = users
+        .iter()
+        .map(|user_id| (user_id.to_string(), user(*user_id)))
+        .collect();
+
+    interaction(
+        user_id,
+        2,
+        json!({
+            "id": "1",
+            "name": name,
+            "type": 1,
+            "options": options,
+            "resolved": { "users": users },
+        }),
+    )
+}
+
+/// An autocomplete interaction, the focused option has `"focused": true`
+pub fn autocomplete(user_id: u64, name: &str, options: Value) -> Interaction {
+    interaction(
+        user_id,
+        4,
+        json!({ "id": "1", "name": name, "type": 1, "options": options }),
+    )
+}
+
+/// A button or select menu interaction on a message the bot sent
+pub fn component(user_id: u64, custom_id: &str, values: &[&str]) -> Interaction {
+    let mut interaction = json!({
+        "custom_id": custom_id,
+        "component_type": if values.is_empty() { 2 } else { 3 },
+        "values": values,
+    });
+    if values.is_empty() {
+        interaction.as_object_mut().unwrap().remove("values");
+    }
+
+    let mut interaction = raw_interaction(user_id, 3, interaction);
+    interaction["message"] = json!({
+        "id": "2",
+        "channel_id": "3",
+        "author": user(1),
+        "content": "",
+        "timestamp": "2024-01-01T00:00:00.000000+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": [],
+        "pinned": false,
+        "type": 0,
+    });
+
+    serde_json::from_value(interaction).unwrap()
+}
+
+/// A modal submit interaction, `values` are the custom IDs and values of its
+/// text inputs
+pub fn modal(user_id: u64, custom_id: &str, values: &[(&str, &str)]) -> Interaction {
+    let components: Vec<Value> = values
+        .iter()
+        .map(|(custom_id, value)| {
+            json!({
+                "type": 1,
+                "components": [{ "type": 4, "custom_id": custom_id, "value": value }],
+            })
+        })
+        .collect();
+
+    interaction(
+        user_id,
+        5,
+        json!({ "custom_id": custom_id, "components": components }),
+    )
+}
+
+fn interaction(user_id: u64, kind: u8, data: Value) -> Interaction {
+    serde_json::from_value(raw_interaction(user_id, kind, data)).unwrap()
+}
+
+fn raw_interaction(user_id: u64, kind: u8, data: Value) -> Value {
+    json!({
+        "id": NEXT_ID.fetch_add(1, Ordering::Relaxed).to_string(),
+        "application_id": "1",
+        "type": kind,
+        "token": "test-token",
+        "version": 1,
+        "guild_id": "2",
+        "channel_id": "3",
+        "member": {
+            "user": user(user_id),
+            "roles": [],
+            "joined_at": "2024-01-01T00:00:00.000000+00:00",
+            "deaf": false,
+            "mute": false,
+            "flags": 0,
+            "permissions": "0",
+        },
+        "data": data,
+    })
+}
+
+fn user(user_id: u64) -> Value {
+    json!({
+        "id": user_id.to_string(),
+        "username": format!("user{}", user_id),
+        "discriminator": "0000",
+        "avatar": null,
+    })
+}
@@ -0,0 +1,175 @@
+use altera_client::config::{Config, ConfigError, DEFAULT_PROVIDER_URL};
+use std::{env, fs};
+
+const MINIMAL: &str = r#"
+[discord]
+token = "token"
+guilds = [2]
+log_channel = 1
+
+[api]
+admin_token = "admin"
+
+[[providers]]
+name = "primary"
+base_url = "https://provider.example/"
+api_key = "key"
+"#;
+
+fn errors(contents: &str) -> Vec<String> {
+    match Config::from_toml(contents) {
+        Err(ConfigError::Invalid(errors)) => errors,
+        result => panic!("the config should be invalid, got {:?}", result.err()),
+    }
+}
+
+#[test]
+fn minimal_config() {
+    let config = Config::from_toml(MINIMAL).unwrap();
+
+    assert_eq!(config.secrets.discord_token, "token");
+    assert_eq!(config.discord.log_channel.get(), 1);
+    assert_eq!(config.api.base_url, "https://api.alterasms.io");
+    // Trailing slashes are dropped
+    assert_eq!(config.provider().base_url, "https://provider.example");
+    assert_eq!(config.colors.success, 0x65C97A);
+    assert_eq!(config.pricing.multiplier, 1.0);
+}
+
+#[test]
+fn example_config_only_lacks_secrets() {
+    let example =
+        fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/config.example.toml")).unwrap();
+
+    assert_eq!(
+        errors(&example),
+        [
+            "discord.token is empty, set it in the config or DISCORD_TOKEN",
+            "api.admin_token is empty, set it in the config or ADMIN_TOKEN",
+            "discord.guilds must contain at least one guild id when discord.deployment is \"guild\"",
+            "discord.log_channel must be set to a channel id",
+            "providers[0].api_key is empty, set it in the config or API_KEY",
+        ]
+    );
+}
+
+#[test]
+fn unknown_fields_are_rejected() {
+    let config = format!("{}\n[pricing]\nmultiplyer = 2.0\n", MINIMAL);
+    assert!(matches!(
+        Config::from_toml(&config),
+        Err(ConfigError::Parse { .. })
+    ));
+}
+
+#[test]
+fn every_problem_is_reported() {
+    let config = MINIMAL
+        .replace("https://provider.example/", "ftp://provider.example")
+        .replace("guilds = [2]", "guilds = [0]")
+        + r##"
+[pricing]
+multiplier = 0.0
+
+[colors]
+success = "+65C97"
+error = "#E8504"
+
+[timeouts]
+http_secs = 0
+"##;
+
+    assert_eq!(
+        errors(&config),
+        [
+            "discord.guilds contains an id of 0",
+            "providers[0].base_url `ftp://provider.example` must start with http://, https:// or fake://",
+            "pricing.multiplier must be a positive number, got 0",
+            "colors.success `+65C97` is not a hex color like #65C97A",
+            "colors.error `#E8504` is not a hex color like #E85041",
+            "timeouts.http_secs must be greater than 0",
+        ]
+    );
+}
+
+#[test]
+fn colors() {
+    let with_colors = |success: &str| format!("{}\n[colors]\nsuccess = \"{}\"\n", MINIMAL, success);
+
+    let config = Config::from_toml(&with_colors("65c97a")).unwrap();
+    assert_eq!(config.colors.success, 0x65C97A);
+    for invalid in ["#+65C97", "+65C97", "#65C97A0", "#GGGGGG", ""] {
+        assert!(
+            Config::from_toml(&with_colors(invalid)).is_err(),
+            "{} should be invalid",
+            invalid
+        );
+    }
+}
+
+/// The only test that sets environment variables, since they are shared by
+/// every test in this file
+#[test]
+fn environment_overrides() {
+    let path = env::temp_dir().join(format!("altera-client-config-{}.toml", std::process::id()));
+    fs::write(&path, MINIMAL).unwrap();
+    env::set_var("ALTERA_CONFIG", &path);
+    env::set_var("DISCORD_TOKEN", "env-token");
+    env::set_var("ADMIN_TOKEN", "env-admin");
+    env::set_var("API_KEY", "env-key");
+    env::set_var("DEBUG_SCOPE", "3");
+    env::set_var("LOG_CHANNEL", "4");
+    env::set_var("PRICE_MULTIPLIER", "1.5");
+
+    let config = Config::load().unwrap();
+    assert_eq!(config.secrets.discord_token, "env-token");
+    assert_eq!(config.secrets.admin_token, "env-admin");
+    assert_eq!(config.provider().api_key, "env-key");
+    assert_eq!(config.provider().base_url, "https://provider.example");
+    assert_eq!(config.discord.guilds[0].get(), 3);
+    assert_eq!(config.discord.log_channel.get(), 4);
+    assert_eq!(config.pricing.multiplier, 1.5);
+
+    // Without providers in the file, `API_KEY` sets up the one the bot always
+    // used unless `API_URL` points elsewhere
+    let without_providers = MINIMAL.split("[[providers]]").next().unwrap();
+    fs::write(&path, without_providers).unwrap();
+    env::set_var("API_URL", "https://other.example");
+    let config = Config::load().unwrap();
+    assert_eq!(config.provider().base_url, "https://other.example");
+    assert_eq!(config.provider().api_key, "env-key");
+
+    env::remove_var("API_URL");
+    let base_url = match Config::load() {
+        Ok(config) => config.provider().base_url.clone(),
+        // Only whether the default is used is checked here, not the URL
+        Err(ConfigError::Invalid(errors)) => {
+            assert_eq!(errors.len(), 1, "{:#?}", errors);
+            DEFAULT_PROVIDER_URL.to_string()
+        }
+        Err(err) => panic!("{}", err),
+    };
+    assert_eq!(base_url, DEFAULT_PROVIDER_URL);
+
+    env::set_var("LOG_CHANNEL", "the log channel");
+    assert!(matches!(
+        Config::load(),
+        Err(ConfigError::Env {
+            var: "LOG_CHANNEL",
+            ..
+        })
+    ));
+
+    for var in [
+        "ALTERA_CONFIG",
+        "DISCORD_TOKEN",
+        "ADMIN_TOKEN",
+        "API_KEY",
+        "DEBUG_SCOPE",
+        "LOG_CHANNEL",
+        "PRICE_MULTIPLIER",
+    ] {
+        env::remove_var(var);
+    }
+    let _ = fs::remove_file(path);
+}
@@ -0,0 +1,219 @@
+//! Decodes the responses in `tests/contracts` with the models the clients
+//! use, so changes to what the provider or the backend send are caught here
+//! instead of at runtime
+
+use altera_client::{
+    api::{ApiResponse, Invoice, InvoiceStatus, Role, User},
+    sms::{
+        create_sms_order::{SMSOrderError, SmsOrderInfo},
+        get_api_balance::BalanceResponseType,
+        get_country_prices::CountryPriceInfo,
+        get_service_list::ServiceResponse,
+        get_sms_code::CheckSMSResponse,
+        SMSResponseError,
+    },
+};
+use serde::de::DeserializeOwned;
+use std::{fs, path::PathBuf};
+
+fn body(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("contracts")
+        .join(name);
+
+    fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("Unable to read {}: {}", path.display(), err))
+}
+
+fn recording<T: DeserializeOwned>(name: &str) -> T {
+    serde_json::from_str(&body(name))
+        .unwrap_or_else(|err| panic!("Unable to decode {}: {}", name, err))
+}
+
+/// The resource of a successful backend response
+fn resource<T: DeserializeOwned>(name: &str) -> T {
+    let response: ApiResponse = recording(name);
+    serde_json::from_value(response.resource).unwrap()
+}
+
+#[test]
+fn purchase() {
+    let order: SmsOrderInfo = recording("provider/purchase_sms.json");
+    assert_eq!(order.order_id, "8f2c41d0");
+    assert_eq!(order.area_code, "49");
+    assert_eq!(order.phonenumber, "4915550100");
+    assert_eq!(order.number, 15550100);
+    assert_eq!(order.cost, 0.70);
+    assert_eq!(order.expires_in, 20);
+    assert_eq!(order.expiration, 1717171717);
+}
+
+#[test]
+fn purchase_with_numbers_and_missing_fields() {
+    let order: SmsOrderInfo = recording("provider/purchase_sms_numeric.json");
+    assert_eq!(order.order_id, "912734");
+    assert_eq!(order.area_code, "1");
+    assert_eq!(order.phonenumber, "12025550143");
+    assert_eq!(order.number, 2025550143);
+    assert_eq!(order.cost, 0.5);
+    assert_eq!(order.expiration, 1717171717);
+    assert_eq!(order.success, 1);
+    assert_eq!(order.pool, 0);
+    assert!(order.message.is_empty());
+}
+
+#[test]
+fn purchase_errors() {
+    let err: SMSOrderError = recording("provider/purchase_sms_out_of_stock.json");
+    assert_eq!(err.error_type, "OUT_OF_STOCK");
+    assert_eq!(err.message, "No numbers are in stock");
+
+    let err: SMSOrderError = recording("provider/purchase_sms_country_unavailable.json");
+    assert_eq!(err.error_type, "COUNTRY_NOT_AVAILABLE_FOR_SERVICE");
+    assert_eq!(err.success, 0);
+}
+
+#[test]
+fn check_sms() {
+    let pending: CheckSMSResponse = recording("provider/check_sms_pending.json");
+    assert_eq!(pending.status, 1);
+    assert_eq!(pending.time_left, Some(1140));
+    assert!(pending.sms.is_none());
+
+    let received: CheckSMSResponse = recording("provider/check_sms_received.json");
+    assert_eq!(received.status, 3);
+    assert_eq!(received.expiration, 1717171717);
+    assert_eq!(received.resend, Some(0));
+    assert_eq!(received.time_left, Some(905));
+    assert_eq!(received.sms.as_deref(), Some("481516"));
+
+    let expired: CheckSMSResponse = recording("provider/check_sms_expired.json");
+    assert_eq!(expired.status, 5);
+    assert_eq!(expired.time_left, None);
+    assert!(expired.message.is_none());
+}
+
+#[test]
+fn country_prices() {
+    let countries: Vec<CountryPriceInfo> = recording("provider/success_rate.json");
+    assert_eq!(countries.len(), 2);
+    assert_eq!(countries[0].iso, "DE");
+    assert_eq!(countries[0].price, 0.70);
+    assert_eq!(countries[0].low_price, 0.65);
+    assert_eq!(countries[1].success_rate, 95);
+
+    let countries: Vec<CountryPriceInfo> = recording("provider/success_rate_numeric.json");
+    assert_eq!(countries[0].price, 0.65);
+    assert_eq!(countries[0].success_rate, 80);
+    assert_eq!(countries[0].country_id, 0);
+}
+
+#[test]
+fn country_prices_of_invalid_service() {
+    // Not a list of countries, which the client reports as an invalid service
+    let body = body("provider/success_rate_invalid_service.json");
+    assert!(serde_json::from_str::<Vec<CountryPriceInfo>>(&body).is_err());
+}
+
+#[test]
+fn service_list() {
+    let services: Vec<ServiceResponse> = recording("provider/service_list.json");
+    assert_eq!(services.len(), 3);
+    assert_eq!(services[1].id, 2);
+    assert_eq!(services[1].favourite, 1);
+    assert_eq!(services[2].favourite, 0);
+}
+
+#[test]
+fn balance() {
+    let balance: BalanceResponseType = recording("provider/balance.json");
+    assert_eq!(balance.balance, 123.45);
+
+    let balance: BalanceResponseType = recording("provider/balance_numeric.json");
+    assert_eq!(balance.balance, 123.45);
+}
+
+#[test]
+fn request_errors() {
+    let err: SMSResponseError = recording("provider/request_error.json");
+    assert_eq!(err.errors[0].param, "orderid");
+    assert_eq!(err.errors[0].message, "Invalid order");
+
+    let err: SMSResponseError = recording("provider/request_error_minimal.json");
+    assert_eq!(err.success, 0);
+    assert_eq!(err.errors[0].message, "Unauthorized");
+    assert!(err.errors[0].description.is_empty());
+}
+
+#[test]
+fn user() {
+    let user: User = resource("backend/user.json");
+    assert_eq!(user.id, 7);
+    assert_eq!(user.discord_id, "1000");
+    assert_eq!(user.balance, 1234);
+    assert_eq!(user.role, Role::Support);
+
+    assert_eq!(user.invoices.len(), 2);
+    assert_eq!(user.invoices[0].status, InvoiceStatus::Completed);
+    assert_eq!(user.invoices[0].amount, 12.5);
+    assert!(user.invoices[0].created_at().is_some());
+    assert_eq!(
+        user.invoices[1].url.as_deref(),
+        Some("https://pay.example.com/inv_2")
+    );
+
+    let number = &user.numbers[0];
+    assert_eq!(number.price, 70);
+    assert_eq!(number.base_cost, Some(50));
+    assert!(number.received);
+    assert_eq!(
+        number.received_at().unwrap() - number.ordered_at().unwrap(),
+        90
+    );
+}
+
+#[test]
+fn user_with_strings_and_missing_fields() {
+    let user: User = resource("backend/user_lenient.json");
+    assert_eq!(user.id, 7);
+    assert_eq!(user.discord_id, "1000");
+    assert_eq!(user.balance, 1234);
+    assert_eq!(user.role, Role::Admin);
+    assert!(user.invoices.is_empty());
+    assert!(user.name.is_empty());
+
+    let number = &user.numbers[0];
+    assert_eq!(number.number, "12025550143");
+    assert_eq!(number.order_id, "912734");
+    assert_eq!(number.price, 50);
+    assert_eq!(number.base_cost, None);
+    assert!(!number.received);
+    assert!(number.ordered_at().is_none());
+}
+
+#[test]
+fn user_not_found() {
+    let response: ApiResponse = recording("backend/user_not_found.json");
+    assert_eq!(response.message, "User not found");
+    assert!(response.resource.is_null());
+}
+
+#[test]
+fn invoice() {
+    let invoice: Invoice = resource("backend/invoice.json");
+    assert_eq!(invoice.id, "42");
+    assert_eq!(invoice.amount, 12.5);
+    assert_eq!(invoice.status, InvoiceStatus::Pending);
+    assert!(invoice.method.is_none());
+
+    let invoice: Invoice = resource("backend/invoice_unknown_status.json");
+    assert_eq!(invoice.status, InvoiceStatus::Unknown);
+}
+
+#[test]
+fn number_posted() {
+    let response: ApiResponse = recording("backend/number_posted.json");
+    assert_eq!(response.message, "Number added");
+    assert!(response.resource.is_null());
+}
@@ -0,0 +1,317 @@
+mod common;
+
+use altera_client::{
+    config::{Config, ConfigError},
+    currencies::{Currency, Rates},
+    interaction::respond::Response,
+    money,
+};
+use common::{Harness, USER_ID};
+use serde_json::{json, Value};
+use std::{env, fs, path::PathBuf};
+use twilight_model::{
+    application::command::CommandOptionChoiceValue, channel::message::Embed, id::Id,
+};
+
+/// Write a rates file no other test uses
+fn rates_file(name: &str, contents: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!(
+        "altera-client-rates-{}-{}.toml",
+        std::process::id(),
+        name
+    ));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn euro_harness(name: &str) -> (Harness, PathBuf) {
+    let file = rates_file(name, "[EUR]\nrate = 0.92\nsymbol = \"€\"\n");
+    let harness = Harness::with_config(|config| config.currencies.file = Some(file.clone()));
+    (harness, file)
+}
+
+fn field<'a>(embed: &'a Embed, name: &str) -> Option<&'a str> {
+    embed
+        .fields
+        .iter()
+        .find(|field| field.name == name)
+        .map(|field| field.value.as_str())
+}
+
+fn search_price(country: &str) -> Value {
+    json!([{
+        "name": "prices",
+        "type": 1,
+        "options": [
+            { "name": "service", "type": 3, "value": "Discord" },
+            { "name": "country", "type": 3, "value": country },
+        ],
+    }])
+}
+
+#[test]
+fn example_rates_are_valid() {
+    let rates =
+        Rates::load(&PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("currencies.example.toml"))
+            .unwrap();
+    let codes: Vec<_> = rates.all().map(|currency| currency.code.as_str()).collect();
+    assert_eq!(codes, vec!["BRL", "EUR", "GBP", "JPY"]);
+    assert_eq!(rates.get("jpy").unwrap().decimals, 0);
+}
+
+#[test]
+fn invalid_rates() {
+    let file = rates_file(
+        "invalid",
+        r#"
+        [euro]
+        rate = 0.92
+
+        [USD]
+        rate = 1.0
+
+        [GBP]
+        rate = 0.0
+        decimals = 6
+        "#,
+    );
+    let errors = Rates::load(&file).unwrap_err();
+
+    // They are reported with the rest of the config
+    let Err(ConfigError::Invalid(config_errors)) = Config::from_toml(&format!(
+        r#"
+        [discord]
+        token = "test"
+        guilds = [2]
+        log_channel = 1
+
+        [api]
+        admin_token = "test"
+
+        [[providers]]
+        name = "fake"
+        base_url = "fake://"
+
+        [currencies]
+        file = {:?}
+        "#,
+        file.display().to_string()
+    )) else {
+        panic!("the rates file should be invalid");
+    };
+    assert_eq!(config_errors, errors);
+    let _ = fs::remove_file(file);
+
+    assert_eq!(
+        errors,
+        vec![
+            "currencies.file: `USD` is what prices are charged in and can't have a rate",
+            "currencies.file: `euro` isn't a currency code such as `EUR`",
+            "currencies.file: the decimals of `GBP` must be at most 4, got 6",
+            "currencies.file: the rate of `GBP` must be a positive number, got 0",
+        ]
+    );
+
+    let file = rates_file("unknown-field", "[EUR]\nrate = 0.92\nname = \"Euro\"\n");
+    let errors = Rates::load(&file).unwrap_err();
+    let _ = fs::remove_file(file);
+    assert!(errors[0].contains("is invalid"), "{:?}", errors);
+}
+
+#[test]
+fn money_formats() {
+    let euro = Currency {
+        code: "EUR".to_string(),
+        symbol: Some("€".to_string()),
+        decimals: 2,
+        rate: 0.92,
+    };
+    let yen = Currency {
+        code: "JPY".to_string(),
+        symbol: Some("¥".to_string()),
+        decimals: 0,
+        rate: 150.0,
+    };
+
+    assert_eq!(money::amount(1234.5, &euro, None), "€1,234.50 EUR");
+    assert_eq!(money::amount(1234.5, &euro, Some("de")), "1.234,50 EUR");
+    assert_eq!(money::amount(yen.convert(0.7), &yen, None), "¥105 JPY");
+    assert_eq!(money::amount(-1500.4, &yen, Some("fr")), "-1\u{a0}500 JPY");
+}
+
+#[tokio::test]
+async fn prices_in_display_currency() {
+    let (harness, file) = euro_harness("prices");
+    harness.register(500);
+
+    let embeds = harness
+        .command(
+            "currency",
+            json!([{ "name": "currency", "type": 3, "value": "eur" }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some(
+            "Prices are shown in **EUR** next to USD, at `1 USD = 0.92 EUR`. You are still charged in USD."
+        )
+    );
+
+    let embeds = harness.command("search", search_price("uk")).await.embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some(
+            "The price for a number from **United Kingdom** for the service **Discord** can range from \
+             `$0.48 USD (≈ €0.44 EUR)` - `$0.60 USD (≈ €0.55 EUR)`"
+        )
+    );
+    assert_eq!(
+        field(&embeds[0], "Charged in:"),
+        Some("`USD`, amounts in EUR are estimates at `1 USD = 0.92 EUR`")
+    );
+
+    let embeds = harness
+        .command(
+            "getnumber",
+            json!([
+                { "name": "service", "type": 3, "value": "Discord" },
+                { "name": "country", "type": 3, "value": "Germany" },
+            ]),
+        )
+        .await
+        .embeds();
+    assert_eq!(
+        field(&embeds[0], "Message rate:"),
+        Some("`$0.70 USD (≈ €0.64 EUR) / sms`")
+    );
+    // Only prices are converted, the balance is what is charged
+    assert_eq!(field(&embeds[0], "Balance:"), Some("`$5.00 USD`"));
+    let user = harness.backend.user(Id::new(USER_ID)).unwrap();
+    assert_eq!(user.numbers[0].price, 70);
+
+    // USD goes back to showing only USD
+    let embeds = harness
+        .command(
+            "currency",
+            json!([{ "name": "currency", "type": 3, "value": "USD" }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Display Currency"));
+    assert_eq!(
+        harness.ctx.preferences().get(Id::new(USER_ID)).currency,
+        None
+    );
+    let embeds = harness.command("search", search_price("uk")).await.embeds();
+    assert!(embeds[0].fields.is_empty());
+    assert!(embeds[0]
+        .description
+        .as_deref()
+        .unwrap()
+        .ends_with("`$0.48 USD` - `$0.60 USD`"));
+
+    let _ = fs::remove_file(file);
+}
+
+#[tokio::test]
+async fn unknown_currency() {
+    let (harness, file) = euro_harness("unknown");
+
+    let embeds = harness
+        .command(
+            "currency",
+            json!([{ "name": "currency", "type": 3, "value": "XYZ" }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Error"));
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("`XYZ` can't be shown, choose one of `EUR`.")
+    );
+    assert_eq!(
+        harness.ctx.preferences().get(Id::new(USER_ID)).currency,
+        None
+    );
+
+    let _ = fs::remove_file(file);
+
+    // Without a rates file there is nothing to choose from
+    let harness = Harness::new();
+    let embeds = harness
+        .command(
+            "currency",
+            json!([{ "name": "currency", "type": 3, "value": "EUR" }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("Prices can only be shown in USD at the moment.")
+    );
+}
+
+#[tokio::test]
+async fn currency_autocomplete() {
+    let (harness, file) = euro_harness("autocomplete");
+
+    let recording = harness
+        .run(common::autocomplete(
+            USER_ID,
+            "currency",
+            json!([{ "name": "currency", "type": 3, "value": "", "focused": true }]),
+        ))
+        .await;
+    let Response::Autocomplete(choices) = &recording.responses()[0] else {
+        panic!("expected autocomplete choices");
+    };
+    let values: Vec<_> = choices.iter().map(|choice| &choice.value).collect();
+    assert_eq!(
+        values,
+        vec![
+            &CommandOptionChoiceValue::String("USD".to_string()),
+            &CommandOptionChoiceValue::String("EUR".to_string()),
+        ]
+    );
+
+    let _ = fs::remove_file(file);
+}
+
+#[tokio::test]
+async fn rates_reload() {
+    let (harness, file) = euro_harness("reload");
+    harness
+        .command(
+            "currency",
+            json!([{ "name": "currency", "type": 3, "value": "EUR" }]),
+        )
+        .await;
+    assert_eq!(harness.ctx.reload_rates(), Ok(false));
+
+    // An invalid file keeps the current rates
+    fs::write(&file, "[EUR]\nrate = -1.0\n").unwrap();
+    assert!(harness.ctx.reload_rates().is_err());
+    assert_eq!(harness.ctx.rates().get("EUR").unwrap().rate, 0.92);
+
+    fs::write(
+        &file,
+        "[EUR]\nrate = 0.9\nsymbol = \"€\"\n\n[GBP]\nrate = 0.8\n",
+    )
+    .unwrap();
+    assert_eq!(harness.ctx.reload_rates(), Ok(true));
+    let embeds = harness.command("search", search_price("uk")).await.embeds();
+    assert!(embeds[0]
+        .description
+        .as_deref()
+        .unwrap()
+        .ends_with("`$0.48 USD (≈ €0.43 EUR)` - `$0.60 USD (≈ €0.54 EUR)`"));
+
+    // Users whose currency is removed see USD again
+    fs::write(&file, "[GBP]\nrate = 0.8\n").unwrap();
+    assert_eq!(harness.ctx.reload_rates(), Ok(true));
+    let embeds = harness.command("search", search_price("uk")).await.embeds();
+    assert!(embeds[0].fields.is_empty());
+
+    let _ = fs::remove_file(file);
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use altera_client::{api::InvoiceStatus, pending_deposits::PendingDeposit};
+use common::{Harness, USER_ID};
+use serde_json::json;
+use twilight_model::id::Id;
+
+/// Start a deposit of $10 with `/deposit`, returning its invoice ID
+async fn deposit(harness: &Harness) -> String {
+    harness
+        .command(
+            "deposit",
+            json!([{ "name": "amount", "type": 10, "value": 10.0 }]),
+        )
+        .await;
+
+    let deposits = harness.ctx.pending_deposits().all().to_vec();
+    assert_eq!(deposits.len(), 1);
+    deposits[0].invoice_id.clone()
+}
+
+#[tokio::test]
+async fn paid_deposit_is_credited() {
+    let harness = Harness::new();
+    harness.register(0);
+    let invoice_id = deposit(&harness).await;
+
+    // Unpaid deposits keep being checked
+    harness.ctx.poll_deposits().await;
+    assert_eq!(harness.ctx.pending_deposits().all().len(), 1);
+
+    assert!(harness.backend.complete_invoice(&invoice_id));
+    harness.ctx.poll_deposits().await;
+
+    assert!(harness.ctx.pending_deposits().all().is_empty());
+    let user = harness.backend.user(Id::new(USER_ID)).unwrap();
+    assert_eq!(user.balance, 1000);
+    assert_eq!(user.invoices[0].status, InvoiceStatus::Completed);
+}
+
+#[tokio::test]
+async fn unpaid_deposit_expires() {
+    let harness = Harness::with_config(|config| config.deposits.expire_after_hours = 1);
+    harness.register(0);
+    let invoice_id = deposit(&harness).await;
+
+    let mut pending = harness.ctx.pending_deposits().all()[0].clone();
+    harness.ctx.pending_deposits().remove(&invoice_id);
+    pending.created_at -= 2 * 60 * 60;
+    harness.ctx.pending_deposits().insert(pending).unwrap();
+
+    harness.ctx.poll_deposits().await;
+
+    assert!(harness.ctx.pending_deposits().all().is_empty());
+    let user = harness.backend.user(Id::new(USER_ID)).unwrap();
+    assert_eq!(user.balance, 0);
+}
+
+#[tokio::test]
+async fn deposits_are_kept_while_the_backend_is_down() {
+    // Nothing listens on the port once the listener is dropped
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let harness = Harness::with_config(|config| config.api.base_url = format!("http://{}", addr));
+    harness
+        .ctx
+        .pending_deposits()
+        .insert(PendingDeposit {
+            invoice_id: "mock-invoice-1".to_string(),
+            user_id: Id::new(USER_ID),
+            amount: 10.0,
+            created_at: 0,
+            interaction_token: "token".to_string(),
+            locale: None,
+        })
+        .unwrap();
+
+    harness.ctx.poll_deposits().await;
+
+    assert_eq!(harness.ctx.pending_deposits().all().len(), 1);
+}
@@ -0,0 +1,275 @@
+use altera_client::{
+    config::{FakeOperation, FakeProviderConfig, ProviderConfig, FAKE_PROVIDER_URL},
+    sms::{get_country_prices::CountryCodes, SmsClient},
+};
+use std::time::{Duration, Instant};
+
+const OPERATIONS: [FakeOperation; 5] = [
+    FakeOperation::Purchase,
+    FakeOperation::CheckSms,
+    FakeOperation::CountryPrices,
+    FakeOperation::ServiceList,
+    FakeOperation::Balance,
+];
+
+fn fake_sms(fake: FakeProviderConfig) -> SmsClient {
+    let provider = ProviderConfig {
+        name: "fake".to_string(),
+        base_url: FAKE_PROVIDER_URL.to_string(),
+        api_key: String::new(),
+        enabled: true,
+    };
+
+    SmsClient::new(&provider, &fake, Duration::from_secs(5))
+}
+
+/// Run the operation against the fake provider, returning whether it
+/// succeeded
+async fn succeeds(sms: &SmsClient, operation: FakeOperation) -> bool {
+    match operation {
+        FakeOperation::Purchase => sms.clone().create_sms_order("discord", "").await.is_ok(),
+        FakeOperation::CheckSms => match sms.clone().create_sms_order("discord", "").await {
+            Ok(order) => sms.clone().get_sms_code(&order.order_id).await.is_ok(),
+            Err(_) => false,
+        },
+        FakeOperation::CountryPrices => sms.clone().get_country_prices("discord").await.is_ok(),
+        FakeOperation::ServiceList => sms.clone().get_service_list().await.is_ok(),
+        FakeOperation::Balance => sms.clone().get_api_balance().await.is_ok(),
+    }
+}
+
+#[tokio::test]
+async fn cheapest_country_is_used_without_one() {
+    let sms = fake_sms(FakeProviderConfig::default());
+
+    let prices = sms.clone().get_country_prices("discord").await.unwrap();
+    let cheapest = prices
+        .iter()
+        .min_by(|a, b| a.price.total_cmp(&b.price))
+        .unwrap();
+
+    let order = sms.create_sms_order("discord", "").await.unwrap();
+    assert_eq!(order.country, cheapest.name);
+    assert_eq!(order.cost, cheapest.price);
+}
+
+#[tokio::test]
+async fn out_of_stock() {
+    let sms = fake_sms(FakeProviderConfig {
+        out_of_stock: vec!["discord".to_string()],
+        ..FakeProviderConfig::default()
+    });
+
+    let err = sms.create_sms_order("discord", "").await.unwrap_err();
+    assert_eq!(err.error_type, "OUT_OF_STOCK");
+}
+
+#[tokio::test]
+async fn country_not_available() {
+    let sms = fake_sms(FakeProviderConfig {
+        unavailable_countries: vec!["germany".to_string()],
+        ..FakeProviderConfig::default()
+    });
+
+    let err = sms
+        .clone()
+        .create_sms_order("discord", "Germany")
+        .await
+        .unwrap_err();
+    assert_eq!(err.error_type, "COUNTRY_NOT_AVAILABLE_FOR_SERVICE");
+
+    let prices = sms.get_country_prices("discord").await.unwrap();
+    assert!(prices.iter().all(|country| country.name != "Germany"));
+}
+
+#[tokio::test]
+async fn unknown_service() {
+    let sms = fake_sms(FakeProviderConfig::default());
+
+    assert!(sms.clone().get_country_prices("nonexistent").await.is_err());
+    let err = sms.create_sms_order("nonexistent", "").await.unwrap_err();
+    assert_eq!(err.error_type, "INVALID_SERVICE");
+}
+
+#[tokio::test]
+async fn never_delivered_numbers_expire() {
+    let sms = fake_sms(FakeProviderConfig {
+        sms_delay_secs: 0,
+        order_lifetime_secs: 1,
+        never_delivered: vec!["discord".to_string()],
+        ..FakeProviderConfig::default()
+    });
+
+    let order = sms.clone().create_sms_order("discord", "").await.unwrap();
+    assert_eq!(sms.clone().get_sms_code(&order.order_id).await.unwrap().status, 1);
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(sms.get_sms_code(&order.order_id).await.unwrap().status, 5);
+}
+
+#[tokio::test]
+async fn received_codes_are_charged() {
+    let sms = fake_sms(FakeProviderConfig {
+        balance: 10.0,
+        sms_delay_secs: 0,
+        ..FakeProviderConfig::default()
+    });
+
+    let order = sms.clone().create_sms_order("discord", "").await.unwrap();
+    assert_eq!(sms.clone().get_api_balance().await.unwrap(), 10.0);
+
+    let sms_code = sms.clone().get_sms_code(&order.order_id).await.unwrap();
+    assert_eq!(sms_code.status, 3);
+    // Checking again doesn't charge again
+    sms.clone().get_sms_code(&order.order_id).await.unwrap();
+    assert_eq!(sms.get_api_balance().await.unwrap(), 10.0 - order.cost);
+}
+
+#[tokio::test]
+async fn malformed_country_prices() {
+    let sms = fake_sms(FakeProviderConfig {
+        malformed: vec![FakeOperation::CountryPrices],
+        ..FakeProviderConfig::default()
+    });
+
+    assert!(sms.clone().get_country_prices("discord").await.is_err());
+    // Other operations are unaffected
+    assert!(!sms.get_service_list().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn slow_responses() {
+    let sms = fake_sms(FakeProviderConfig {
+        latency_ms: 200,
+        ..FakeProviderConfig::default()
+    });
+
+    let started = Instant::now();
+    sms.get_api_balance().await.unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(200));
+}
+
+#[tokio::test]
+async fn malformed_responses_are_errors() {
+    for malformed in OPERATIONS {
+        let sms = fake_sms(FakeProviderConfig {
+            malformed: vec![malformed],
+            ..FakeProviderConfig::default()
+        });
+
+        for operation in OPERATIONS {
+            // Checking an order purchases it first
+            let affected = operation == malformed
+                || (malformed == FakeOperation::Purchase && operation == FakeOperation::CheckSms);
+            assert_eq!(
+                succeeds(&sms, operation).await,
+                !affected,
+                "{:?} with malformed {:?}",
+                operation,
+                malformed
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn every_operation_is_slowed_down() {
+    let sms = fake_sms(FakeProviderConfig {
+        latency_ms: 100,
+        ..FakeProviderConfig::default()
+    });
+
+    for operation in OPERATIONS {
+        let started = Instant::now();
+        assert!(succeeds(&sms, operation).await, "{:?}", operation);
+        assert!(
+            started.elapsed() >= Duration::from_millis(100),
+            "{:?}",
+            operation
+        );
+    }
+}
+
+#[tokio::test]
+async fn out_of_stock_country_prices() {
+    let sms = fake_sms(FakeProviderConfig {
+        out_of_stock: vec!["discord".to_string()],
+        ..FakeProviderConfig::default()
+    });
+
+    let prices = sms.clone().get_country_prices("discord").await.unwrap();
+    assert!(prices.iter().all(|country| country.success_rate == 0));
+    let prices = sms.get_country_prices("telegram").await.unwrap();
+    assert!(prices.iter().all(|country| country.success_rate > 0));
+}
+
+#[tokio::test]
+async fn unavailable_countries_are_skipped_for_the_cheapest() {
+    let sms = fake_sms(FakeProviderConfig {
+        unavailable_countries: vec!["United States".to_string()],
+        ..FakeProviderConfig::default()
+    });
+
+    let order = sms.create_sms_order("discord", "").await.unwrap();
+    assert_eq!(order.country, "Canada");
+}
+
+#[tokio::test]
+async fn codes_arrive_after_the_delay() {
+    let sms = fake_sms(FakeProviderConfig {
+        balance: 10.0,
+        sms_delay_secs: 1,
+        ..FakeProviderConfig::default()
+    });
+
+    let order = sms.clone().create_sms_order("discord", "").await.unwrap();
+    let sms_code = sms.clone().get_sms_code(&order.order_id).await.unwrap();
+    assert_eq!(sms_code.status, 1);
+    assert_eq!(sms.clone().get_api_balance().await.unwrap(), 10.0);
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let sms_code = sms.clone().get_sms_code(&order.order_id).await.unwrap();
+    assert_eq!(sms_code.status, 3);
+    assert!(sms_code.sms.is_some());
+    assert_eq!(sms.get_api_balance().await.unwrap(), 10.0 - order.cost);
+}
+
+#[tokio::test]
+async fn undelivered_numbers_are_not_charged() {
+    let sms = fake_sms(FakeProviderConfig {
+        balance: 10.0,
+        sms_delay_secs: 0,
+        never_delivered: vec!["discord".to_string()],
+        ..FakeProviderConfig::default()
+    });
+
+    let order = sms.clone().create_sms_order("discord", "").await.unwrap();
+    let sms_code = sms.clone().get_sms_code(&order.order_id).await.unwrap();
+    assert_eq!(sms_code.status, 1);
+    assert!(sms_code.sms.is_none());
+    assert_eq!(sms.get_api_balance().await.unwrap(), 10.0);
+}
+
+#[tokio::test]
+async fn unknown_orders_are_errors() {
+    let sms = fake_sms(FakeProviderConfig::default());
+
+    let err = sms.get_sms_code("nonexistent").await.unwrap_err();
+    assert_eq!(err.errors[0].message, "Invalid order");
+}
+
+#[tokio::test]
+async fn country_codes_remember_missing_countries() {
+    let sms = fake_sms(FakeProviderConfig::default());
+    let codes = CountryCodes::default();
+    codes.extend(&sms.get_country_prices("discord").await.unwrap());
+    assert_eq!(codes.get("Germany").as_deref(), Some("de"));
+    assert!(codes.looked_up("germany"));
+
+    assert!(!codes.looked_up("Atlantis"));
+    codes.mark_looked_up("Atlantis");
+    codes.mark_looked_up("Germany");
+    assert!(codes.looked_up("atlantis"));
+    assert_eq!(codes.get("Atlantis"), None);
+    assert_eq!(codes.get("Germany").as_deref(), Some("de"));
+}
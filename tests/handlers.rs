@@ -0,0 +1,350 @@
+mod common;
+
+use altera_client::{
+    api::Role,
+    config::{FakeOperation, FakeProviderConfig},
+    interaction::respond::Response,
+};
+use common::{Harness, USER_ID};
+use serde_json::json;
+use sparkle_convenience::interaction::DeferVisibility;
+use twilight_model::{
+    application::command::CommandOptionChoiceValue, channel::message::MessageFlags, id::Id,
+};
+
+fn field<'a>(embed: &'a twilight_model::channel::message::Embed, name: &str) -> &'a str {
+    &embed
+        .fields
+        .iter()
+        .find(|field| field.name == name)
+        .unwrap_or_else(|| panic!("no field {}", name))
+        .value
+}
+
+#[tokio::test]
+async fn balance() {
+    let harness = Harness::new();
+    harness.register(1234);
+
+    let recording = harness.command("balance", json!([])).await;
+    let replies = recording.replies();
+    assert_eq!(replies.len(), 1);
+    assert!(replies[0].flags.contains(MessageFlags::EPHEMERAL));
+    assert_eq!(replies[0].embeds[0].title.as_deref(), Some("Success"));
+    assert_eq!(
+        replies[0].embeds[0].description.as_deref(),
+        Some("Your balance is `$12.34 USD`")
+    );
+}
+
+#[tokio::test]
+async fn balance_without_account() {
+    let harness = Harness::new();
+
+    let embeds = harness.command("balance", json!([])).await.embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Error"));
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some(format!("No account could be found for **@user{}**", USER_ID).as_str())
+    );
+}
+
+#[tokio::test]
+async fn getnumber() {
+    let harness = Harness::new();
+    harness.register(500);
+
+    let recording = harness
+        .command(
+            "getnumber",
+            json!([
+                { "name": "service", "type": 3, "value": "Discord" },
+                { "name": "country", "type": 3, "value": "Germany" },
+            ]),
+        )
+        .await;
+
+    assert_eq!(
+        recording.responses()[0],
+        Response::Defer(DeferVisibility::Ephemeral)
+    );
+    let embeds = recording.embeds();
+    assert_eq!(embeds.len(), 1);
+    assert_eq!(embeds[0].title.as_deref(), Some("Success"));
+    assert_eq!(field(&embeds[0], "Service:"), "Discord");
+    assert_eq!(field(&embeds[0], "Country:"), "Germany  :flag_de:");
+    assert_eq!(field(&embeds[0], "Message rate:"), "`$0.70 USD / sms`");
+    assert_eq!(field(&embeds[0], "Balance:"), "`$5.00 USD`");
+
+    let user = harness.backend.user(Id::new(USER_ID)).unwrap();
+    assert_eq!(user.numbers.len(), 1);
+    assert_eq!(user.numbers[0].price, 70);
+    assert_eq!(user.balance, 500);
+}
+
+#[tokio::test]
+async fn getnumber_out_of_stock() {
+    let harness = Harness::with_fake_provider(FakeProviderConfig {
+        out_of_stock: vec!["discord".to_string()],
+        ..FakeProviderConfig::default()
+    });
+    harness.register(500);
+
+    let embeds = harness
+        .command(
+            "getnumber",
+            json!([{ "name": "service", "type": 3, "value": "Discord" }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Error"));
+    assert!(embeds[0]
+        .description
+        .as_deref()
+        .unwrap()
+        .starts_with("We are currently out of stock"));
+    assert!(harness
+        .backend
+        .user(Id::new(USER_ID))
+        .unwrap()
+        .numbers
+        .is_empty());
+}
+
+#[tokio::test]
+async fn checksms_pending_then_received() {
+    let harness = Harness::with_fake_provider(FakeProviderConfig {
+        sms_delay_secs: 1,
+        ..FakeProviderConfig::default()
+    });
+    harness.register(500);
+    harness
+        .command(
+            "getnumber",
+            json!([{ "name": "service", "type": 3, "value": "Discord" }]),
+        )
+        .await;
+
+    let embeds = harness.command("checksms", json!([])).await.embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Pending"));
+
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+
+    let embeds = harness.command("checksms", json!([])).await.embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Success"));
+    assert!(embeds[0]
+        .description
+        .as_deref()
+        .unwrap()
+        .contains("Your Discord verification code is"));
+
+    let user = harness.backend.user(Id::new(USER_ID)).unwrap();
+    assert!(user.numbers[0].received);
+    assert_eq!(user.balance, 500 - user.numbers[0].price);
+}
+
+#[tokio::test]
+async fn checksms_without_number() {
+    let harness = Harness::new();
+    harness.register(500);
+
+    let embeds = harness.command("checksms", json!([])).await.embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Error"));
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some(format!("No number could be found for **@user{}**", USER_ID).as_str())
+    );
+}
+
+#[tokio::test]
+async fn history_of_another_user() {
+    let harness = Harness::new();
+    harness.register(500);
+    harness
+        .command(
+            "getnumber",
+            json!([{ "name": "service", "type": 3, "value": "Discord" }]),
+        )
+        .await;
+
+    let recording = harness
+        .run(common::component(
+            3000,
+            &format!("history:page:{}:0::", USER_ID),
+            &[],
+        ))
+        .await;
+    let replies = recording.replies();
+    assert_eq!(replies.len(), 1);
+    assert!(replies[0].flags.contains(MessageFlags::EPHEMERAL));
+    assert_eq!(
+        replies[0].embeds[0].description.as_deref(),
+        Some("Only the user who ran `/history` can use this, run it yourself to see your orders.")
+    );
+}
+
+#[tokio::test]
+async fn search_services() {
+    let harness = Harness::new();
+
+    let embeds = harness
+        .command(
+            "search",
+            json!([{
+                "name": "services",
+                "type": 1,
+                "options": [{ "name": "service", "type": 3, "value": "telegra" }],
+            }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Success"));
+    assert!(embeds[0]
+        .description
+        .as_deref()
+        .unwrap()
+        .contains("Telegram"));
+}
+
+#[tokio::test]
+async fn service_autocomplete() {
+    let harness = Harness::new();
+
+    let recording = harness
+        .run(common::autocomplete(
+            USER_ID,
+            "getnumber",
+            json!([{ "name": "service", "type": 3, "value": "whats", "focused": true }]),
+        ))
+        .await;
+
+    let Response::Autocomplete(choices) = &recording.responses()[0] else {
+        panic!("expected autocomplete choices");
+    };
+    assert_eq!(
+        choices[0].value,
+        CommandOptionChoiceValue::String("WhatsApp".to_string())
+    );
+}
+
+#[tokio::test]
+async fn unknown_command() {
+    let harness = Harness::new();
+
+    let result = harness
+        .ctx
+        .respond_to_interaction(
+            common::command(USER_ID, "nonexistent", json!([])),
+            altera_client::interaction::respond::Responder::Recording(&Default::default()),
+        )
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn malformed_purchase() {
+    let harness = Harness::with_fake_provider(FakeProviderConfig {
+        malformed: vec![FakeOperation::Purchase],
+        ..FakeProviderConfig::default()
+    });
+    harness.register(500);
+
+    let embeds = harness
+        .command(
+            "getnumber",
+            json!([
+                { "name": "service", "type": 3, "value": "Discord" },
+                { "name": "country", "type": 3, "value": "Germany" },
+            ]),
+        )
+        .await
+        .embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("An error occurred while processing your request. Please try again later.")
+    );
+    let user = harness.backend.user(Id::new(USER_ID)).unwrap();
+    assert!(user.numbers.is_empty());
+}
+
+#[tokio::test]
+async fn malformed_service_list() {
+    let harness = Harness::with_fake_provider(FakeProviderConfig {
+        malformed: vec![FakeOperation::CountryPrices, FakeOperation::ServiceList],
+        ..FakeProviderConfig::default()
+    });
+    harness.register(500);
+
+    let embeds = harness
+        .command(
+            "getnumber",
+            json!([{ "name": "service", "type": 3, "value": "Discord" }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("An error occurred when making your request.")
+    );
+}
+
+#[tokio::test]
+async fn errors_without_details() {
+    let harness = Harness::with_fake_provider(FakeProviderConfig {
+        failing: vec![FakeOperation::ServiceList, FakeOperation::Balance],
+        ..FakeProviderConfig::default()
+    });
+    harness.register_as(Role::Admin, 500);
+
+    let embeds = harness
+        .command(
+            "search",
+            json!([{
+                "name": "services",
+                "type": 1,
+                "options": [{ "name": "service", "type": 3, "value": "Discord" }],
+            }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("An error occurred when making your request.")
+    );
+
+    let embeds = harness.command("adminbal", json!([])).await.embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("An error occurred while processing your request. Please try again later.")
+    );
+}
+
+#[tokio::test]
+async fn receipt_splits_the_recorded_price() {
+    let harness = Harness::with_config(|config| {
+        config.fake_provider.sms_delay_secs = 0;
+        config.pricing.multiplier = 2.0;
+    });
+    harness.register(500);
+    harness
+        .command(
+            "getnumber",
+            json!([
+                { "name": "service", "type": 3, "value": "Discord" },
+                { "name": "country", "type": 3, "value": "Germany" },
+            ]),
+        )
+        .await;
+    harness.command("checksms", json!([])).await;
+
+    let embeds = harness
+        .command(
+            "receipt",
+            json!([{ "name": "order", "type": 3, "value": "fake-1" }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(field(&embeds[0], "Base Cost:"), "`$0.70 USD`");
+    assert_eq!(field(&embeds[0], "Markup:"), "`$0.70 USD`");
+    assert_eq!(field(&embeds[0], "Charged:"), "`$1.40 USD`");
+}
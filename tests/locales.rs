@@ -0,0 +1,384 @@
+mod common;
+
+use altera_client::{
+    api::{InvoiceStatus, Role},
+    config::{Config, ConfigError},
+    interaction::localized_commands,
+    money,
+    permissions::Capability,
+    preferences::{CodeDelivery, PriceSort},
+    templates::{self, Templates},
+};
+use common::{Harness, USER_ID};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+use twilight_model::id::Id;
+
+/// Write the locale files to a directory no other test uses
+fn locales_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = env::temp_dir().join(format!(
+        "altera-client-locales-{}-{}",
+        std::process::id(),
+        name
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (file_name, contents) in files {
+        fs::write(dir.join(file_name), contents).unwrap();
+    }
+    dir
+}
+
+/// The locale files shipped in the repository
+fn shipped_locales() -> Templates {
+    let mut templates = Templates::default();
+    templates
+        .load_locales(&PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("locales"))
+        .unwrap();
+    templates
+}
+
+#[test]
+fn shipped_locales_are_valid() {
+    let templates = shipped_locales();
+    assert_eq!(templates.locales(), vec!["de"]);
+}
+
+#[test]
+fn invalid_locale_files() {
+    let dir = locales_dir(
+        "invalid",
+        &[
+            ("german.toml", ""),
+            (
+                "de.toml",
+                r#"
+                [templates.no_acount]
+                title = "Fehler"
+
+                [templates.balance]
+                description = "Du hast {money}"
+
+                [labels]
+                "Balanse:" = "Guthaben:"
+                "Language" = "Die Sprache, in der dir der Bot antworten soll"
+                "Page:" = "Seite {page}:"
+
+                [commands."balance amount"]
+                description = "der Betrag"
+
+                [commands.balance]
+                name = "Guthaben Anzeigen"
+                "#,
+            ),
+            ("fr.toml", "[templates.balance]\ntone = \"error\""),
+        ],
+    );
+
+    let Err(ConfigError::Invalid(errors)) = Config::from_toml(&format!(
+        r#"
+        [discord]
+        token = "test"
+        guilds = [2]
+        log_channel = 1
+
+        [api]
+        admin_token = "test"
+
+        [[providers]]
+        name = "fake"
+        base_url = "fake://"
+
+        [templates]
+        locales = {:?}
+        "#,
+        dir.display().to_string()
+    )) else {
+        panic!("the locale files should be invalid");
+    };
+    assert_eq!(errors.len(), 9, "{:#?}", errors);
+    assert_eq!(
+        errors[0],
+        "templates.locales: the description of `balance` in `de.toml` uses `{money}`, which it doesn't have"
+    );
+    assert_eq!(
+        errors[1],
+        "templates.locales: `de.toml` has no template `no_acount`"
+    );
+    assert_eq!(
+        errors[2],
+        "templates.locales: `de.toml` has no label `Balanse:`"
+    );
+    assert_eq!(
+        errors[3],
+        "templates.locales: the label `Language` in `de.toml` is shown in a modal, which allows at most 45 characters"
+    );
+    assert_eq!(
+        errors[4],
+        "templates.locales: the label `Page:` in `de.toml` uses `{page}`, which it doesn't have"
+    );
+    assert!(errors[5].starts_with("templates.locales: the name of `balance` in `de.toml`"));
+    assert_eq!(
+        errors[6],
+        "templates.locales: `de.toml` has no command `balance amount`"
+    );
+    assert!(errors[7].starts_with("templates.locales: `fr.toml` is invalid"));
+    assert_eq!(
+        errors[8],
+        "templates.locales: `german.toml` isn't named after a Discord locale such as `de.toml`"
+    );
+
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn localized_replies() {
+    let templates = shipped_locales();
+    let harness = Harness::with_config(|config| config.templates = templates);
+    harness.register(123_456);
+
+    let mut interaction = common::command(USER_ID, "balance", json!([]));
+    interaction.locale = Some("de".to_string());
+    let embeds = harness.run(interaction).await.embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Erfolg"));
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("Dein Guthaben beträgt `1.234,56 USD`")
+    );
+
+    // Locales without a file are shown in English
+    let mut interaction = common::command(USER_ID, "balance", json!([]));
+    interaction.locale = Some("ja".to_string());
+    let embeds = harness.run(interaction).await.embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("Your balance is `$1,234.56 USD`")
+    );
+}
+
+/// The text of every `label("…")` in the source files under `dir`
+fn labels_in_source(dir: &Path, labels: &mut Vec<String>) {
+    let label = Regex::new(r#"label\(\s*(?:None,\s*|locale,\s*)?"((?:[^"\\]|\\.)*)""#).unwrap();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            labels_in_source(&path, labels);
+        } else if path.extension().is_some_and(|extension| extension == "rs") {
+            let source = fs::read_to_string(&path).unwrap();
+            labels.extend(
+                label
+                    .captures_iter(&source)
+                    .map(|captures| captures[1].to_string()),
+            );
+        }
+    }
+}
+
+#[test]
+fn labels_are_listed() {
+    let mut used = vec![];
+    labels_in_source(
+        &PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src"),
+        &mut used,
+    );
+    assert!(used.len() > 50, "{:#?}", used);
+
+    // The labels made from values rather than written out
+    for status in [
+        InvoiceStatus::Completed,
+        InvoiceStatus::Pending,
+        InvoiceStatus::Expired,
+        InvoiceStatus::Cancelled,
+        InvoiceStatus::Unknown,
+    ] {
+        used.push(status.to_string());
+        used.push(format!("{}:", status));
+    }
+    for capability in [
+        Capability::ViewUserData,
+        Capability::ViewBotBalance,
+        Capability::ManageBans,
+        Capability::AdjustBalances,
+        Capability::ManageBlacklist,
+        Capability::ManageBot,
+    ] {
+        used.push(capability.to_string());
+    }
+    for role in [Role::User, Role::Support, Role::Admin] {
+        used.push(role.to_string());
+    }
+    for name in [
+        PriceSort::Price.name(),
+        PriceSort::SuccessRate.name(),
+        CodeDelivery::Reply.name(),
+        CodeDelivery::Dm.name(),
+    ] {
+        used.push(name.to_string());
+    }
+
+    let labels = templates::labels();
+    for label in &used {
+        assert!(
+            labels.contains(&label.as_str()),
+            "`{}` is missing from src/labels.txt",
+            label
+        );
+    }
+}
+
+#[tokio::test]
+async fn localized_admin_replies() {
+    let templates = shipped_locales();
+    let harness = Harness::with_config(|config| config.templates = templates);
+    harness.register_as(Role::Support, 0);
+    harness.backend.add_user(Id::new(2000), Role::Admin, 0);
+
+    let mut interaction = common::command(USER_ID, "adminbal", json!([]));
+    interaction.locale = Some("de".to_string());
+    let embeds = harness.run(interaction).await.embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("Du hast keine Berechtigung, das Guthaben des Bots anzusehen.")
+    );
+
+    let ban = json!([{
+        "name": "ban",
+        "type": 1,
+        "options": [
+            { "name": "user", "type": 6, "value": "2000" },
+            { "name": "reason", "type": 3, "value": "testing" },
+        ],
+    }]);
+    let mut interaction = common::command_with_users(USER_ID, "admin", ban, &[2000]);
+    interaction.locale = Some("de".to_string());
+    let embeds = harness.run(interaction).await.embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Fehler"));
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some(
+            "**@user2000** ist Admin, du kannst nur Benutzer mit einer niedrigeren Rolle sperren."
+        )
+    );
+}
+
+#[tokio::test]
+async fn localized_maintenance_replies() {
+    let templates = shipped_locales();
+    let harness = Harness::with_config(|config| config.templates = templates);
+    harness.register_as(Role::Admin, 0);
+
+    let maintenance = |name: &str, options: Value| {
+        let mut interaction = common::command(
+            USER_ID,
+            "admin",
+            json!([{
+                "name": "maintenance",
+                "type": 2,
+                "options": [{ "name": name, "type": 1, "options": options }],
+            }]),
+        );
+        interaction.locale = Some("de".to_string());
+        interaction
+    };
+    let getnumber = json!([{ "name": "command", "type": 3, "value": "getnumber" }]);
+
+    let embeds = harness
+        .run(maintenance("disable", getnumber.clone()))
+        .await
+        .embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("`/getnumber` ist jetzt deaktiviert.")
+    );
+
+    let embeds = harness
+        .run(maintenance("disable", getnumber))
+        .await
+        .embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Fehler"));
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("`/getnumber` ist bereits deaktiviert.")
+    );
+
+    let embeds = harness.run(maintenance("status", json!([]))).await.embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("Der Wartungsmodus ist **aus**.\nDeaktivierte Befehle: `/getnumber`")
+    );
+}
+
+#[tokio::test]
+async fn untranslated_text_falls_back() {
+    let dir = locales_dir(
+        "fallback",
+        &[(
+            "es-ES.toml",
+            r#"
+            [templates.balance]
+            description = "Tu saldo es `{balance}`"
+            "#,
+        )],
+    );
+    let mut templates = Templates::default();
+    templates.load_locales(&dir).unwrap();
+    let _ = fs::remove_dir_all(dir);
+
+    // The title isn't translated, and `es-419` uses the other Spanish file
+    let balance = templates.get(Some("es-419"), "balance");
+    assert_eq!(balance.title, "Success");
+    assert_eq!(balance.description, "Tu saldo es `{balance}`");
+    assert_eq!(templates.label(Some("es-ES"), "Balance:"), "Balance:");
+    assert_eq!(
+        templates.get(Some("fr"), "balance").description,
+        "Your balance is `{balance}`"
+    );
+}
+
+#[test]
+fn command_localizations() {
+    let commands = localized_commands(&shipped_locales());
+
+    let balance = commands.iter().find(|c| c.name == "balance").unwrap();
+    assert_eq!(
+        balance.description_localizations.as_ref().unwrap()["de"],
+        "zeigt dein Guthaben"
+    );
+    assert!(balance.name_localizations.is_none());
+
+    let search = commands.iter().find(|c| c.name == "search").unwrap();
+    let prices = search.options.iter().find(|o| o.name == "prices").unwrap();
+    let sort_by = prices
+        .options
+        .iter()
+        .flatten()
+        .find(|o| o.name == "sort_by")
+        .unwrap();
+    let success_rate = sort_by
+        .choices
+        .iter()
+        .flatten()
+        .find(|c| c.name == "success_rate")
+        .unwrap();
+    assert_eq!(
+        success_rate.name_localizations.as_ref().unwrap()["de"],
+        "Erfolgsquote"
+    );
+
+    let admin = commands.iter().find(|c| c.name == "admin").unwrap();
+    assert!(admin.description_localizations.is_none());
+}
+
+#[test]
+fn money_formats() {
+    assert_eq!(money::usd(1234.5, None), "$1,234.50 USD");
+    assert_eq!(money::usd(0.7_f32, Some("en-GB")), "$0.70 USD");
+    assert_eq!(money::usd(-5.0, None), "-$5.00 USD");
+    assert_eq!(money::usd(1_234_567.891, Some("de")), "1.234.567,89 USD");
+    assert_eq!(money::usd(1234.5, Some("fr")), "1\u{a0}234,50 USD");
+    assert_eq!(money::usd(12.0, Some("pt-BR")), "$12,00 USD");
+}
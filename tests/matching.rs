@@ -0,0 +1,159 @@
+mod common;
+
+use altera_client::{
+    config::{Config, ConfigError},
+    interaction::respond::Response,
+};
+use common::{Harness, USER_ID};
+use serde_json::json;
+use twilight_model::{application::command::CommandOptionChoiceValue, id::Id};
+
+/// The services autocomplete suggests for the input, best first
+async fn suggestions(harness: &Harness, input: &str) -> Vec<String> {
+    let recording = harness
+        .run(common::autocomplete(
+            USER_ID,
+            "getnumber",
+            json!([{ "name": "service", "type": 3, "value": input, "focused": true }]),
+        ))
+        .await;
+    let Response::Autocomplete(choices) = &recording.responses()[0] else {
+        panic!("expected autocomplete choices");
+    };
+
+    choices
+        .iter()
+        .map(|choice| match &choice.value {
+            CommandOptionChoiceValue::String(name) => name.clone(),
+            value => panic!("expected a service name, got {:?}", value),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn aliases() {
+    let harness = Harness::new();
+
+    assert_eq!(suggestions(&harness, "tg").await[0], "Telegram");
+    assert_eq!(suggestions(&harness, "Insta").await[0], "Instagram");
+    assert_eq!(suggestions(&harness, "x").await, vec!["Twitter"]);
+    assert_eq!(suggestions(&harness, "twitter").await[0], "Twitter");
+
+    // Configured aliases replace the built-in ones
+    let harness = Harness::with_config(|config| {
+        config
+            .service_matching
+            .aliases
+            .insert("TG".to_string(), "Uber".to_string());
+    });
+    assert_eq!(suggestions(&harness, "tg").await[0], "Uber");
+}
+
+#[tokio::test]
+async fn aliases_are_ordered_by_name() {
+    let harness = Harness::new();
+    harness.register(500);
+
+    let embeds = harness
+        .command(
+            "getnumber",
+            json!([{ "name": "service", "type": 3, "value": "tg" }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Success"));
+
+    let user = harness.backend.user(Id::new(USER_ID)).unwrap();
+    assert_eq!(user.numbers[0].service, "Telegram");
+}
+
+#[tokio::test]
+async fn services_are_not_aliases() {
+    // An alias named like a service the provider sells doesn't hide it
+    let harness = Harness::with_config(|config| {
+        config
+            .service_matching
+            .aliases
+            .insert("uber".to_string(), "Telegram".to_string());
+    });
+    harness.register(500);
+    assert_eq!(suggestions(&harness, "Uber").await[0], "Uber");
+
+    let embeds = harness
+        .command(
+            "getnumber",
+            json!([{ "name": "service", "type": 3, "value": "uber" }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Success"));
+
+    let user = harness.backend.user(Id::new(USER_ID)).unwrap();
+    assert_eq!(user.numbers[0].service, "Uber");
+}
+
+#[tokio::test]
+async fn token_aware_scoring() {
+    let harness = Harness::new();
+
+    assert_eq!(suggestions(&harness, "google voice").await[0], "Google");
+    assert_eq!(suggestions(&harness, "whats app").await[0], "WhatsApp");
+    assert_eq!(suggestions(&harness, "face-book!").await[0], "Facebook");
+    // Equally similar services are ranked by popularity and then by name
+    assert_eq!(
+        suggestions(&harness, "gram").await,
+        vec!["Instagram", "Telegram"]
+    );
+    assert!(suggestions(&harness, "?!").await.is_empty());
+}
+
+#[tokio::test]
+async fn limits() {
+    let harness = Harness::with_config(|config| {
+        config.service_matching.min_score = 1;
+        config.service_matching.max_results = 3;
+    });
+    assert_eq!(suggestions(&harness, "a").await.len(), 3);
+
+    let harness = Harness::with_config(|config| config.service_matching.min_score = 100);
+    assert!(suggestions(&harness, "discor").await.is_empty());
+}
+
+#[test]
+fn invalid_matching_config() {
+    let Err(ConfigError::Invalid(errors)) = Config::from_toml(
+        r#"
+        [discord]
+        token = "test"
+        guilds = [2]
+        log_channel = 1
+
+        [api]
+        admin_token = "test"
+
+        [[providers]]
+        name = "fake"
+        base_url = "fake://"
+
+        [service_matching]
+        min_score = 101
+        max_results = 0
+
+        [service_matching.aliases]
+        tg = " "
+        TG = "Telegram"
+        "#,
+    ) else {
+        panic!("the matching config should be invalid");
+    };
+
+    assert_eq!(
+        errors,
+        vec![
+            "service_matching.min_score must be between 1 and 100, got 101",
+            "service_matching.max_results must be between 1 and 25, got 0",
+            "service_matching.aliases must not be empty, got `tg` = ` `",
+            "service_matching.aliases `TG` and `tg` are the same alias",
+        ]
+    );
+}
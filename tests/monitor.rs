@@ -0,0 +1,142 @@
+mod common;
+
+use altera_client::{
+    config::{FakeOperation, FakeProviderConfig},
+    monitor::{BalanceMonitor, Changes},
+};
+use common::Harness;
+use serde_json::json;
+
+const WARN_AT: [f32; 3] = [50.0, 20.0, 10.0];
+const HOUR: i64 = 60 * 60;
+
+#[test]
+fn warnings_are_sent_once_per_threshold() {
+    let monitor = BalanceMonitor::default();
+    let crossed = |now, balance| monitor.record(now, balance, &WARN_AT, 0.0).crossed;
+
+    assert_eq!(crossed(0, 60.0), None);
+    assert_eq!(crossed(1, 45.0), Some(50.0));
+    assert_eq!(crossed(2, 40.0), None);
+    // Dropping below several thresholds at once warns about the lowest
+    assert_eq!(crossed(3, 5.0), Some(10.0));
+    assert_eq!(crossed(4, 4.0), None);
+    // A top up warns about the thresholds again
+    assert_eq!(crossed(5, 100.0), None);
+    assert_eq!(crossed(6, 15.0), Some(20.0));
+}
+
+#[test]
+fn orders_are_paused_below_the_floor() {
+    let monitor = BalanceMonitor::default();
+    let paused = |now, balance| monitor.record(now, balance, &[], 10.0).paused;
+
+    assert_eq!(paused(0, 12.0), None);
+    assert_eq!(paused(1, 9.0), Some(true));
+    assert!(monitor.is_paused());
+    assert_eq!(paused(2, 8.0), None);
+    assert_eq!(paused(3, 10.0), Some(false));
+    assert!(!monitor.is_paused());
+
+    // A floor of 0 never pauses
+    let monitor = BalanceMonitor::default();
+    assert_eq!(monitor.record(0, 0.0, &[], 0.0), Changes::default());
+    assert!(!monitor.is_paused());
+}
+
+#[test]
+fn reset_forgets_the_pause() {
+    let monitor = BalanceMonitor::default();
+    monitor.record(0, 100.0, &[], 10.0);
+    monitor.record(HOUR, 5.0, &[], 10.0);
+    assert!(monitor.is_paused());
+
+    monitor.reset();
+    assert!(!monitor.is_paused());
+    assert_eq!(monitor.hours_left(), None);
+}
+
+#[test]
+fn hours_left_at_the_recent_rate() {
+    let monitor = BalanceMonitor::default();
+    let hours_left = |now, balance| monitor.record(now, balance, &[], 0.0).hours_left;
+
+    assert_eq!(hours_left(0, 100.0), None);
+    assert_eq!(hours_left(HOUR, 90.0), Some(9.0));
+    // Top ups don't count as negative spending
+    assert_eq!(hours_left(2 * HOUR, 200.0), Some(40.0));
+    assert_eq!(monitor.hours_left(), Some(40.0));
+
+    // Only the last 6 hours are looked at
+    assert_eq!(hours_left(9 * HOUR, 200.0), None);
+    assert_eq!(hours_left(10 * HOUR, 190.0), Some(19.0));
+}
+
+#[tokio::test]
+async fn low_balance_pauses_getnumber() {
+    let harness = Harness::with_config(|config| {
+        config.fake_provider.balance = 5.0;
+        config.balance_monitor.pause_below = 10.0;
+    });
+    harness.register(500);
+
+    harness.ctx.check_balance().await;
+
+    let embeds = harness
+        .command(
+            "getnumber",
+            json!([{ "name": "service", "type": 3, "value": "Discord" }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("Ordering numbers is paused for maintenance. Please try again later!")
+    );
+}
+
+#[tokio::test]
+async fn turning_the_monitor_off_resumes_getnumber() {
+    let harness = Harness::with_config(|config| {
+        config.fake_provider.balance = 5.0;
+        config.balance_monitor.enabled = false;
+        config.balance_monitor.pause_below = 10.0;
+    });
+    harness.register(500);
+
+    // As if the monitor paused ordering before a reload turned it off
+    harness.ctx.check_balance().await;
+
+    let embeds = harness
+        .command(
+            "getnumber",
+            json!([{ "name": "service", "type": 3, "value": "Discord" }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Success"));
+}
+
+#[tokio::test]
+async fn unreadable_balance_is_skipped() {
+    let harness = Harness::with_config(|config| {
+        config.fake_provider = FakeProviderConfig {
+            balance: 5.0,
+            malformed: vec![FakeOperation::Balance],
+            ..FakeProviderConfig::default()
+        };
+        config.balance_monitor.pause_below = 10.0;
+    });
+    harness.register(500);
+
+    harness.ctx.check_balance().await;
+
+    let embeds = harness
+        .command(
+            "getnumber",
+            json!([{ "name": "service", "type": 3, "value": "Discord" }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Success"));
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use altera_client::{
+    api::Role,
+    interaction::respond::{Recording, Responder},
+    permissions::Capability,
+};
+use common::{Harness, USER_ID};
+use serde_json::{json, Value};
+
+#[test]
+fn capabilities_by_role() {
+    use Capability::*;
+
+    let matrix = [
+        (ViewUserData, [false, true, true]),
+        (ManageBans, [false, true, true]),
+        (AdjustBalances, [false, true, true]),
+        (ViewBotBalance, [false, false, true]),
+        (ManageBlacklist, [false, false, true]),
+        (ManageBot, [false, false, true]),
+    ];
+    for (capability, allowed) in matrix {
+        for (role, allowed) in [Role::User, Role::Support, Role::Admin]
+            .into_iter()
+            .zip(allowed)
+        {
+            assert_eq!(role.can(capability), allowed, "{} {}", role, capability);
+        }
+    }
+}
+
+fn denied(recording: &Recording) -> bool {
+    recording
+        .embeds()
+        .first()
+        .and_then(|embed| embed.description.as_deref())
+        .is_some_and(|description| description.starts_with("You don't have permission to"))
+}
+
+#[tokio::test]
+async fn commands_check_the_role() {
+    let blacklist = json!([{
+        "name": "blacklist",
+        "type": 1,
+        "options": [{ "name": "list", "type": 1, "options": [] }],
+    }]);
+    let commands: [(&str, Value, Role); 2] = [
+        ("adminbal", json!([]), Role::Admin),
+        ("admin", blacklist, Role::Admin),
+    ];
+
+    for (name, options, min_role) in commands {
+        for role in [Role::User, Role::Support, Role::Admin] {
+            let harness = Harness::new();
+            harness.register_as(role, 0);
+
+            let recording = harness.command(name, options.clone()).await;
+            assert_eq!(denied(&recording), role < min_role, "/{} as {}", name, role);
+        }
+
+        // Users without an account have no role
+        let recording = Harness::new().command(name, options).await;
+        assert!(denied(&recording), "/{} without an account", name);
+    }
+}
+
+#[tokio::test]
+async fn unreachable_backend_is_an_error() {
+    // Nothing listens on the port once the listener is dropped
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let harness = Harness::with_config(|config| config.api.base_url = format!("http://{}", addr));
+
+    let recording = Recording::default();
+    let result = harness
+        .ctx
+        .respond_to_interaction(
+            common::command(USER_ID, "adminbal", json!([])),
+            Responder::Recording(&recording),
+        )
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(
+        recording.embeds()[0].description.as_deref(),
+        Some("An error occurred while processing your request. Please try again later.")
+    );
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use altera_client::{
+    config::FakeProviderConfig,
+    ratelimit::{ActiveNumbers, BucketKey, Limit, LimitedAction, RateLimiter},
+};
+use common::{Harness, USER_ID};
+use serde_json::json;
+use std::time::{Duration, Instant};
+use twilight_model::id::Id;
+
+const USER: BucketKey = BucketKey::User(LimitedAction::Order, Id::new(1));
+const GUILD: BucketKey = BucketKey::Guild(LimitedAction::Order, Id::new(2));
+
+fn limit(amount: u32, secs: u64) -> Limit {
+    Limit {
+        amount,
+        period: Duration::from_secs(secs),
+    }
+}
+
+/// How many seconds until a token is available, rounded
+fn wait_secs(limiter: &RateLimiter, limits: &[(BucketKey, Limit)], now: Instant) -> Option<u64> {
+    match limiter.try_acquire_at(limits, now) {
+        Ok(()) => None,
+        Err((_, wait_time)) => Some(wait_time.as_secs_f64().round() as u64),
+    }
+}
+
+#[test]
+fn buckets_refill_gradually() {
+    let limiter = RateLimiter::default();
+    let limits = [(USER, limit(2, 60))];
+    let start = Instant::now();
+
+    assert!(limiter.try_acquire_at(&limits, start).is_ok());
+    assert!(limiter.try_acquire_at(&limits, start).is_ok());
+    // A token is back every 30 seconds
+    assert_eq!(wait_secs(&limiter, &limits, start), Some(30));
+    assert_eq!(
+        wait_secs(&limiter, &limits, start + Duration::from_secs(20)),
+        Some(10)
+    );
+    assert!(limiter
+        .try_acquire_at(&limits, start + Duration::from_secs(30))
+        .is_ok());
+
+    // Buckets don't fill up beyond the limit
+    let later = start + Duration::from_secs(60 * 60);
+    assert!(limiter.try_acquire_at(&limits, later).is_ok());
+    assert!(limiter.try_acquire_at(&limits, later).is_ok());
+    assert!(limiter.try_acquire_at(&limits, later).is_err());
+}
+
+#[test]
+fn every_bucket_needs_a_token() {
+    let limiter = RateLimiter::default();
+    let limits = [(USER, limit(5, 60)), (GUILD, limit(1, 60))];
+    let now = Instant::now();
+
+    assert!(limiter.try_acquire_at(&limits, now).is_ok());
+    assert_eq!(
+        limiter.try_acquire_at(&limits, now).map_err(|(key, _)| key),
+        Err(GUILD)
+    );
+    // The user's bucket wasn't taken from when the guild's was empty
+    for _ in 0..4 {
+        assert!(limiter.try_acquire_at(&[(USER, limit(5, 60))], now).is_ok());
+    }
+    assert!(limiter
+        .try_acquire_at(&[(USER, limit(5, 60))], now)
+        .is_err());
+
+    // A limit of 0 is no limit
+    let unlimited = [(USER, limit(0, 60))];
+    assert!(limiter.try_acquire_at(&unlimited, now).is_ok());
+}
+
+#[test]
+fn refunded_tokens_can_be_used_again() {
+    let limiter = RateLimiter::default();
+    let limits = [(USER, limit(1, 60 * 60))];
+
+    assert!(limiter.try_acquire(&limits).is_ok());
+    limiter.refund(&limits);
+    assert!(limiter.try_acquire(&limits).is_ok());
+    assert!(limiter.try_acquire(&limits).is_err());
+
+    // Refunds don't fill a bucket beyond the limit
+    limiter.refund(&limits);
+    limiter.refund(&limits);
+    assert!(limiter.try_acquire(&limits).is_ok());
+    assert!(limiter.try_acquire(&limits).is_err());
+}
+
+#[test]
+fn active_numbers_expire() {
+    let numbers = ActiveNumbers::default();
+    let user = Id::new(1);
+    let now = chrono::Utc::now().timestamp();
+
+    assert_eq!(numbers.active(user), (0, None));
+
+    numbers.insert(user, "a".to_string(), now + 600);
+    numbers.insert(user, "b".to_string(), now + 300);
+    numbers.insert(user, "c".to_string(), now - 1);
+    assert_eq!(numbers.active(user), (2, Some(now + 300)));
+    assert_eq!(numbers.active(Id::new(2)), (0, None));
+
+    numbers.remove(user, "b");
+    assert_eq!(numbers.active(user), (1, Some(now + 600)));
+    numbers.remove(user, "a");
+    assert_eq!(numbers.active(user), (0, None));
+}
+
+#[tokio::test]
+async fn failed_orders_dont_count() {
+    let harness = Harness::with_config(|config| {
+        config.limits.orders_per_hour = 1;
+        config.fake_provider = FakeProviderConfig {
+            out_of_stock: vec!["discord".to_string()],
+            ..FakeProviderConfig::default()
+        };
+    });
+    harness.register(500);
+    let getnumber = |service| json!([{ "name": "service", "type": 3, "value": service }]);
+
+    let embeds = harness
+        .command("getnumber", getnumber("Discord"))
+        .await
+        .embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Error"));
+
+    let embeds = harness
+        .command("getnumber", getnumber("Telegram"))
+        .await
+        .embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Success"));
+
+    let embeds = harness
+        .command("getnumber", getnumber("WhatsApp"))
+        .await
+        .embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("Slow Down"));
+}
+
+#[tokio::test]
+async fn orders_without_funds_are_refused_before_buying() {
+    let harness = Harness::with_config(|config| config.limits.orders_per_hour = 1);
+    harness.register(0);
+    let getnumber = json!([{ "name": "service", "type": 3, "value": "Discord" }]);
+
+    // Refused before a number is bought, so it doesn't use up the order
+    for _ in 0..2 {
+        let embeds = harness
+            .command("getnumber", getnumber.clone())
+            .await
+            .embeds();
+        assert!(embeds[0]
+            .description
+            .as_deref()
+            .unwrap()
+            .starts_with("You do not have enough funds"));
+    }
+    assert!(harness
+        .backend
+        .user(Id::new(USER_ID))
+        .unwrap()
+        .numbers
+        .is_empty());
+}
@@ -0,0 +1,232 @@
+mod common;
+
+use altera_client::{
+    config::FakeProviderConfig,
+    interaction::respond::Response,
+    preferences::{CodeDelivery, PriceSort},
+    templates::Templates,
+};
+use common::{Harness, USER_ID};
+use serde_json::{json, Value};
+use std::{env, path::PathBuf};
+use twilight_model::{
+    channel::message::Embed,
+    id::{marker::UserMarker, Id},
+};
+
+const USER: Id<UserMarker> = Id::new(USER_ID);
+
+fn field<'a>(embed: &'a Embed, name: &str) -> &'a str {
+    &embed
+        .fields
+        .iter()
+        .find(|field| field.name == name)
+        .unwrap_or_else(|| panic!("no field {}", name))
+        .value
+}
+
+/// The values of the text inputs of the settings modal `/settings` opens
+async fn modal_values(harness: &Harness) -> Vec<(String, Option<String>)> {
+    let recording = harness.command("settings", json!([])).await;
+    let Response::Modal {
+        custom_id,
+        text_inputs,
+        ..
+    } = &recording.responses()[0]
+    else {
+        panic!("expected a modal");
+    };
+    assert_eq!(custom_id, "settings");
+
+    text_inputs
+        .iter()
+        .map(|input| (input.custom_id.clone(), input.value.clone()))
+        .collect()
+}
+
+async fn save_settings(harness: &Harness, values: &[(&str, &str)]) -> Vec<Embed> {
+    harness
+        .run(common::modal(USER_ID, "settings", values))
+        .await
+        .embeds()
+}
+
+fn search_prices(sort_by: Option<&str>) -> Value {
+    let mut options = vec![json!({ "name": "service", "type": 3, "value": "Discord" })];
+    if let Some(sort_by) = sort_by {
+        options.push(json!({ "name": "sort_by", "type": 3, "value": sort_by }));
+    }
+    json!([{ "name": "prices", "type": 1, "options": options }])
+}
+
+#[tokio::test]
+async fn settings_modal() {
+    let harness = Harness::new();
+
+    assert_eq!(
+        modal_values(&harness).await,
+        vec![
+            ("country".to_string(), None),
+            ("sort_by".to_string(), Some("price".to_string())),
+            ("code_delivery".to_string(), Some("reply".to_string())),
+            ("currency".to_string(), None),
+            ("language".to_string(), None),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn settings_modal_in_german() {
+    let mut templates = Templates::default();
+    templates
+        .load_locales(&PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("locales"))
+        .unwrap();
+    let harness = Harness::with_config(|config| config.templates = templates);
+
+    let mut interaction = common::command(USER_ID, "settings", json!([]));
+    interaction.locale = Some("de".to_string());
+    let recording = harness.run(interaction).await;
+    let Response::Modal {
+        title, text_inputs, ..
+    } = &recording.responses()[0]
+    else {
+        panic!("expected a modal");
+    };
+
+    assert_eq!(title, "Einstellungen");
+    assert_eq!(text_inputs[0].label, "Standardland für /getnumber");
+    assert_eq!(
+        text_inputs[0].placeholder.as_deref(),
+        Some("Ein Land wie Deutschland, oder leer für jedes")
+    );
+    // Discord refuses to open the modal if any of them is longer
+    for label in [title]
+        .into_iter()
+        .chain(text_inputs.iter().map(|input| &input.label))
+    {
+        assert!(label.chars().count() <= 45, "`{}` is too long", label);
+    }
+}
+
+#[tokio::test]
+async fn save_settings_in_new_language() {
+    let mut templates = Templates::default();
+    templates
+        .load_locales(&PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("locales"))
+        .unwrap();
+    let harness = Harness::with_config(|config| config.templates = templates);
+    harness.register(1234);
+
+    let embeds = save_settings(
+        &harness,
+        &[
+            ("country", " Germany "),
+            ("sort_by", "Success Rate"),
+            ("code_delivery", "dm"),
+            ("currency", ""),
+            ("language", "DE"),
+        ],
+    )
+    .await;
+    assert_eq!(embeds[0].title.as_deref(), Some("Einstellungen"));
+    assert_eq!(field(&embeds[0], "Standardland:"), "Germany");
+    assert_eq!(field(&embeds[0], "Preise sortieren nach:"), "Erfolgsquote");
+    assert_eq!(field(&embeds[0], "Anzeigewährung:"), "USD");
+    assert_eq!(field(&embeds[0], "Sprache:"), "de");
+
+    let preferences = harness.ctx.preferences().get(USER);
+    assert_eq!(preferences.country.as_deref(), Some("Germany"));
+    assert_eq!(preferences.sort_by, PriceSort::SuccessRate);
+    assert_eq!(preferences.code_delivery, CodeDelivery::Dm);
+    assert_eq!(preferences.language.as_deref(), Some("de"));
+
+    // The language is used over the one of the user's client
+    let embeds = harness.command("balance", json!([])).await.embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("Dein Guthaben beträgt `12,34 USD`")
+    );
+
+    // The modal opens with the saved settings
+    let values = modal_values(&harness).await;
+    assert_eq!(values[0].1.as_deref(), Some("Germany"));
+    assert_eq!(values[1].1.as_deref(), Some("success rate"));
+    assert_eq!(values[2].1.as_deref(), Some("dm"));
+    assert_eq!(values[4].1.as_deref(), Some("de"));
+}
+
+#[tokio::test]
+async fn invalid_settings() {
+    let harness = Harness::new();
+    save_settings(&harness, &[("country", "Germany")]).await;
+
+    let embeds = save_settings(
+        &harness,
+        &[
+            ("country", "France"),
+            ("sort_by", "cheapest"),
+            ("code_delivery", "email"),
+            ("currency", "EUR"),
+            ("language", "german"),
+        ],
+    )
+    .await;
+    assert_eq!(embeds[0].title.as_deref(), Some("Error"));
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some(
+            "Your settings weren't saved:\n\
+             - `cheapest` isn't a way to sort prices, use `price` or `success rate`\n\
+             - `email` isn't a way to send codes, use `reply` or `dm`\n\
+             - `EUR` can't be shown, choose one of `USD`\n\
+             - `german` isn't a Discord language such as `de` or `pt-BR`"
+        )
+    );
+    assert_eq!(
+        harness.ctx.preferences().get(USER).country.as_deref(),
+        Some("Germany")
+    );
+}
+
+#[tokio::test]
+async fn settings_apply_to_commands() {
+    let harness = Harness::with_fake_provider(FakeProviderConfig {
+        sms_delay_secs: 0,
+        ..FakeProviderConfig::default()
+    });
+    harness.register(500);
+    save_settings(
+        &harness,
+        &[
+            ("country", "Canada"),
+            ("sort_by", "success rate"),
+            ("code_delivery", "dm"),
+        ],
+    )
+    .await;
+
+    // Without a country the default country is ordered from
+    let embeds = harness
+        .command(
+            "getnumber",
+            json!([{ "name": "service", "type": 3, "value": "Discord" }]),
+        )
+        .await
+        .embeds();
+    assert_eq!(field(&embeds[0], "Country:"), "Canada  :flag_ca:");
+
+    // Without a sort the preferred one is used
+    let preferred = harness.command("search", search_prices(None)).await;
+    let explicit = harness
+        .command("search", search_prices(Some("success_rate")))
+        .await;
+    assert_eq!(preferred.embeds(), explicit.embeds());
+    let by_price = harness
+        .command("search", search_prices(Some("price")))
+        .await;
+    assert_ne!(preferred.embeds(), by_price.embeds());
+
+    // The DM can't be sent without a gateway, so the code is replied instead
+    let embeds = harness.command("checksms", json!([])).await.embeds();
+    assert!(!field(&embeds[0], "SMS Code:").is_empty());
+}
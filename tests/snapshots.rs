@@ -0,0 +1,632 @@
+//! Every embed variant the user facing commands reply with, compared against
+//! the golden files in `tests/golden`
+
+mod common;
+
+use altera_client::{
+    api::Role,
+    config::{FakeOperation, FakeProviderConfig},
+};
+use common::{golden::assert_golden, Harness};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+fn getnumber_options(service: &str, country: Option<&str>) -> Value {
+    let mut options = vec![json!({ "name": "service", "type": 3, "value": service })];
+    if let Some(country) = country {
+        options.push(json!({ "name": "country", "type": 3, "value": country }));
+    }
+
+    Value::Array(options)
+}
+
+/// Order a number for the default user, to set up the command under test
+async fn getnumber(harness: &Harness, service: &str, country: Option<&str>) {
+    harness
+        .command("getnumber", getnumber_options(service, country))
+        .await;
+}
+
+fn search(subcommand: &str, options: Value) -> Value {
+    json!([{ "name": subcommand, "type": 1, "options": options }])
+}
+
+#[tokio::test]
+async fn balance() {
+    let harness = Harness::new();
+    harness.register(1234);
+
+    assert_golden("balance", &harness.command("balance", json!([])).await);
+}
+
+#[tokio::test]
+async fn balance_without_account() {
+    let harness = Harness::new();
+
+    assert_golden(
+        "balance_without_account",
+        &harness.command("balance", json!([])).await,
+    );
+}
+
+#[tokio::test]
+async fn getnumber_success() {
+    let harness = Harness::new();
+    harness.register(500);
+
+    let recording = harness
+        .command("getnumber", getnumber_options("Discord", Some("Germany")))
+        .await;
+    assert_golden("getnumber_success", &recording);
+}
+
+#[tokio::test]
+async fn getnumber_without_account() {
+    let harness = Harness::new();
+
+    let recording = harness
+        .command("getnumber", getnumber_options("Discord", None))
+        .await;
+    assert_golden("getnumber_without_account", &recording);
+}
+
+#[tokio::test]
+async fn getnumber_insufficient_funds() {
+    let harness = Harness::new();
+    harness.register(10);
+
+    let recording = harness
+        .command("getnumber", getnumber_options("Discord", None))
+        .await;
+    assert_golden("getnumber_insufficient_funds", &recording);
+}
+
+#[tokio::test]
+async fn getnumber_out_of_stock() {
+    let harness = Harness::with_fake_provider(FakeProviderConfig {
+        out_of_stock: vec!["discord".to_string()],
+        ..FakeProviderConfig::default()
+    });
+    harness.register(500);
+
+    let recording = harness
+        .command("getnumber", getnumber_options("Discord", None))
+        .await;
+    assert_golden("getnumber_out_of_stock", &recording);
+}
+
+#[tokio::test]
+async fn getnumber_similar_countries() {
+    let harness = Harness::new();
+    harness.register(500);
+
+    let recording = harness
+        .command("getnumber", getnumber_options("Discord", Some("Germani")))
+        .await;
+    assert_golden("getnumber_similar_countries", &recording);
+}
+
+#[tokio::test]
+async fn getnumber_no_similar_countries() {
+    let harness = Harness::new();
+    harness.register(500);
+
+    let recording = harness
+        .command("getnumber", getnumber_options("Discord", Some("Qqqqqqqq")))
+        .await;
+    assert_golden("getnumber_no_similar_countries", &recording);
+}
+
+#[tokio::test]
+async fn getnumber_similar_services() {
+    let harness = Harness::new();
+    harness.register(500);
+
+    let recording = harness
+        .command("getnumber", getnumber_options("Discorb", None))
+        .await;
+    assert_golden("getnumber_similar_services", &recording);
+}
+
+#[tokio::test]
+async fn getnumber_no_similar_services() {
+    let harness = Harness::new();
+    harness.register(500);
+
+    let recording = harness
+        .command("getnumber", getnumber_options("Qqqqqqqq", None))
+        .await;
+    assert_golden("getnumber_no_similar_services", &recording);
+}
+
+#[tokio::test]
+async fn getnumber_blocked_service() {
+    let harness = Harness::new();
+    harness.register(500);
+
+    let recording = harness
+        .command("getnumber", getnumber_options("Paypal", None))
+        .await;
+    assert_golden("getnumber_blocked_service", &recording);
+}
+
+#[tokio::test]
+async fn getnumber_too_many_active_numbers() {
+    let harness = Harness::new();
+    harness.register(500);
+    getnumber(&harness, "Discord", None).await;
+    getnumber(&harness, "Telegram", None).await;
+
+    let recording = harness
+        .command("getnumber", getnumber_options("WhatsApp", None))
+        .await;
+    assert_golden("getnumber_too_many_active_numbers", &recording);
+}
+
+#[tokio::test]
+async fn checksms_without_account() {
+    let harness = Harness::new();
+
+    assert_golden(
+        "checksms_without_account",
+        &harness.command("checksms", json!([])).await,
+    );
+}
+
+#[tokio::test]
+async fn checksms_without_number() {
+    let harness = Harness::new();
+    harness.register(500);
+
+    assert_golden(
+        "checksms_without_number",
+        &harness.command("checksms", json!([])).await,
+    );
+}
+
+#[tokio::test]
+async fn checksms_insufficient_balance() {
+    let harness = Harness::new();
+    let account_id = harness.register(500);
+    getnumber(&harness, "Discord", None).await;
+    harness
+        .api
+        .adjust_balance(account_id, -490, "test")
+        .await
+        .unwrap();
+
+    assert_golden(
+        "checksms_insufficient_balance",
+        &harness.command("checksms", json!([])).await,
+    );
+}
+
+#[tokio::test]
+async fn checksms_provider_error() {
+    let harness = Harness::with_fake_provider(FakeProviderConfig {
+        malformed: vec![FakeOperation::CheckSms],
+        ..FakeProviderConfig::default()
+    });
+    harness.register(500);
+    getnumber(&harness, "Discord", None).await;
+
+    assert_golden(
+        "checksms_provider_error",
+        &harness.command("checksms", json!([])).await,
+    );
+}
+
+#[tokio::test]
+async fn checksms_pending() {
+    let harness = Harness::new();
+    harness.register(500);
+    getnumber(&harness, "Discord", None).await;
+
+    assert_golden(
+        "checksms_pending",
+        &harness.command("checksms", json!([])).await,
+    );
+}
+
+#[tokio::test]
+async fn checksms_received() {
+    let harness = Harness::with_fake_provider(FakeProviderConfig {
+        sms_delay_secs: 0,
+        ..FakeProviderConfig::default()
+    });
+    harness.register(500);
+    getnumber(&harness, "Discord", None).await;
+
+    assert_golden(
+        "checksms_received",
+        &harness.command("checksms", json!([])).await,
+    );
+}
+
+#[tokio::test]
+async fn checksms_expired() {
+    let harness = Harness::with_fake_provider(FakeProviderConfig {
+        order_lifetime_secs: 1,
+        never_delivered: vec!["discord".to_string()],
+        ..FakeProviderConfig::default()
+    });
+    harness.register(500);
+    getnumber(&harness, "Discord", None).await;
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    assert_golden(
+        "checksms_expired",
+        &harness.command("checksms", json!([])).await,
+    );
+}
+
+#[tokio::test]
+async fn checksms_rate_limited() {
+    let harness = Harness::new();
+    harness.register(500);
+    for _ in 0..6 {
+        harness.command("checksms", json!([])).await;
+    }
+
+    assert_golden(
+        "checksms_rate_limited",
+        &harness.command("checksms", json!([])).await,
+    );
+}
+
+#[tokio::test]
+async fn search_services() {
+    let harness = Harness::new();
+
+    let recording = harness
+        .command(
+            "search",
+            search(
+                "services",
+                json!([{ "name": "service", "type": 3, "value": "gram" }]),
+            ),
+        )
+        .await;
+    assert_golden("search_services", &recording);
+}
+
+#[tokio::test]
+async fn search_services_none_similar() {
+    let harness = Harness::new();
+
+    let recording = harness
+        .command(
+            "search",
+            search(
+                "services",
+                json!([{ "name": "service", "type": 3, "value": "Qqqqqqqq" }]),
+            ),
+        )
+        .await;
+    assert_golden("search_services_none_similar", &recording);
+}
+
+#[tokio::test]
+async fn search_prices() {
+    let harness = Harness::new();
+
+    let recording = harness
+        .command(
+            "search",
+            search(
+                "prices",
+                json!([{ "name": "service", "type": 3, "value": "Discord" }]),
+            ),
+        )
+        .await;
+    assert_golden("search_prices", &recording);
+}
+
+#[tokio::test]
+async fn search_prices_by_success_rate() {
+    let harness = Harness::new();
+
+    let recording = harness
+        .command(
+            "search",
+            search(
+                "prices",
+                json!([
+                    { "name": "service", "type": 3, "value": "Discord" },
+                    { "name": "sort_by", "type": 3, "value": "success_rate" },
+                ]),
+            ),
+        )
+        .await;
+    assert_golden("search_prices_by_success_rate", &recording);
+}
+
+#[tokio::test]
+async fn search_prices_country() {
+    let harness = Harness::new();
+
+    let recording = harness
+        .command(
+            "search",
+            search(
+                "prices",
+                json!([
+                    { "name": "service", "type": 3, "value": "Discord" },
+                    { "name": "country", "type": 3, "value": "uk" },
+                ]),
+            ),
+        )
+        .await;
+    assert_golden("search_prices_country", &recording);
+}
+
+#[tokio::test]
+async fn search_prices_similar_countries() {
+    let harness = Harness::new();
+
+    let recording = harness
+        .command(
+            "search",
+            search(
+                "prices",
+                json!([
+                    { "name": "service", "type": 3, "value": "Discord" },
+                    { "name": "country", "type": 3, "value": "Germani" },
+                ]),
+            ),
+        )
+        .await;
+    assert_golden("search_prices_similar_countries", &recording);
+}
+
+#[tokio::test]
+async fn search_prices_similar_services() {
+    let harness = Harness::new();
+
+    let recording = harness
+        .command(
+            "search",
+            search(
+                "prices",
+                json!([{ "name": "service", "type": 3, "value": "Discorb" }]),
+            ),
+        )
+        .await;
+    assert_golden("search_prices_similar_services", &recording);
+}
+
+#[tokio::test]
+async fn search_prices_blocked_service() {
+    let harness = Harness::new();
+
+    let recording = harness
+        .command(
+            "search",
+            search(
+                "prices",
+                json!([{ "name": "service", "type": 3, "value": "Paypal" }]),
+            ),
+        )
+        .await;
+    assert_golden("search_prices_blocked_service", &recording);
+}
+
+#[tokio::test]
+async fn deposit() {
+    let harness = Harness::new();
+    harness.register(0);
+
+    let recording = harness
+        .command(
+            "deposit",
+            json!([{ "name": "amount", "type": 10, "value": 12.5 }]),
+        )
+        .await;
+    assert_golden("deposit", &recording);
+}
+
+#[tokio::test]
+async fn deposit_out_of_range() {
+    let harness = Harness::new();
+    harness.register(0);
+
+    let recording = harness
+        .command(
+            "deposit",
+            json!([{ "name": "amount", "type": 10, "value": 0.01 }]),
+        )
+        .await;
+    assert_golden("deposit_out_of_range", &recording);
+}
+
+#[tokio::test]
+async fn deposit_without_account() {
+    let harness = Harness::new();
+
+    let recording = harness
+        .command(
+            "deposit",
+            json!([{ "name": "amount", "type": 10, "value": 12.5 }]),
+        )
+        .await;
+    assert_golden("deposit_without_account", &recording);
+}
+
+#[tokio::test]
+async fn deposits() {
+    let harness = Harness::new();
+    let account_id = harness.register(0);
+    let invoice = harness.api.create_invoice(account_id, 12.5).await.unwrap();
+    harness.backend.complete_invoice(&invoice.id);
+    harness.api.create_invoice(account_id, 5.0).await.unwrap();
+
+    assert_golden("deposits", &harness.command("deposits", json!([])).await);
+}
+
+#[tokio::test]
+async fn deposits_none() {
+    let harness = Harness::new();
+    harness.register(0);
+
+    assert_golden(
+        "deposits_none",
+        &harness.command("deposits", json!([])).await,
+    );
+}
+
+#[tokio::test]
+async fn stats() {
+    let harness = Harness::with_fake_provider(FakeProviderConfig {
+        sms_delay_secs: 0,
+        ..FakeProviderConfig::default()
+    });
+    harness.register(500);
+    getnumber(&harness, "Discord", Some("Germany")).await;
+    harness.command("checksms", json!([])).await;
+
+    assert_golden("stats", &harness.command("stats", json!([])).await);
+}
+
+#[tokio::test]
+async fn stats_without_account() {
+    let harness = Harness::new();
+
+    assert_golden(
+        "stats_without_account",
+        &harness.command("stats", json!([])).await,
+    );
+}
+
+#[tokio::test]
+async fn receipt() {
+    let harness = Harness::with_fake_provider(FakeProviderConfig {
+        sms_delay_secs: 0,
+        ..FakeProviderConfig::default()
+    });
+    harness.register(500);
+    getnumber(&harness, "Discord", Some("Germany")).await;
+    harness.command("checksms", json!([])).await;
+
+    let recording = harness
+        .command(
+            "receipt",
+            json!([{ "name": "order", "type": 3, "value": "fake-1" }]),
+        )
+        .await;
+    assert_golden("receipt", &recording);
+}
+
+#[tokio::test]
+async fn receipt_not_received() {
+    let harness = Harness::new();
+    harness.register(500);
+    getnumber(&harness, "Discord", Some("Germany")).await;
+
+    let recording = harness
+        .command(
+            "receipt",
+            json!([{ "name": "order", "type": 3, "value": "fake-1" }]),
+        )
+        .await;
+    assert_golden("receipt_not_received", &recording);
+}
+
+#[tokio::test]
+async fn receipt_unknown_order() {
+    let harness = Harness::new();
+    harness.register(500);
+
+    let recording = harness
+        .command(
+            "receipt",
+            json!([{ "name": "order", "type": 3, "value": "fake-1" }]),
+        )
+        .await;
+    assert_golden("receipt_unknown_order", &recording);
+}
+
+#[tokio::test]
+async fn history() {
+    let harness = Harness::new();
+    harness.register(500);
+    getnumber(&harness, "Discord", Some("Germany")).await;
+    getnumber(&harness, "Telegram", None).await;
+
+    assert_golden("history", &harness.command("history", json!([])).await);
+}
+
+#[tokio::test]
+async fn history_empty() {
+    let harness = Harness::new();
+    harness.register(500);
+
+    assert_golden(
+        "history_empty",
+        &harness.command("history", json!([])).await,
+    );
+}
+
+#[tokio::test]
+async fn history_no_matches() {
+    let harness = Harness::new();
+    harness.register(500);
+    getnumber(&harness, "Discord", None).await;
+
+    let recording = harness
+        .command(
+            "history",
+            json!([{ "name": "status", "type": 3, "value": "received" }]),
+        )
+        .await;
+    assert_golden("history_no_matches", &recording);
+}
+
+#[tokio::test]
+async fn history_order_details() {
+    let harness = Harness::with_fake_provider(FakeProviderConfig {
+        sms_delay_secs: 0,
+        ..FakeProviderConfig::default()
+    });
+    harness.register(500);
+    getnumber(&harness, "Discord", Some("Germany")).await;
+    harness.command("checksms", json!([])).await;
+
+    let recording = harness
+        .run(common::component(
+            common::USER_ID,
+            &format!("history:detail:{}", common::USER_ID),
+            &["fake-1"],
+        ))
+        .await;
+    assert_golden("history_order_details", &recording);
+}
+
+#[tokio::test]
+async fn userdata() {
+    let harness = Harness::new();
+    harness.register_as(Role::Support, 1234);
+
+    assert_golden(
+        "userdata",
+        &harness.command("userdata", json!([])).await,
+    );
+}
+
+#[tokio::test]
+async fn adminbal() {
+    let harness = Harness::new();
+    harness.register_as(Role::Admin, 0);
+
+    assert_golden("adminbal", &harness.command("adminbal", json!([])).await);
+}
+
+#[tokio::test]
+async fn adminbal_without_permission() {
+    let harness = Harness::new();
+    harness.register(0);
+
+    assert_golden(
+        "adminbal_without_permission",
+        &harness.command("adminbal", json!([])).await,
+    );
+}
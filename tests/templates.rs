@@ -0,0 +1,158 @@
+mod common;
+
+use altera_client::{
+    config::{Config, ConfigError},
+    templates::{fill, Templates, Tone},
+};
+use common::{Harness, USER_ID};
+use serde_json::json;
+use std::{env, fs, path::PathBuf};
+
+/// Write the override file to a path no other test uses
+fn override_file(name: &str, contents: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!(
+        "altera-client-templates-{}-{}.toml",
+        std::process::id(),
+        name
+    ));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn config_with_templates(templates: &str) -> Result<Config, ConfigError> {
+    Config::from_toml(&format!(
+        r#"
+        [discord]
+        token = "test"
+        guilds = [2]
+        log_channel = 1
+
+        [api]
+        admin_token = "test"
+
+        [[providers]]
+        name = "fake"
+        base_url = "fake://"
+
+        [templates]
+        {}
+        "#,
+        templates
+    ))
+}
+
+#[test]
+fn fill_placeholders() {
+    assert_eq!(
+        fill(
+            "Hi **@{user}**, you have {count}",
+            &[("user", &"someone"), ("count", &3)]
+        ),
+        "Hi **@someone**, you have 3"
+    );
+    assert_eq!(
+        fill("`{{price}} | {{success_rate}}`", &[("price", &1)]),
+        "`{price} | {success_rate}`"
+    );
+    assert_eq!(fill("{missing} and } {", &[]), "{missing} and } {");
+}
+
+#[test]
+fn builtin_templates() {
+    let templates = Templates::default();
+    assert!(templates.names().contains(&"no_account"));
+
+    let no_account = templates.get(None, "no_account");
+    assert_eq!(no_account.tone, Tone::Error);
+    assert_eq!(no_account.title, "Error");
+    for name in templates.names() {
+        let template = templates.get(None, name);
+        assert!(!template.title.is_empty(), "{} has no title", name);
+        assert!(
+            !template.description.is_empty(),
+            "{} has no description",
+            name
+        );
+    }
+}
+
+#[test]
+fn overrides_are_checked() {
+    let file = override_file(
+        "invalid",
+        r#"
+        [no_acount]
+        title = "Oops"
+
+        [balance]
+        description = "You have {money}"
+        "#,
+    );
+
+    let Err(ConfigError::Invalid(errors)) =
+        config_with_templates(&format!("file = {:?}", file.display().to_string()))
+    else {
+        panic!("the override file should be invalid");
+    };
+    assert_eq!(
+        errors,
+        vec![
+            "templates.file: the description of `balance` uses `{money}`, which it doesn't have",
+            "templates.file: there is no template `no_acount`",
+        ]
+    );
+
+    let Err(ConfigError::Invalid(errors)) = config_with_templates("file = \"missing.toml\"") else {
+        panic!("a missing override file should be invalid");
+    };
+    assert!(errors[0].starts_with("templates.file `missing.toml` can't be read"));
+
+    let _ = fs::remove_file(file);
+}
+
+#[tokio::test]
+async fn overridden_template() {
+    let file = override_file(
+        "override",
+        r#"
+        [no_account]
+        title = "No Account"
+        description = "**@{user}**, sign up on the website first."
+        "#,
+    );
+    let harness = Harness::with_config(|config| {
+        config.templates = Templates::load(&file).unwrap();
+    });
+    let _ = fs::remove_file(file);
+
+    let embeds = harness.command("balance", json!([])).await.embeds();
+    assert_eq!(embeds[0].title.as_deref(), Some("No Account"));
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some(format!("**@user{}**, sign up on the website first.", USER_ID).as_str())
+    );
+
+    // Templates that aren't overridden keep the built-in text
+    harness.register(1234);
+    let embeds = harness.command("balance", json!([])).await.embeds();
+    assert_eq!(
+        embeds[0].description.as_deref(),
+        Some("Your balance is `$12.34 USD`")
+    );
+}
+
+#[tokio::test]
+async fn footer_and_timestamp() {
+    let harness = Harness::with_config(|config| {
+        config.templates.footer = Some("AlteraSMS".to_string());
+        config.templates.timestamp = true;
+    });
+    harness.register(1234);
+
+    let embeds = harness.command("balance", json!([])).await.embeds();
+    assert_eq!(embeds[0].footer.as_ref().unwrap().text, "AlteraSMS");
+    assert!(embeds[0].timestamp.is_some());
+
+    let config = config_with_templates("footer = \"  \"").unwrap();
+    assert!(config.templates.footer.is_none());
+}